@@ -33,7 +33,7 @@ CREATE INDEX IF NOT EXISTS idx_users_expires_at ON users(expires_at);
 -- ALTER TABLE users ADD CONSTRAINT users_user_id_key UNIQUE (user_id);
 
 -- 临时用户说明：
--- 临时用户ID从99999999999开始，通过user_id >= 99999999999判断是否为临时用户
+-- 临时用户ID从99999999999开始，是否为临时用户以is_temp字段为准（见16_users_is_temp.sql）
 
 -- 创建自动清理过期临时用户的函数和触发器
 CREATE OR REPLACE FUNCTION cleanup_expired_users()
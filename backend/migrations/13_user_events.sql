@@ -0,0 +1,21 @@
+-- user_events表 - 存储按用户投递的WebSocket事件，用于断线重连后补发
+-- 文件：13_user_events.sql
+
+-- 每个用户的事件序列号，单调递增
+CREATE TABLE IF NOT EXISTS user_event_seqs (
+    user_id BIGINT PRIMARY KEY REFERENCES users(user_id) ON DELETE CASCADE,
+    last_seq BIGINT NOT NULL DEFAULT 0
+);
+
+-- 用户事件日志，payload为完整的WebSocket消息
+CREATE TABLE IF NOT EXISTS user_events (
+    user_id BIGINT NOT NULL REFERENCES users(user_id) ON DELETE CASCADE,
+    seq BIGINT NOT NULL,
+    event_type VARCHAR(32) NOT NULL,
+    payload JSONB NOT NULL,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    PRIMARY KEY (user_id, seq)
+);
+
+-- 创建索引以加速过期事件清理
+CREATE INDEX IF NOT EXISTS idx_user_events_created_at ON user_events(created_at);
@@ -0,0 +1,31 @@
+-- refresh_tokens表 - 存储刷新令牌及其轮换家族
+-- 文件：14_refresh_tokens.sql
+
+-- 刷新令牌家族：一次登录产生一个家族，之后每次刷新都在同一家族内轮换
+CREATE TABLE IF NOT EXISTS refresh_token_families (
+    family_id UUID PRIMARY KEY,
+    user_id BIGINT NOT NULL REFERENCES users(user_id) ON DELETE CASCADE,
+    is_temp BOOLEAN NOT NULL DEFAULT FALSE,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    revoked_at TIMESTAMPTZ,
+    revoked_reason VARCHAR(20) CHECK (revoked_reason IN ('reuse', 'logout', 'logout_all'))
+);
+
+-- 刷新令牌：token_id与JWT中的jti对应，used_at非空表示已被轮换
+CREATE TABLE IF NOT EXISTS refresh_tokens (
+    token_id UUID PRIMARY KEY,
+    family_id UUID NOT NULL REFERENCES refresh_token_families(family_id) ON DELETE CASCADE,
+    user_id BIGINT NOT NULL REFERENCES users(user_id) ON DELETE CASCADE,
+    issued_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    expires_at TIMESTAMPTZ NOT NULL,
+    used_at TIMESTAMPTZ,
+    replaced_by UUID
+);
+
+-- 创建索引以加速查询
+CREATE INDEX IF NOT EXISTS idx_refresh_token_families_user_id ON refresh_token_families(user_id);
+CREATE INDEX IF NOT EXISTS idx_refresh_tokens_family_id ON refresh_tokens(family_id);
+CREATE INDEX IF NOT EXISTS idx_refresh_tokens_expires_at ON refresh_tokens(expires_at);
+
+-- users表：退出所有设备的时间，早于该时间签发的访问令牌失效
+ALTER TABLE users ADD COLUMN IF NOT EXISTS tokens_revoked_at TIMESTAMPTZ;
@@ -0,0 +1,13 @@
+-- revoked_tokens表 - 访问令牌黑名单（未配置Redis时使用）
+-- 文件：15_revoked_tokens.sql
+
+-- 已注销的访问令牌，jti对应JWT中的令牌ID，过期后可以清理
+CREATE TABLE IF NOT EXISTS revoked_tokens (
+    jti VARCHAR(64) PRIMARY KEY,
+    user_id BIGINT NOT NULL REFERENCES users(user_id) ON DELETE CASCADE,
+    expires_at TIMESTAMPTZ NOT NULL,
+    revoked_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+-- 创建索引以加速过期记录清理
+CREATE INDEX IF NOT EXISTS idx_revoked_tokens_expires_at ON revoked_tokens(expires_at);
@@ -0,0 +1,26 @@
+-- users表 - 临时用户标志
+-- 文件：16_users_is_temp.sql
+
+-- 临时用户改为使用独立的标志位判断，认领账号后只需清除标志，用户ID及其内容保持不变
+ALTER TABLE users ADD COLUMN IF NOT EXISTS is_temp BOOLEAN NOT NULL DEFAULT FALSE;
+
+-- 已有的临时用户（ID从99999999999开始）
+UPDATE users SET is_temp = TRUE WHERE user_id >= 99999999999;
+
+CREATE INDEX IF NOT EXISTS idx_users_is_temp ON users(is_temp) WHERE is_temp;
+
+-- 认领账号时吊销临时用户的所有刷新令牌家族
+ALTER TABLE refresh_token_families
+    DROP CONSTRAINT IF EXISTS refresh_token_families_revoked_reason_check;
+ALTER TABLE refresh_token_families
+    ADD CONSTRAINT refresh_token_families_revoked_reason_check
+    CHECK (revoked_reason IN ('reuse', 'logout', 'logout_all', 'claim'));
+
+-- 清理过期临时用户时改为按标志位判断，已认领的账号不会被删除
+CREATE OR REPLACE FUNCTION cleanup_expired_users()
+RETURNS TRIGGER AS $$
+BEGIN
+    DELETE FROM users WHERE is_temp AND expires_at < NOW();
+    RETURN NULL;
+END;
+$$ LANGUAGE plpgsql;
@@ -0,0 +1,9 @@
+-- users表 - 用户资料字段
+-- 文件：17_user_profiles.sql
+
+-- 头像、个人简介、性别和生日，均为可选
+ALTER TABLE users ADD COLUMN IF NOT EXISTS avatar_url VARCHAR(500);
+ALTER TABLE users ADD COLUMN IF NOT EXISTS bio VARCHAR(500);
+ALTER TABLE users ADD COLUMN IF NOT EXISTS gender VARCHAR(10)
+    CHECK (gender IN ('male', 'female', 'other'));
+ALTER TABLE users ADD COLUMN IF NOT EXISTS birthday DATE;
@@ -0,0 +1,8 @@
+-- users表 - 账号注销
+-- 文件：18_account_deletion.sql
+
+-- 账号计划删除时间，非空表示用户已申请注销，宽限期结束后由后台任务删除
+ALTER TABLE users ADD COLUMN IF NOT EXISTS deletion_scheduled_at TIMESTAMPTZ;
+
+CREATE INDEX IF NOT EXISTS idx_users_deletion_scheduled_at ON users(deletion_scheduled_at)
+    WHERE deletion_scheduled_at IS NOT NULL;
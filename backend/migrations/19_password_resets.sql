@@ -0,0 +1,27 @@
+-- password_reset_codes表 - 密码修改与重置
+-- 文件：19_password_resets.sql
+
+-- 密码最后修改时间，早于该时间签发的访问令牌失效
+ALTER TABLE users ADD COLUMN IF NOT EXISTS password_changed_at TIMESTAMPTZ;
+
+-- 修改或重置密码时吊销所有刷新令牌家族
+ALTER TABLE refresh_token_families
+    DROP CONSTRAINT IF EXISTS refresh_token_families_revoked_reason_check;
+ALTER TABLE refresh_token_families
+    ADD CONSTRAINT refresh_token_families_revoked_reason_check
+    CHECK (revoked_reason IN ('reuse', 'logout', 'logout_all', 'claim', 'password_change', 'password_reset'));
+
+-- 密码重置验证码：只保存验证码的SHA-256哈希，使用一次后作废
+CREATE TABLE IF NOT EXISTS password_reset_codes (
+    id UUID PRIMARY KEY,
+    user_id BIGINT NOT NULL REFERENCES users(user_id) ON DELETE CASCADE,
+    code_hash VARCHAR(64) NOT NULL,
+    attempts INT NOT NULL DEFAULT 0,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    expires_at TIMESTAMPTZ NOT NULL,
+    used_at TIMESTAMPTZ
+);
+
+-- 创建索引以加速查询
+CREATE INDEX IF NOT EXISTS idx_password_reset_codes_user_id ON password_reset_codes(user_id);
+CREATE INDEX IF NOT EXISTS idx_password_reset_codes_expires_at ON password_reset_codes(expires_at);
@@ -0,0 +1,14 @@
+-- user_blocks表 - 用户屏蔽关系
+-- 文件：20_user_blocks.sql
+
+-- 屏蔽是单向记录，但双方都互相不可见、不能发起私信
+CREATE TABLE IF NOT EXISTS user_blocks (
+    blocker_id BIGINT NOT NULL REFERENCES users(user_id) ON DELETE CASCADE,
+    blocked_id BIGINT NOT NULL REFERENCES users(user_id) ON DELETE CASCADE,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    PRIMARY KEY (blocker_id, blocked_id),
+    CHECK (blocker_id <> blocked_id)
+);
+
+-- 创建索引以加速反向查询（谁屏蔽了我）
+CREATE INDEX IF NOT EXISTS idx_user_blocks_blocked_id ON user_blocks(blocked_id);
@@ -0,0 +1,37 @@
+-- friend_requests/friendships表 - 好友申请与好友关系
+-- 文件：21_friendships.sql
+
+-- 好友申请
+CREATE TABLE IF NOT EXISTS friend_requests (
+    id BIGSERIAL PRIMARY KEY,
+    request_id UUID NOT NULL UNIQUE,
+    requester_id BIGINT NOT NULL REFERENCES users(user_id) ON DELETE CASCADE,
+    addressee_id BIGINT NOT NULL REFERENCES users(user_id) ON DELETE CASCADE,
+    status VARCHAR(20) NOT NULL DEFAULT 'pending'
+        CHECK (status IN ('pending', 'accepted', 'declined')),
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    responded_at TIMESTAMPTZ,
+    CHECK (requester_id <> addressee_id)
+);
+
+-- 同一对用户之间同时只能有一个待处理的申请
+CREATE UNIQUE INDEX IF NOT EXISTS idx_friend_requests_pending_pair ON friend_requests(
+    LEAST(requester_id, addressee_id), GREATEST(requester_id, addressee_id)
+) WHERE status = 'pending';
+
+-- 创建索引以加速查询
+CREATE INDEX IF NOT EXISTS idx_friend_requests_addressee ON friend_requests(addressee_id, status);
+CREATE INDEX IF NOT EXISTS idx_friend_requests_requester ON friend_requests(requester_id, status);
+
+-- 好友关系：每对好友双向各存一行，便于按用户查询
+CREATE TABLE IF NOT EXISTS friendships (
+    id BIGSERIAL PRIMARY KEY,
+    user_id BIGINT NOT NULL REFERENCES users(user_id) ON DELETE CASCADE,
+    friend_id BIGINT NOT NULL REFERENCES users(user_id) ON DELETE CASCADE,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    UNIQUE (user_id, friend_id),
+    CHECK (user_id <> friend_id)
+);
+
+-- 创建索引以加速查询
+CREATE INDEX IF NOT EXISTS idx_friendships_friend_id ON friendships(friend_id);
@@ -0,0 +1,34 @@
+-- admin_audit_logs表 - 平台管理员角色、封禁与审计日志
+-- 文件：22_admin.sql
+
+-- 平台级角色，与群组内的角色无关；首个管理员需直接在数据库中设置
+ALTER TABLE users ADD COLUMN IF NOT EXISTS platform_role VARCHAR(20) NOT NULL DEFAULT 'user'
+    CHECK (platform_role IN ('user', 'moderator', 'admin'));
+
+-- 封禁状态，封禁期间不能登录，已签发的令牌失效
+ALTER TABLE users ADD COLUMN IF NOT EXISTS banned_at TIMESTAMPTZ;
+ALTER TABLE users ADD COLUMN IF NOT EXISTS ban_reason TEXT;
+
+-- 封禁用户时吊销其所有刷新令牌家族
+ALTER TABLE refresh_token_families
+    DROP CONSTRAINT IF EXISTS refresh_token_families_revoked_reason_check;
+ALTER TABLE refresh_token_families
+    ADD CONSTRAINT refresh_token_families_revoked_reason_check
+    CHECK (revoked_reason IN ('reuse', 'logout', 'logout_all', 'claim', 'password_change', 'password_reset', 'ban'));
+
+-- 管理操作审计日志，管理员账号删除后保留记录
+CREATE TABLE IF NOT EXISTS admin_audit_logs (
+    id BIGSERIAL PRIMARY KEY,
+    admin_id BIGINT REFERENCES users(user_id) ON DELETE SET NULL,
+    action VARCHAR(50) NOT NULL,
+    target_type VARCHAR(20) NOT NULL,
+    target_id TEXT NOT NULL,
+    reason TEXT,
+    detail JSONB,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+-- 创建索引以加速查询
+CREATE INDEX IF NOT EXISTS idx_admin_audit_logs_admin_id ON admin_audit_logs(admin_id);
+CREATE INDEX IF NOT EXISTS idx_admin_audit_logs_target ON admin_audit_logs(target_type, target_id);
+CREATE INDEX IF NOT EXISTS idx_users_platform_role ON users(platform_role) WHERE platform_role <> 'user';
@@ -0,0 +1,19 @@
+-- users表 - 位置隐私级别
+-- 文件：23_location_privacy.sql
+
+-- 其他用户看到的位置精度：exact精确位置，neighbourhood街区（约1公里网格），city城市（约10公里网格），hidden不公开
+ALTER TABLE users ADD COLUMN IF NOT EXISTS location_privacy VARCHAR(20) NOT NULL DEFAULT 'exact'
+    CHECK (location_privacy IN ('exact', 'neighbourhood', 'city', 'hidden'));
+
+-- 按隐私级别模糊单个坐标：对齐到网格中心，hidden返回NULL；0表示未提供位置，保持不变
+-- 距离和范围筛选都应基于模糊后的坐标计算，避免通过多次查询反推出精确位置
+CREATE OR REPLACE FUNCTION fuzz_coordinate(coordinate DOUBLE PRECISION, privacy VARCHAR)
+RETURNS DOUBLE PRECISION AS $$
+    SELECT CASE
+        WHEN coordinate = 0 THEN coordinate
+        WHEN privacy = 'neighbourhood' THEN (floor(coordinate * 100) + 0.5) / 100
+        WHEN privacy = 'city' THEN (floor(coordinate * 10) + 0.5) / 10
+        WHEN privacy = 'hidden' THEN NULL
+        ELSE coordinate
+    END
+$$ LANGUAGE SQL IMMUTABLE;
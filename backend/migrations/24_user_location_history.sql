@@ -0,0 +1,34 @@
+-- user_location_history表 - 用户位置轨迹
+-- 文件：24_user_location_history.sql
+
+-- user_locations每个用户只保留一行当前位置，更新位置时依赖user_id唯一约束覆盖旧记录
+DELETE FROM user_locations ul
+USING user_locations newer
+WHERE ul.user_id = newer.user_id
+  AND (ul.updated_at, ul.id) < (newer.updated_at, newer.id);
+CREATE UNIQUE INDEX IF NOT EXISTS idx_user_locations_user_id_unique ON user_locations(user_id);
+
+-- 只追加的位置轨迹，用于热点分析和轨迹研究；超过保留期的记录定期清理
+CREATE TABLE IF NOT EXISTS user_location_history (
+    id BIGSERIAL PRIMARY KEY,
+    user_id BIGINT NOT NULL REFERENCES users(user_id) ON DELETE CASCADE,
+    latitude DOUBLE PRECISION NOT NULL,
+    longitude DOUBLE PRECISION NOT NULL,
+    accuracy DOUBLE PRECISION,       -- 定位精度（米）
+    speed DOUBLE PRECISION,          -- 速度（米/秒）
+    heading DOUBLE PRECISION,        -- 方向（度，正北为0，顺时针）
+    location_name TEXT,
+    recorded_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    geom GEOGRAPHY(POINT, 4326)
+);
+
+-- 复用user_locations的触发器函数自动更新地理点字段
+DROP TRIGGER IF EXISTS update_user_location_history_geom ON user_location_history;
+CREATE TRIGGER update_user_location_history_geom
+BEFORE INSERT OR UPDATE OF latitude, longitude ON user_location_history
+FOR EACH ROW EXECUTE FUNCTION update_geom_column();
+
+-- 创建索引以加速查询
+CREATE INDEX IF NOT EXISTS idx_user_location_history_user_recorded ON user_location_history(user_id, recorded_at DESC);
+CREATE INDEX IF NOT EXISTS idx_user_location_history_recorded_at ON user_location_history(recorded_at);
+CREATE INDEX IF NOT EXISTS idx_user_location_history_geom ON user_location_history USING gist (geom);
@@ -0,0 +1,35 @@
+-- users表 - 位置可见范围（隐身模式）
+-- 文件：25_location_visibility.sql
+
+-- 谁能在附近的人中看到该用户及其在线状态：everyone所有人，friends_and_groups好友和同群成员，invisible隐身
+ALTER TABLE users ADD COLUMN IF NOT EXISTS location_visibility VARCHAR(20) NOT NULL DEFAULT 'everyone'
+    CHECK (location_visibility IN ('everyone', 'friends_and_groups', 'invisible'));
+-- 设置的到期时间，到期后恢复为everyone；为空表示一直有效
+ALTER TABLE users ADD COLUMN IF NOT EXISTS location_visibility_expires_at TIMESTAMPTZ;
+
+-- 定时任务按到期时间查找需要恢复为所有人可见的用户，只有设置了到期时间的用户需要索引
+CREATE INDEX IF NOT EXISTS idx_users_location_visibility_expires_at
+    ON users (location_visibility_expires_at)
+    WHERE location_visibility_expires_at IS NOT NULL;
+
+-- 判断target_id的位置和在线状态是否对viewer_id可见，用户本人始终可见
+CREATE OR REPLACE FUNCTION location_visible_to(target_id BIGINT, viewer_id BIGINT)
+RETURNS BOOLEAN AS $$
+    SELECT CASE
+        WHEN target_id = viewer_id THEN TRUE
+        WHEN u.location_visibility_expires_at <= NOW() THEN TRUE
+        WHEN u.location_visibility = 'invisible' THEN FALSE
+        WHEN u.location_visibility = 'friends_and_groups' THEN
+            EXISTS (
+                SELECT 1 FROM friendships f
+                WHERE f.user_id = target_id AND f.friend_id = viewer_id
+            ) OR EXISTS (
+                SELECT 1 FROM group_members gm1
+                JOIN group_members gm2 ON gm1.group_id = gm2.group_id
+                WHERE gm1.user_id = target_id AND gm2.user_id = viewer_id
+            )
+        ELSE TRUE
+    END
+    FROM users u
+    WHERE u.user_id = target_id
+$$ LANGUAGE SQL STABLE;
@@ -0,0 +1,23 @@
+-- gazetteer_places表 - 离线逆地理编码地名库
+-- 文件：26_gazetteer_places.sql
+
+-- 行政区划中心点，由GeoNames或行政区划数据整理后导入；逆地理编码时取距离最近的地点
+CREATE TABLE IF NOT EXISTS gazetteer_places (
+    id BIGSERIAL PRIMARY KEY,
+    country VARCHAR(100) NOT NULL,
+    province VARCHAR(100),
+    city VARCHAR(100),
+    district VARCHAR(100),
+    latitude DOUBLE PRECISION NOT NULL,
+    longitude DOUBLE PRECISION NOT NULL,
+    geom GEOGRAPHY(POINT, 4326)
+);
+
+-- 复用user_locations的触发器函数自动更新地理点字段
+DROP TRIGGER IF EXISTS update_gazetteer_places_geom ON gazetteer_places;
+CREATE TRIGGER update_gazetteer_places_geom
+BEFORE INSERT OR UPDATE OF latitude, longitude ON gazetteer_places
+FOR EACH ROW EXECUTE FUNCTION update_geom_column();
+
+-- 创建索引以加速最近地点查询
+CREATE INDEX IF NOT EXISTS idx_gazetteer_places_geom ON gazetteer_places USING gist (geom);
@@ -0,0 +1,18 @@
+-- hotspot_rollups表 - 热力图预聚合
+-- 文件：27_hotspot_rollups.sql
+
+-- 按小时和6位geohash单元（约1.2公里×0.6公里）统计签到、群组消息和活跃用户数，由定时任务刷新；
+-- 查询更大范围时按geohash前缀合并为更粗的单元
+CREATE TABLE IF NOT EXISTS hotspot_rollups (
+    bucket_start TIMESTAMPTZ NOT NULL,          -- 统计小时的开始时间
+    geohash VARCHAR(12) NOT NULL,
+    latitude DOUBLE PRECISION NOT NULL,         -- 单元内事件的平均坐标，用于按范围筛选
+    longitude DOUBLE PRECISION NOT NULL,
+    checkin_count INTEGER NOT NULL DEFAULT 0,
+    message_count INTEGER NOT NULL DEFAULT 0,
+    active_user_count INTEGER NOT NULL DEFAULT 0,
+    PRIMARY KEY (bucket_start, geohash)
+);
+
+-- 创建索引以加速按范围查询
+CREATE INDEX IF NOT EXISTS idx_hotspot_rollups_lat_lon ON hotspot_rollups(latitude, longitude);
@@ -0,0 +1,13 @@
+-- 地图可视范围查询的空间索引
+-- 文件：28_viewport_indexes.sql
+
+-- 可视范围按经纬度矩形筛选（ST_MakeEnvelope），使用geometry表达式索引，
+-- 避免geography按大圆弧计算矩形边界导致范围偏差
+CREATE INDEX IF NOT EXISTS idx_user_locations_geom_geometry
+ON user_locations USING gist ((geom::geometry));
+
+CREATE INDEX IF NOT EXISTS idx_groups_geom_geometry
+ON groups USING gist ((geom::geometry));
+
+CREATE INDEX IF NOT EXISTS idx_checkins_geom_geometry
+ON checkins USING gist ((geom::geometry));
@@ -1,26 +1,244 @@
+use crate::utils::jwt::JwtKeys;
+use std::path::PathBuf;
+use std::sync::Arc;
+
+// WebSocket事件总线的实现方式
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EventBusKind {
+    // 进程内分发，适用于单节点部署
+    InProcess,
+    // 通过Redis pub/sub在多个节点之间分发
+    Redis,
+}
+
+impl EventBusKind {
+    // 从配置值解析，未知值时使用进程内实现
+    pub fn from_config(value: &str) -> Self {
+        match value.to_ascii_lowercase().as_str() {
+            "redis" => EventBusKind::Redis,
+            _ => EventBusKind::InProcess,
+        }
+    }
+}
+
+// 用户通知（验证码等）的发送渠道
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum NotifierConfig {
+    // 写入日志
+    Log,
+    // 按行追加到文件
+    File { path: PathBuf },
+}
+
+impl NotifierConfig {
+    // 从配置值解析，未知值时写入日志
+    pub fn from_config(value: &str, file_path: Option<String>) -> Self {
+        match value.to_ascii_lowercase().as_str() {
+            "file" => NotifierConfig::File {
+                path: PathBuf::from(file_path.unwrap_or_else(|| "notifications.log".to_string())),
+            },
+            _ => NotifierConfig::Log,
+        }
+    }
+}
+
+// Argon2id密码哈希的工作因子
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PasswordHashConfig {
+    // 内存开销（KiB）
+    pub memory_kib: u32,
+    // 迭代次数
+    pub iterations: u32,
+    // 并行度
+    pub parallelism: u32,
+}
+
+impl Default for PasswordHashConfig {
+    // 与argon2库的默认参数一致（OWASP推荐的最低配置）
+    fn default() -> Self {
+        Self {
+            memory_kib: 19 * 1024,
+            iterations: 2,
+            parallelism: 1,
+        }
+    }
+}
+
+// 登录和账号创建的防暴力破解参数
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct LoginGuardConfig {
+    // 登录失败次数的统计窗口（秒）
+    pub failure_window_secs: u64,
+    // 同一登录ID在窗口内失败多少次后锁定
+    pub max_failures_per_login_id: u32,
+    // 同一IP在窗口内失败多少次后锁定
+    pub max_failures_per_ip: u32,
+    // 首次锁定时长（秒），之后每次锁定时长翻倍
+    pub lockout_secs: u64,
+    // 锁定时长上限（秒）
+    pub max_lockout_secs: u64,
+    // 同一IP在窗口内最多创建的账号数（包括注册和临时用户）
+    pub max_signups_per_ip: u32,
+    // 账号创建次数的统计窗口（秒）
+    pub signup_window_secs: u64,
+}
+
+impl Default for LoginGuardConfig {
+    fn default() -> Self {
+        Self {
+            failure_window_secs: 15 * 60,
+            max_failures_per_login_id: 5,
+            max_failures_per_ip: 20,
+            lockout_secs: 60,
+            max_lockout_secs: 60 * 60,
+            max_signups_per_ip: 10,
+            signup_window_secs: 60 * 60,
+        }
+    }
+}
+
+// 位置轨迹的去重和保留参数
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct LocationHistoryConfig {
+    // 轨迹保留天数，0表示永久保留
+    pub retention_days: u32,
+    // 与上一个轨迹点的距离小于该值（米）时视为同一位置
+    pub dedup_distance_meters: f64,
+    // 同一位置在该时间（秒）内不重复记录，超过后仍记录一次用于统计停留时长
+    pub dedup_interval_secs: u64,
+}
+
+impl Default for LocationHistoryConfig {
+    fn default() -> Self {
+        Self {
+            retention_days: 90,
+            dedup_distance_meters: 10.0,
+            dedup_interval_secs: 5 * 60,
+        }
+    }
+}
+
+// 热力图预聚合参数
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct HotspotConfig {
+    // 刷新预聚合数据的间隔（秒）
+    pub rollup_interval_secs: u64,
+    // 预聚合数据保留天数，也是热力图可查询的最长时间范围
+    pub retention_days: u32,
+}
+
+impl Default for HotspotConfig {
+    fn default() -> Self {
+        Self {
+            rollup_interval_secs: 5 * 60,
+            retention_days: 30,
+        }
+    }
+}
+
+// 地图标记聚合参数
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ClusterConfig {
+    // 每个瓦片聚合结果的缓存时间（秒）
+    pub cache_ttl_secs: u64,
+    // 网格单元内的点数达到该值时返回聚合，否则返回单个点
+    pub point_threshold: i64,
+}
+
+impl Default for ClusterConfig {
+    fn default() -> Self {
+        Self {
+            cache_ttl_secs: 60,
+            point_threshold: 5,
+        }
+    }
+}
+
+// 离线逆地理编码参数
+#[derive(Debug, Clone, PartialEq)]
+pub struct GeocodeConfig {
+    // 地名库文件路径，地名库为空时启动导入；未设置时不导入
+    pub gazetteer_file: Option<String>,
+    // 最近地点超过该距离（米）时视为无法解析
+    pub max_distance_meters: f64,
+}
+
+impl Default for GeocodeConfig {
+    fn default() -> Self {
+        Self {
+            gazetteer_file: None,
+            max_distance_meters: 50_000.0,
+        }
+    }
+}
+
 #[derive(Debug, Clone)]
 pub struct AppConfig {
-    pub jwt_secret: String,
-    pub jwt_expires_in: String,
+    // JWT签名和验证密钥
+    pub jwt_keys: Arc<JwtKeys>,
+    // 访问令牌有效期（秒）
+    pub jwt_expires_in: i64,
     pub database_url: String,
     pub redis_url: Option<String>,
     pub server_port: u16,
+    pub event_bus: EventBusKind,
+    pub password_hash: PasswordHashConfig,
+    pub login_guard: LoginGuardConfig,
+    // 是否信任反向代理写入的X-Forwarded-For头来确定客户端IP
+    pub trust_forwarded_for: bool,
+    pub notifier: NotifierConfig,
+    pub location_history: LocationHistoryConfig,
+    pub geocode: GeocodeConfig,
+    pub hotspot: HotspotConfig,
+    pub cluster: ClusterConfig,
 }
 
 impl AppConfig {
     pub fn new(
-        jwt_secret: String,
-        jwt_expires_in: String,
+        jwt_keys: Arc<JwtKeys>,
+        jwt_expires_in: i64,
         database_url: String,
         redis_url: Option<String>,
         server_port: u16,
+        event_bus: EventBusKind,
+        password_hash: PasswordHashConfig,
     ) -> Self {
         Self {
-            jwt_secret,
+            jwt_keys,
             jwt_expires_in,
             database_url,
             redis_url,
             server_port,
+            event_bus,
+            password_hash,
+            login_guard: LoginGuardConfig::default(),
+            trust_forwarded_for: false,
+            notifier: NotifierConfig::Log,
+            location_history: LocationHistoryConfig::default(),
+            geocode: GeocodeConfig::default(),
+            hotspot: HotspotConfig::default(),
+            cluster: ClusterConfig::default(),
         }
     }
-} 
\ No newline at end of file
+} 
+
+// 解析时长配置为秒数，支持纯数字（秒）和s/m/h/d后缀，如"3600"、"60m"、"7d"
+pub fn parse_duration_secs(value: &str) -> Option<i64> {
+    let value = value.trim();
+    let (number, unit) = match value.find(|c: char| !c.is_ascii_digit()) {
+        Some(index) => value.split_at(index),
+        None => (value, "s"),
+    };
+    let multiplier = match unit {
+        "s" => 1,
+        "m" => 60,
+        "h" => 60 * 60,
+        "d" => 24 * 60 * 60,
+        _ => return None,
+    };
+    number
+        .parse::<i64>()
+        .ok()
+        .and_then(|n| n.checked_mul(multiplier))
+        .filter(|&secs| secs > 0)
+}
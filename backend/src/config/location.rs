@@ -7,4 +7,7 @@ pub const DEFAULT_LATITUDE: f64 = 35.16;
 pub const DEFAULT_LONGITUDE: f64 = 112.68;
 
 /// 默认位置名称
-pub const DEFAULT_LOCATION_NAME: &str = "南天门"; 
\ No newline at end of file
+pub const DEFAULT_LOCATION_NAME: &str = "南天门"; 
+
+/// 新签到通知的推送半径（米）
+pub const CHECKIN_NOTIFICATION_RADIUS: f64 = 5000.0;
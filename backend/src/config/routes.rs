@@ -5,6 +5,98 @@ pub fn get_public_routes() -> Vec<&'static str> {
         "/api/v1/user/login",
         "/api/v1/user/create_temp_user",
         "/api/v1/user/refresh-token",
+        "/api/v1/user/password_reset/",
         "/ws/",
+        "/.well-known/",
     ]
-} 
\ No newline at end of file
+}
+
+/// 令牌桶限流策略
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct RateLimitPolicy {
+    /// 策略名称，同名策略共用一个令牌桶
+    pub name: &'static str,
+    /// 桶容量，即允许的突发请求数
+    pub burst: u32,
+    /// 每分钟补充的令牌数，即持续请求速率
+    pub per_minute: u32,
+}
+
+/// 定义各路由分组的限流策略（按路径前缀匹配，取最长的前缀）
+/// 已认证请求按用户限流，公开路由按客户端IP限流
+pub fn get_rate_limit_policies() -> Vec<(&'static str, RateLimitPolicy)> {
+    vec![
+        ("/api/v1/user", policy("user", 30, 120)),
+        (
+            "/api/v1/user/search_by_location",
+            policy("user_nearby", 5, 20),
+        ),
+        ("/api/v1/group", policy("group", 30, 120)),
+        (
+            "/api/v1/group/search_by_location",
+            policy("group_nearby", 5, 20),
+        ),
+        ("/api/v1/chat", policy("chat", 60, 300)),
+        ("/api/v1/chat/group/send", policy("chat_send", 20, 60)),
+        ("/api/v1/chat/user/send", policy("chat_send", 20, 60)),
+        ("/api/v1/checkin", policy("checkin", 30, 120)),
+        (
+            "/api/v1/checkin/search_by_location",
+            policy("checkin_nearby", 5, 20),
+        ),
+        ("/api/v1/friend", policy("friend", 30, 120)),
+        ("/api/v1/location", policy("location", 30, 120)),
+        ("/api/admin", policy("admin", 60, 300)),
+    ]
+}
+
+/// 查找路径对应的限流策略，没有匹配的策略时不限流
+pub fn find_rate_limit_policy(path: &str) -> Option<RateLimitPolicy> {
+    get_rate_limit_policies()
+        .into_iter()
+        .filter(|(prefix, _)| path.starts_with(prefix))
+        .max_by_key(|(prefix, _)| prefix.len())
+        .map(|(_, policy)| policy)
+}
+
+/// 定义WebSocket上行命令的限流策略，按用户限流；
+/// 与HTTP接口同名的策略共用令牌桶，通过WebSocket发送消息不会绕过chat_send限制
+pub fn get_ws_command_rate_limit_policies() -> Vec<(&'static str, RateLimitPolicy)> {
+    vec![
+        ("send_direct_message", policy("chat_send", 20, 60)),
+        ("send_group_message", policy("chat_send", 20, 60)),
+        ("mark_read", policy("chat", 60, 300)),
+        ("typing", policy("ws_typing", 30, 120)),
+        ("subscribe", policy("ws_subscribe", 30, 120)),
+    ]
+}
+
+/// 查找WebSocket命令对应的限流策略，没有匹配的策略时不限流
+pub fn find_ws_command_rate_limit_policy(command: &str) -> Option<RateLimitPolicy> {
+    get_ws_command_rate_limit_policies()
+        .into_iter()
+        .find(|(name, _)| *name == command)
+        .map(|(_, policy)| policy)
+}
+
+const fn policy(name: &'static str, burst: u32, per_minute: u32) -> RateLimitPolicy {
+    RateLimitPolicy {
+        name,
+        burst,
+        per_minute,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn ws_message_commands_share_http_send_bucket() {
+        let http = find_rate_limit_policy("/api/v1/chat/user/send").unwrap();
+        for command in ["send_direct_message", "send_group_message"] {
+            assert_eq!(find_ws_command_rate_limit_policy(command), Some(http));
+        }
+        assert_eq!(find_ws_command_rate_limit_policy("ping"), None);
+    }
+}
@@ -0,0 +1,284 @@
+use std::sync::Arc;
+
+use axum::{Extension, Json, debug_handler, extract::State};
+use tracing::info;
+
+use crate::{
+    middleware::{admin::require_admin, auth::AppState},
+    models::api::{
+        admin::{
+            AdminDeleteCheckinRequest, AdminDeleteMessageRequest, AdminGroupActionRequest,
+            AdminListRequest, AdminUserActionRequest, AuditLogInfo, ListAuditLogsResponse,
+            ListRegistrationsResponse, RegistrationInfo,
+        },
+        user::AuthUser,
+    },
+    services::admin_service::AdminActor,
+    utils::response::{ApiResponse, AppError, PaginatedResponse, PaginationMeta, success_response},
+    validators::{admin_validator, common_validator, message_validator},
+};
+
+fn actor<'a>(current_user: &'a AuthUser, reason: Option<&'a str>) -> AdminActor<'a> {
+    AdminActor {
+        user_id: current_user.user_id,
+        platform_role: &current_user.platform_role,
+        reason,
+    }
+}
+
+// 封禁用户
+#[debug_handler]
+pub async fn ban_user(
+    Extension(current_user): Extension<AuthUser>,
+    State(state): State<Arc<AppState>>,
+    Json(payload): Json<AdminUserActionRequest>,
+) -> Result<Json<ApiResponse<()>>, AppError> {
+    info!(
+        "【Controller】开始封禁用户: admin_id={}, 目标用户ID={}",
+        current_user.user_id, payload.user_id
+    );
+
+    admin_validator::validate_reason(payload.reason.as_deref())?;
+
+    state
+        .admin_service
+        .ban_user(
+            actor(&current_user, payload.reason.as_deref()),
+            payload.user_id,
+        )
+        .await?;
+
+    // 关闭被封禁用户仍在线的连接
+    state
+        .ws_service
+        .close_user_sessions(payload.user_id, "account_banned")
+        .await;
+
+    Ok(success_response(()))
+}
+
+// 解除封禁
+#[debug_handler]
+pub async fn unban_user(
+    Extension(current_user): Extension<AuthUser>,
+    State(state): State<Arc<AppState>>,
+    Json(payload): Json<AdminUserActionRequest>,
+) -> Result<Json<ApiResponse<()>>, AppError> {
+    info!(
+        "【Controller】开始解除封禁: admin_id={}, 目标用户ID={}",
+        current_user.user_id, payload.user_id
+    );
+
+    admin_validator::validate_reason(payload.reason.as_deref())?;
+
+    state
+        .admin_service
+        .unban_user(
+            actor(&current_user, payload.reason.as_deref()),
+            payload.user_id,
+        )
+        .await?;
+
+    Ok(success_response(()))
+}
+
+// 获取最近注册的用户
+#[debug_handler]
+pub async fn list_registrations(
+    Extension(current_user): Extension<AuthUser>,
+    State(state): State<Arc<AppState>>,
+    Json(payload): Json<AdminListRequest>,
+) -> Result<Json<ApiResponse<ListRegistrationsResponse>>, AppError> {
+    let cursor = payload.pagination.cursor.unwrap_or(0);
+    let limit = payload.pagination.limit.unwrap_or(20);
+
+    info!(
+        "【Controller】开始获取最近注册用户: admin_id={}, 游标={}, 限制={}",
+        current_user.user_id, cursor, limit
+    );
+
+    // 验证分页参数
+    common_validator::validate_pagination(cursor, limit, 100)?;
+
+    let (rows, has_more, next_cursor) = state
+        .admin_service
+        .list_registrations(cursor, limit)
+        .await?;
+
+    let items = rows
+        .into_iter()
+        .map(|row| RegistrationInfo {
+            user_id: row.user_id,
+            login_id: row.login_id,
+            nickname: row.nickname,
+            is_temp: row.is_temp,
+            platform_role: row.platform_role,
+            banned_at: row.banned_at,
+            created_at: row.created_at,
+        })
+        .collect();
+
+    Ok(success_response(PaginatedResponse {
+        items,
+        pagination: PaginationMeta {
+            has_more,
+            next_cursor,
+        },
+    }))
+}
+
+// 强制删除群组，不通知成员
+#[debug_handler]
+pub async fn delete_group(
+    Extension(current_user): Extension<AuthUser>,
+    State(state): State<Arc<AppState>>,
+    Json(payload): Json<AdminGroupActionRequest>,
+) -> Result<Json<ApiResponse<()>>, AppError> {
+    info!(
+        "【Controller】开始强制删除群组: admin_id={}, group_id={}",
+        current_user.user_id, payload.group_id
+    );
+
+    require_admin(&current_user)?;
+    let group_id = common_validator::validate_uuid(&payload.group_id, "群组ID")?;
+    admin_validator::validate_reason(payload.reason.as_deref())?;
+
+    state
+        .admin_service
+        .delete_group(
+            actor(&current_user, payload.reason.as_deref()),
+            group_id,
+            false,
+        )
+        .await?;
+
+    Ok(success_response(()))
+}
+
+// 解散群组，通知所有成员
+#[debug_handler]
+pub async fn dissolve_group(
+    Extension(current_user): Extension<AuthUser>,
+    State(state): State<Arc<AppState>>,
+    Json(payload): Json<AdminGroupActionRequest>,
+) -> Result<Json<ApiResponse<()>>, AppError> {
+    info!(
+        "【Controller】开始解散群组: admin_id={}, group_id={}",
+        current_user.user_id, payload.group_id
+    );
+
+    require_admin(&current_user)?;
+    let group_id = common_validator::validate_uuid(&payload.group_id, "群组ID")?;
+    admin_validator::validate_reason(payload.reason.as_deref())?;
+
+    let group = state
+        .admin_service
+        .delete_group(
+            actor(&current_user, payload.reason.as_deref()),
+            group_id,
+            true,
+        )
+        .await?;
+
+    let _ = state
+        .ws_service
+        .send_group_dissolved(&group.member_ids, group_id, &group.name)
+        .await;
+
+    Ok(success_response(()))
+}
+
+// 删除任意签到
+#[debug_handler]
+pub async fn delete_checkin(
+    Extension(current_user): Extension<AuthUser>,
+    State(state): State<Arc<AppState>>,
+    Json(payload): Json<AdminDeleteCheckinRequest>,
+) -> Result<Json<ApiResponse<()>>, AppError> {
+    info!(
+        "【Controller】开始删除签到: admin_id={}, checkin_id={}",
+        current_user.user_id, payload.checkin_id
+    );
+
+    let checkin_id = common_validator::validate_uuid(&payload.checkin_id, "签到ID")?;
+    admin_validator::validate_reason(payload.reason.as_deref())?;
+
+    state
+        .admin_service
+        .delete_checkin(actor(&current_user, payload.reason.as_deref()), checkin_id)
+        .await?;
+
+    Ok(success_response(()))
+}
+
+// 删除任意群组消息或私聊消息
+#[debug_handler]
+pub async fn delete_message(
+    Extension(current_user): Extension<AuthUser>,
+    State(state): State<Arc<AppState>>,
+    Json(payload): Json<AdminDeleteMessageRequest>,
+) -> Result<Json<ApiResponse<()>>, AppError> {
+    info!(
+        "【Controller】开始删除消息: admin_id={}, message_id={}, 类型={}",
+        current_user.user_id, payload.message_id, payload.target_type
+    );
+
+    let message_id = common_validator::validate_uuid(&payload.message_id, "消息ID")?;
+    message_validator::validate_target_type(&payload.target_type)?;
+    admin_validator::validate_reason(payload.reason.as_deref())?;
+
+    state
+        .admin_service
+        .delete_message(
+            actor(&current_user, payload.reason.as_deref()),
+            message_id,
+            &payload.target_type,
+        )
+        .await?;
+
+    Ok(success_response(()))
+}
+
+// 获取审计日志
+#[debug_handler]
+pub async fn list_audit_logs(
+    Extension(current_user): Extension<AuthUser>,
+    State(state): State<Arc<AppState>>,
+    Json(payload): Json<AdminListRequest>,
+) -> Result<Json<ApiResponse<ListAuditLogsResponse>>, AppError> {
+    let cursor = payload.pagination.cursor.unwrap_or(0);
+    let limit = payload.pagination.limit.unwrap_or(20);
+
+    info!(
+        "【Controller】开始获取审计日志: admin_id={}, 游标={}, 限制={}",
+        current_user.user_id, cursor, limit
+    );
+
+    // 验证分页参数
+    common_validator::validate_pagination(cursor, limit, 100)?;
+
+    let (rows, has_more, next_cursor) = state.admin_service.list_audit_logs(cursor, limit).await?;
+
+    let items = rows
+        .into_iter()
+        .map(|row| AuditLogInfo {
+            id: row.id,
+            admin_id: row.admin_id,
+            admin_nickname: row.admin_nickname,
+            action: row.action,
+            target_type: row.target_type,
+            target_id: row.target_id,
+            reason: row.reason,
+            detail: row.detail,
+            created_at: row.created_at,
+        })
+        .collect();
+
+    Ok(success_response(PaginatedResponse {
+        items,
+        pagination: PaginationMeta {
+            has_more,
+            next_cursor,
+        },
+    }))
+}
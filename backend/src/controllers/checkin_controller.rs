@@ -4,6 +4,7 @@ use axum::{Extension, Json, debug_handler, extract::State};
 use tracing::{debug, info};
 
 use crate::{
+    controllers::location_controller::checkin_cluster_layer,
     middleware::auth::AppState,
     models::api::checkin::{
         CheckInInfo, CreateCheckinRequest, CreateCheckinResponse, DeleteCheckinRequest,
@@ -12,8 +13,11 @@ use crate::{
         UserCheckinsHistoryRequest, UserCheckinsHistoryResponse,
     },
     models::api::user::AuthUser,
-    utils::response::{ApiResponse, AppError, PaginatedResponse, PaginationMeta, success_response},
-    validators::{checkin_validator, common_validator},
+    utils::{
+        geo::Viewport,
+        response::{ApiResponse, AppError, PaginatedResponse, PaginationMeta, success_response},
+    },
+    validators::{checkin_validator, common_validator, location_validator},
 };
 
 // 创建签到
@@ -24,7 +28,7 @@ pub async fn create_checkin(
     Json(payload): Json<CreateCheckinRequest>,
 ) -> Result<Json<ApiResponse<CreateCheckinResponse>>, AppError> {
     info!(
-        "【Controller】开始创建签到: 用户ID={}, 位置=({}, {}), 位置名称={}",
+        "【Controller】开始创建签到: 用户ID={}, 位置=({}, {}), 位置名称={:?}",
         current_user.user_id, payload.latitude, payload.longitude, payload.location_name
     );
     debug!(
@@ -38,10 +42,19 @@ pub async fn create_checkin(
         &payload.description,
         payload.latitude,
         payload.longitude,
-        &payload.location_name,
+        payload.location_name.as_deref(),
         payload.tags.as_deref().unwrap_or(&[]),
     )?;
 
+    let location_name = state
+        .geocode_service
+        .resolve_location_name(
+            payload.latitude,
+            payload.longitude,
+            payload.location_name.as_deref(),
+        )
+        .await?;
+
     let checkin_id = state
         .checkin_service
         .create_checkin(
@@ -49,7 +62,7 @@ pub async fn create_checkin(
             &payload.description,
             payload.latitude,
             payload.longitude,
-            &payload.location_name,
+            &location_name,
             payload.tags.as_deref().unwrap_or(&[]),
         )
         .await?;
@@ -64,7 +77,7 @@ pub async fn create_checkin(
             &current_user.nickname,
             payload.latitude,
             payload.longitude,
-            &payload.location_name,
+            &location_name,
             Some(&preview),
         )
         .await;
@@ -122,10 +135,28 @@ pub async fn history(
         common_validator::validate_pagination(c, l, 50)?;
     }
 
-    let db_items = state
-        .checkin_service
-        .search_checkins_by_user(user_id, cursor, limit)
-        .await?;
+    // 存在屏蔽关系时不能查看对方的签到
+    if user_id != current_user.user_id
+        && state
+            .block_service
+            .is_blocked_between(current_user.user_id, user_id)
+            .await?
+    {
+        return Err(AppError::NotFound("用户不存在".to_string()));
+    }
+
+    // friends_only为true时返回好友最近的签到，忽略user_id
+    let db_items = if payload.friends_only.unwrap_or(false) {
+        state
+            .checkin_service
+            .search_friend_checkins(current_user.user_id, cursor, limit)
+            .await?
+    } else {
+        state
+            .checkin_service
+            .search_checkins_by_user(user_id, cursor, limit)
+            .await?
+    };
 
     debug!("【Controller】获取到{}条用户签到记录", db_items.len());
 
@@ -198,6 +229,36 @@ pub async fn search_checkin_by_location(
         common_validator::validate_pagination(c, l, 50)?;
     }
 
+    // 指定缩放级别时按瓦片聚合返回，不分页
+    if let Some(zoom) = payload.zoom {
+        let viewport = Viewport::around(payload.latitude, payload.longitude, payload.radius);
+        location_validator::validate_cluster_zoom(zoom, &viewport)?;
+
+        let mut tiles = state
+            .cluster_service
+            .checkin_clusters(current_user.user_id, viewport, zoom)
+            .await?;
+        tiles.retain_within(payload.latitude, payload.longitude, payload.radius);
+        info!(
+            "【Controller】聚合附近签到成功: 用户ID={}, 缩放级别={}, 聚合{}个, 单点{}个",
+            current_user.user_id,
+            zoom,
+            tiles.clusters.len(),
+            tiles.points.len()
+        );
+
+        return Ok(success_response(SearchCheckinByLocationResponse {
+            page: PaginatedResponse {
+                items: Vec::new(),
+                pagination: PaginationMeta {
+                    has_more: false,
+                    next_cursor: None,
+                },
+            },
+            clusters: Some(checkin_cluster_layer(zoom, tiles)),
+        }));
+    }
+
     let db_items = state
         .checkin_service
         .search_checkins_by_location(
@@ -238,12 +299,15 @@ pub async fn search_checkin_by_location(
     } else {
         items
     };
-    let response = PaginatedResponse {
-        items: items.clone(),
-        pagination: PaginationMeta {
-            has_more,
-            next_cursor,
+    let response = SearchCheckinByLocationResponse {
+        page: PaginatedResponse {
+            items: items.clone(),
+            pagination: PaginationMeta {
+                has_more,
+                next_cursor,
+            },
         },
+        clusters: None,
     };
 
     info!(
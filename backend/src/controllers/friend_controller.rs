@@ -0,0 +1,251 @@
+use std::sync::Arc;
+
+use axum::{Extension, Json, debug_handler, extract::State};
+use chrono::Utc;
+use tracing::info;
+
+use crate::{
+    middleware::auth::AppState,
+    models::{
+        api::friend::{
+            FriendInfo, FriendRequestInfo, ListFriendRequestsRequest, ListFriendRequestsResponse,
+            ListFriendsRequest, ListFriendsResponse, RemoveFriendRequest,
+            RespondFriendRequestRequest, SendFriendRequestRequest, SendFriendRequestResponse,
+        },
+        api::user::AuthUser,
+        constants::friend_request_status,
+        websocket::{FriendEvent, WebSocketMessageType},
+    },
+    utils::response::{ApiResponse, AppError, PaginatedResponse, PaginationMeta, success_response},
+    validators::common_validator,
+};
+
+// 发送好友申请
+#[debug_handler]
+pub async fn send_request(
+    Extension(current_user): Extension<AuthUser>,
+    State(state): State<Arc<AppState>>,
+    Json(payload): Json<SendFriendRequestRequest>,
+) -> Result<Json<ApiResponse<SendFriendRequestResponse>>, AppError> {
+    info!(
+        "【Controller】开始发送好友申请: user_id={}, 目标用户ID={}",
+        current_user.user_id, payload.user_id
+    );
+
+    if payload.user_id <= 0 {
+        return Err(AppError::BadRequest("无效的用户ID".to_string()));
+    }
+
+    let result = state
+        .friend_service
+        .send_request(current_user.user_id, payload.user_id)
+        .await?;
+
+    // 对方已向自己发出申请时双方直接成为好友，通知双方
+    let (message_type, recipient_ids) = if result.status == friend_request_status::ACCEPTED {
+        (
+            WebSocketMessageType::FriendAccepted,
+            vec![payload.user_id, current_user.user_id],
+        )
+    } else {
+        (WebSocketMessageType::FriendRequest, vec![payload.user_id])
+    };
+    let event = FriendEvent {
+        request_id: Some(result.request_id.to_string()),
+        user_id: current_user.user_id,
+        nickname: current_user.nickname.clone(),
+        timestamp: Utc::now(),
+    };
+    let _ = state
+        .ws_service
+        .send_friend_event(message_type, &recipient_ids, event)
+        .await;
+
+    Ok(success_response(SendFriendRequestResponse {
+        request_id: result.request_id.to_string(),
+        status: result.status.to_string(),
+    }))
+}
+
+// 同意好友申请
+#[debug_handler]
+pub async fn accept_request(
+    Extension(current_user): Extension<AuthUser>,
+    State(state): State<Arc<AppState>>,
+    Json(payload): Json<RespondFriendRequestRequest>,
+) -> Result<Json<ApiResponse<()>>, AppError> {
+    info!(
+        "【Controller】开始同意好友申请: user_id={}, request_id={}",
+        current_user.user_id, payload.request_id
+    );
+
+    let request_id = common_validator::validate_uuid(&payload.request_id, "申请ID")?;
+    let requester_id = state
+        .friend_service
+        .accept_request(current_user.user_id, request_id)
+        .await?;
+
+    let event = FriendEvent {
+        request_id: Some(payload.request_id),
+        user_id: current_user.user_id,
+        nickname: current_user.nickname.clone(),
+        timestamp: Utc::now(),
+    };
+    let _ = state
+        .ws_service
+        .send_friend_event(
+            WebSocketMessageType::FriendAccepted,
+            &[requester_id, current_user.user_id],
+            event,
+        )
+        .await;
+
+    Ok(success_response(()))
+}
+
+// 拒绝好友申请，不通知申请人
+#[debug_handler]
+pub async fn decline_request(
+    Extension(current_user): Extension<AuthUser>,
+    State(state): State<Arc<AppState>>,
+    Json(payload): Json<RespondFriendRequestRequest>,
+) -> Result<Json<ApiResponse<()>>, AppError> {
+    info!(
+        "【Controller】开始拒绝好友申请: user_id={}, request_id={}",
+        current_user.user_id, payload.request_id
+    );
+
+    let request_id = common_validator::validate_uuid(&payload.request_id, "申请ID")?;
+    state
+        .friend_service
+        .decline_request(current_user.user_id, request_id)
+        .await?;
+
+    Ok(success_response(()))
+}
+
+// 删除好友
+#[debug_handler]
+pub async fn remove_friend(
+    Extension(current_user): Extension<AuthUser>,
+    State(state): State<Arc<AppState>>,
+    Json(payload): Json<RemoveFriendRequest>,
+) -> Result<Json<ApiResponse<()>>, AppError> {
+    info!(
+        "【Controller】开始删除好友: user_id={}, 好友ID={}",
+        current_user.user_id, payload.user_id
+    );
+
+    state
+        .friend_service
+        .remove_friend(current_user.user_id, payload.user_id)
+        .await?;
+
+    let event = FriendEvent {
+        request_id: None,
+        user_id: current_user.user_id,
+        nickname: current_user.nickname.clone(),
+        timestamp: Utc::now(),
+    };
+    let _ = state
+        .ws_service
+        .send_friend_event(
+            WebSocketMessageType::FriendRemoved,
+            &[payload.user_id, current_user.user_id],
+            event,
+        )
+        .await;
+
+    Ok(success_response(()))
+}
+
+// 获取好友列表
+#[debug_handler]
+pub async fn list_friends(
+    Extension(current_user): Extension<AuthUser>,
+    State(state): State<Arc<AppState>>,
+    Json(payload): Json<ListFriendsRequest>,
+) -> Result<Json<ApiResponse<ListFriendsResponse>>, AppError> {
+    let cursor = payload.pagination.cursor.unwrap_or(0);
+    let limit = payload.pagination.limit.unwrap_or(20);
+
+    info!(
+        "【Controller】开始获取好友列表: user_id={}, 游标={}, 限制={}",
+        current_user.user_id, cursor, limit
+    );
+
+    // 验证分页参数
+    common_validator::validate_pagination(cursor, limit, 50)?;
+
+    let (rows, has_more, next_cursor) = state
+        .friend_service
+        .list_friends(current_user.user_id, cursor, limit)
+        .await?;
+
+    let items = rows
+        .into_iter()
+        .map(|row| FriendInfo {
+            user_id: row.user_id,
+            nickname: row.nickname,
+            avatar_url: row.avatar_url,
+            last_active: row.last_active,
+            online_status: row.online_status,
+            friends_since: row.friends_since,
+        })
+        .collect();
+
+    Ok(success_response(PaginatedResponse {
+        items,
+        pagination: PaginationMeta {
+            has_more,
+            next_cursor,
+        },
+    }))
+}
+
+// 获取待处理的好友申请
+#[debug_handler]
+pub async fn list_requests(
+    Extension(current_user): Extension<AuthUser>,
+    State(state): State<Arc<AppState>>,
+    Json(payload): Json<ListFriendRequestsRequest>,
+) -> Result<Json<ApiResponse<ListFriendRequestsResponse>>, AppError> {
+    let outgoing = payload.outgoing.unwrap_or(false);
+    let cursor = payload.pagination.cursor.unwrap_or(0);
+    let limit = payload.pagination.limit.unwrap_or(20);
+
+    info!(
+        "【Controller】开始获取好友申请: user_id={}, 发出的申请={}, 游标={}, 限制={}",
+        current_user.user_id, outgoing, cursor, limit
+    );
+
+    // 验证分页参数
+    common_validator::validate_pagination(cursor, limit, 50)?;
+
+    let (rows, has_more, next_cursor) = state
+        .friend_service
+        .list_pending_requests(current_user.user_id, outgoing, cursor, limit)
+        .await?;
+
+    let items = rows
+        .into_iter()
+        .map(|row| FriendRequestInfo {
+            request_id: row.request_id.to_string(),
+            requester_id: row.requester_id,
+            addressee_id: row.addressee_id,
+            user_id: row.user_id,
+            nickname: row.nickname,
+            avatar_url: row.avatar_url,
+            status: row.status,
+            created_at: row.created_at,
+        })
+        .collect();
+
+    Ok(success_response(PaginatedResponse {
+        items,
+        pagination: PaginationMeta {
+            has_more,
+            next_cursor,
+        },
+    }))
+}
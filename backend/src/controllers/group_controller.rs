@@ -3,11 +3,15 @@ use std::sync::Arc;
 use axum::{Extension, Json, debug_handler, extract::State};
 
 use crate::{
+    controllers::location_controller::group_cluster_layer,
     middleware::auth::AppState,
     models::api::{group::*, user::AuthUser},
-    utils::response::{ApiResponse, AppError, success_response},
+    utils::{
+        geo::Viewport,
+        response::{ApiResponse, AppError, success_response},
+    },
     models::constants::group_roles,
-    validators::{group_validator, common_validator},
+    validators::{group_validator, common_validator, location_validator},
 };
 
 // 创建群组
@@ -23,12 +27,23 @@ pub async fn create_group(
     // 验证名称长度
     group_validator::validate_group_name(&payload.name)?;
     
-    // 验证位置名称
-    common_validator::validate_location_name(&payload.location_name)?;
+    // 验证位置名称，未提供时根据坐标生成
+    if let Some(location_name) = &payload.location_name {
+        common_validator::validate_location_name(location_name)?;
+    }
     
     // 验证密码长度
     group_validator::validate_group_password(payload.password.as_deref())?;
-    
+
+    let location_name = state
+        .geocode_service
+        .resolve_location_name(
+            payload.latitude,
+            payload.longitude,
+            payload.location_name.as_deref(),
+        )
+        .await?;
+
     let group_id = state
         .group_service
         .create_group(
@@ -37,7 +52,7 @@ pub async fn create_group(
             current_user.user_id,
             payload.latitude,
             payload.longitude,
-            payload.location_name.as_str(),
+            &location_name,
             payload.password.as_deref(),
         )
         .await?;
@@ -62,6 +77,22 @@ pub async fn search_group_by_location(
     if let (Some(c), Some(l)) = (payload.cursor, payload.limit) {
         common_validator::validate_pagination(c, l, 50)?;
     }
+
+    // 指定缩放级别时以请求的坐标为中心按瓦片聚合返回，不分页
+    if let Some(zoom) = payload.zoom {
+        let viewport = Viewport::around(payload.latitude, payload.longitude, payload.radius);
+        location_validator::validate_cluster_zoom(zoom, &viewport)?;
+
+        let mut tiles = state.cluster_service.group_clusters(viewport, zoom).await?;
+        tiles.retain_within(payload.latitude, payload.longitude, payload.radius);
+
+        return Ok(success_response(SearchGroupByLocationResponse {
+            groups: Vec::new(),
+            has_more: false,
+            next_cursor: None,
+            clusters: Some(group_cluster_layer(zoom, tiles)),
+        }));
+    }
     
     let (groups, has_more, next_cursor) = state
         .group_service
@@ -100,6 +131,7 @@ pub async fn search_group_by_location(
         groups: items,
         has_more,
         next_cursor,
+        clusters: None,
     };
 
     Ok(success_response(response))
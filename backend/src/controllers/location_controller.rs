@@ -0,0 +1,402 @@
+use std::sync::Arc;
+
+use axum::{Extension, Json, debug_handler, extract::State};
+use chrono::{Duration, Utc};
+use tracing::info;
+
+use crate::{
+    middleware::auth::AppState,
+    models::{
+        api::{
+            checkin::CheckInInfo,
+            group::GroupInfo,
+            location::{
+                BoundingBox, CheckinMarker, ClusterLayer, GeoPoint, GroupMarker, HeatmapCell,
+                HeatmapRequest, HeatmapResponse, MapCluster, ReverseGeocodeRequest,
+                ReverseGeocodeResponse, ViewportRequest, ViewportResponse,
+            },
+            user::{AuthUser, UserInfo},
+        },
+        constants::viewport_types,
+    },
+    services::{
+        cluster_service::{CheckinPointRow, ClusterRow, GroupPointRow, TileClusters},
+        hotspot_service::{HeatmapQuery, precision_for_bounds},
+    },
+    utils::{
+        geo::Viewport,
+        geohash,
+        response::{ApiResponse, AppError, PaginatedResponse, PaginationMeta, success_response},
+    },
+    validators::{common_validator, location_validator},
+};
+
+// 根据坐标获取地址名称
+#[debug_handler]
+pub async fn reverse_geocode(
+    Extension(current_user): Extension<AuthUser>,
+    State(state): State<Arc<AppState>>,
+    Json(payload): Json<ReverseGeocodeRequest>,
+) -> Result<Json<ApiResponse<ReverseGeocodeResponse>>, AppError> {
+    info!(
+        "【Controller】开始逆地理编码: 用户ID={}, 位置=({}, {})",
+        current_user.user_id, payload.longitude, payload.latitude
+    );
+
+    common_validator::validate_location_params(payload.latitude, payload.longitude)?;
+
+    let place = state
+        .geocode_service
+        .reverse_geocode(payload.latitude, payload.longitude)
+        .await?
+        .ok_or_else(|| AppError::NotFound("未找到该位置对应的地址".to_string()))?;
+
+    let location_name = place.display_name();
+    Ok(success_response(ReverseGeocodeResponse {
+        country: place.country,
+        province: place.province,
+        city: place.city,
+        district: place.district,
+        location_name,
+        distance: place.distance,
+    }))
+}
+
+// 获取范围和时间段内的热力图
+#[debug_handler]
+pub async fn heatmap(
+    Extension(current_user): Extension<AuthUser>,
+    State(state): State<Arc<AppState>>,
+    Json(payload): Json<HeatmapRequest>,
+) -> Result<Json<ApiResponse<HeatmapResponse>>, AppError> {
+    info!(
+        "【Controller】开始获取热力图: 用户ID={}, 范围={:?}, 时间={:?}~{:?}",
+        current_user.user_id, payload.bounds, payload.start_time, payload.end_time
+    );
+
+    location_validator::validate_bounding_box(&payload.bounds)?;
+    common_validator::validate_time_range(payload.start_time, payload.end_time)?;
+    if let Some(precision) = payload.precision {
+        location_validator::validate_heatmap_precision(precision)?;
+    }
+
+    // 默认统计最近24小时，最长不超过预聚合数据的保留期
+    let end_time = payload.end_time.unwrap_or_else(Utc::now);
+    let start_time = payload
+        .start_time
+        .unwrap_or_else(|| end_time - Duration::hours(24));
+    let retention_days = state.hotspot_service.config().retention_days;
+    if end_time - start_time > Duration::days(retention_days as i64) {
+        return Err(AppError::BadRequest(format!(
+            "时间范围不能超过{}天",
+            retention_days
+        )));
+    }
+
+    let BoundingBox {
+        south_west,
+        north_east,
+    } = payload.bounds;
+    let precision = payload.precision.unwrap_or_else(|| {
+        precision_for_bounds(
+            south_west.latitude,
+            south_west.longitude,
+            north_east.latitude,
+            north_east.longitude,
+        )
+    });
+
+    let rows = state
+        .hotspot_service
+        .get_heatmap(HeatmapQuery {
+            south: south_west.latitude,
+            west: south_west.longitude,
+            north: north_east.latitude,
+            east: north_east.longitude,
+            start_time,
+            end_time,
+            precision,
+        })
+        .await?;
+
+    let cells = rows
+        .into_iter()
+        .filter_map(|row| {
+            let bounds = geohash::decode_bounds(&row.geohash)?;
+            let (latitude, longitude) = bounds.center();
+            Some(HeatmapCell {
+                geohash: row.geohash,
+                latitude,
+                longitude,
+                bounds: BoundingBox {
+                    south_west: GeoPoint {
+                        latitude: bounds.south,
+                        longitude: bounds.west,
+                    },
+                    north_east: GeoPoint {
+                        latitude: bounds.north,
+                        longitude: bounds.east,
+                    },
+                },
+                checkin_count: row.checkin_count,
+                message_count: row.message_count,
+                active_user_count: row.active_user_count,
+            })
+        })
+        .collect();
+
+    Ok(success_response(HeatmapResponse { precision, cells }))
+}
+
+// 获取地图可视范围内的用户、群组和签到
+#[debug_handler]
+pub async fn viewport(
+    Extension(current_user): Extension<AuthUser>,
+    State(state): State<Arc<AppState>>,
+    Json(payload): Json<ViewportRequest>,
+) -> Result<Json<ApiResponse<ViewportResponse>>, AppError> {
+    info!(
+        "【Controller】开始查询可视范围: 用户ID={}, 范围={:?}, 类型={:?}",
+        current_user.user_id, payload.bounds, payload.types
+    );
+
+    location_validator::validate_bounding_box(&payload.bounds)?;
+    if let Some(types) = &payload.types {
+        location_validator::validate_viewport_types(types)?;
+    }
+
+    // 验证分页参数，limit对三类内容分别生效
+    let limit = payload.limit.unwrap_or(20);
+    for cursor in [
+        payload.user_cursor,
+        payload.group_cursor,
+        payload.checkin_cursor,
+    ] {
+        common_validator::validate_pagination(cursor.unwrap_or(0), limit, 100)?;
+    }
+
+    let includes = |viewport_type: &str| {
+        payload
+            .types
+            .as_ref()
+            .is_none_or(|types| types.iter().any(|t| t == viewport_type))
+    };
+    let BoundingBox {
+        south_west,
+        north_east,
+    } = payload.bounds;
+    let viewport = Viewport {
+        south: south_west.latitude,
+        west: south_west.longitude,
+        north: north_east.latitude,
+        east: north_east.longitude,
+    };
+
+    // 指定缩放级别时群组和签到按瓦片聚合返回
+    if let Some(zoom) = payload.zoom {
+        location_validator::validate_cluster_zoom(zoom, &viewport)?;
+    }
+
+    let mut response = ViewportResponse {
+        users: None,
+        groups: None,
+        checkins: None,
+        group_clusters: None,
+        checkin_clusters: None,
+    };
+
+    if includes(viewport_types::USERS) {
+        let (users, has_more, next_cursor) = state
+            .user_service
+            .find_users_in_viewport(
+                viewport,
+                payload.user_cursor.unwrap_or(0),
+                limit,
+                current_user.user_id,
+            )
+            .await?;
+        let items = users
+            .into_iter()
+            .map(|user| UserInfo {
+                user_id: user.user_id,
+                nickname: user.nickname,
+                last_active: user.last_active,
+                latitude: user.latitude,
+                longitude: user.longitude,
+                distance: user.distance,
+                location_name: user.location_name,
+                online_status: user.online_status,
+                avatar_url: user.avatar_url,
+                bio: user.bio,
+                gender: user.gender,
+            })
+            .collect();
+        response.users = Some(PaginatedResponse {
+            items,
+            pagination: PaginationMeta {
+                has_more,
+                next_cursor,
+            },
+        });
+    }
+
+    if let Some(zoom) = payload.zoom
+        && includes(viewport_types::GROUPS)
+    {
+        let tiles = state.cluster_service.group_clusters(viewport, zoom).await?;
+        response.group_clusters = Some(group_cluster_layer(zoom, tiles));
+    } else if includes(viewport_types::GROUPS) {
+        let (groups, has_more, next_cursor) = state
+            .group_service
+            .search_groups_in_viewport(
+                current_user.user_id,
+                viewport,
+                payload.group_cursor.unwrap_or(0),
+                limit,
+            )
+            .await?;
+        let items = groups
+            .into_iter()
+            .map(|group| GroupInfo {
+                group_id: group.group_id,
+                name: group.name,
+                description: group.description,
+                owner_id: group.owner_id,
+                creator_name: group.creator_name,
+                created_at: group.created_at,
+                last_active_at: group.last_active_at,
+                latitude: group.latitude,
+                longitude: group.longitude,
+                location_name: group.location_name,
+                member_count: group.member_count,
+                distance: group.distance,
+                is_password_required: group.is_password_required,
+                is_member: group.is_member,
+                user_role: group.user_role,
+            })
+            .collect();
+        response.groups = Some(PaginatedResponse {
+            items,
+            pagination: PaginationMeta {
+                has_more,
+                next_cursor,
+            },
+        });
+    }
+
+    if let Some(zoom) = payload.zoom
+        && includes(viewport_types::CHECKINS)
+    {
+        let tiles = state
+            .cluster_service
+            .checkin_clusters(current_user.user_id, viewport, zoom)
+            .await?;
+        response.checkin_clusters = Some(checkin_cluster_layer(zoom, tiles));
+    } else if includes(viewport_types::CHECKINS) {
+        let mut checkins = state
+            .checkin_service
+            .search_checkins_in_viewport(
+                current_user.user_id,
+                viewport,
+                payload.checkin_cursor.unwrap_or(0),
+                limit,
+            )
+            .await?;
+        let has_more = checkins.len() as i64 > limit;
+        checkins.truncate(limit as usize);
+        let next_cursor = if has_more {
+            checkins.last().map(|checkin| checkin.id)
+        } else {
+            None
+        };
+        let items = checkins
+            .into_iter()
+            .map(|checkin| CheckInInfo {
+                id: checkin.id,
+                checkin_id: checkin.checkin_id,
+                user_id: checkin.user_id,
+                nickname: checkin.nickname,
+                description: checkin.description,
+                latitude: checkin.latitude,
+                longitude: checkin.longitude,
+                location_name: checkin.location_name,
+                distance: checkin.distance,
+                created_at: checkin.created_at,
+                tags: checkin.tags,
+                likes_count: checkin.likes_count,
+                comments_count: checkin.comments_count,
+                liked_by_me: checkin.liked_by_me,
+            })
+            .collect();
+        response.checkins = Some(PaginatedResponse {
+            items,
+            pagination: PaginationMeta {
+                has_more,
+                next_cursor,
+            },
+        });
+    }
+
+    Ok(success_response(response))
+}
+
+fn map_cluster(row: ClusterRow) -> MapCluster {
+    MapCluster {
+        count: row.count,
+        latitude: row.latitude,
+        longitude: row.longitude,
+        bounds: BoundingBox {
+            south_west: GeoPoint {
+                latitude: row.south,
+                longitude: row.west,
+            },
+            north_east: GeoPoint {
+                latitude: row.north,
+                longitude: row.east,
+            },
+        },
+    }
+}
+
+// 签到聚合结果转换为API响应模型
+pub fn checkin_cluster_layer(
+    zoom: u32,
+    tiles: TileClusters<CheckinPointRow>,
+) -> ClusterLayer<CheckinMarker> {
+    ClusterLayer {
+        zoom,
+        clusters: tiles.clusters.into_iter().map(map_cluster).collect(),
+        points: tiles
+            .points
+            .into_iter()
+            .map(|point| CheckinMarker {
+                id: point.id,
+                checkin_id: point.checkin_id,
+                user_id: point.user_id,
+                latitude: point.latitude,
+                longitude: point.longitude,
+            })
+            .collect(),
+    }
+}
+
+// 群组聚合结果转换为API响应模型
+pub fn group_cluster_layer(
+    zoom: u32,
+    tiles: TileClusters<GroupPointRow>,
+) -> ClusterLayer<GroupMarker> {
+    ClusterLayer {
+        zoom,
+        clusters: tiles.clusters.into_iter().map(map_cluster).collect(),
+        points: tiles
+            .points
+            .into_iter()
+            .map(|point| GroupMarker {
+                group_id: point.group_id,
+                name: point.name,
+                latitude: point.latitude,
+                longitude: point.longitude,
+            })
+            .collect(),
+    }
+}
@@ -9,7 +9,8 @@ use crate::{
         DeleteGroupMessageRequest, DeleteMessageResponse, DirectMessageHistoryRequest,
         DirectMessageHistoryResponse, GroupMessageHistoryRequest, GroupMessageHistoryResponse,
         MarkReadRequest, MarkReadResponse, SendDirectMessageRequest, SendDirectMessageResponse,
-        SendGroupMessageRequest, SendGroupMessageResponse,
+        SendGroupMessageRequest, SendGroupMessageResponse, SyncEventItem, SyncEventsRequest,
+        SyncEventsResponse,
     },
     models::api::user::AuthUser,
     utils::response::{ApiResponse, AppError, success_response},
@@ -66,7 +67,7 @@ pub async fn send_group_message(
         .ws_service
         .send_group_message(
             &send_group_message_result.message_id.to_string(),
-            &payload.group_id,
+            group_id,
             &group.name,
             current_user.user_id,
             &current_user.nickname,
@@ -162,7 +163,7 @@ pub async fn send_direct_message(
         )
         .await?;
 
-    // 通过WebSocket发送实时消息
+    // 通过WebSocket发送实时消息，位置已按发送者的隐私级别模糊
     let _ = state
         .ws_service
         .send_direct_message(
@@ -173,8 +174,8 @@ pub async fn send_direct_message(
             recipient_id,
             payload.content.clone(),
             payload.message_type.clone(),
-            payload.latitude,
-            payload.longitude,
+            message.latitude,
+            message.longitude,
         )
         .await;
 
@@ -362,3 +363,43 @@ pub async fn mark_messages_read(
 
     Ok(success_response(response))
 }
+
+// 同步离线事件（断线时间较长时替代WebSocket补发）
+#[debug_handler]
+pub async fn sync_events(
+    Extension(current_user): Extension<AuthUser>,
+    State(state): State<Arc<AppState>>,
+    Json(payload): Json<SyncEventsRequest>,
+) -> Result<Json<ApiResponse<SyncEventsResponse>>, AppError> {
+    let since_seq = payload.pagination.cursor.unwrap_or(0);
+    let limit = payload.pagination.limit.unwrap_or(100);
+
+    // 验证分页参数
+    common_validator::validate_pagination(since_seq, limit, 200)?; // 最大每页200条
+
+    let (events, has_more) = state
+        .event_service
+        .get_events_since(current_user.user_id, since_seq, limit)
+        .await?;
+
+    let next_cursor = events.last().map(|event| event.seq);
+    let items = events
+        .into_iter()
+        .map(|event| SyncEventItem {
+            seq: event.seq,
+            event_type: event.event_type,
+            payload: event.payload,
+            created_at: event.created_at,
+        })
+        .collect();
+
+    let response = crate::utils::response::PaginatedResponse {
+        items,
+        pagination: crate::utils::response::PaginationMeta {
+            has_more,
+            next_cursor,
+        },
+    };
+
+    Ok(success_response(response))
+}
@@ -1,4 +1,7 @@
+pub mod admin_controller;
 pub mod checkin_controller;
+pub mod friend_controller;
 pub mod group_controller;
+pub mod location_controller;
 pub mod message_controller;
 pub mod user_controller;
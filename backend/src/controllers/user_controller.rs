@@ -1,28 +1,47 @@
 use axum::{
     Json, debug_handler,
     extract::{Extension, State},
+    http::header,
+    response::{IntoResponse, Response},
 };
+use chrono::{DateTime, Duration, Utc};
 use std::sync::Arc;
-use tracing::{debug, info};
+use tracing::{debug, info, warn};
 
 use crate::{
     middleware::auth::AppState,
     models::{
         api::user::AuthUser,
         api::user::{
-            AuthResponse, CreateTempUserRequest, LoginRequest, RefreshTokenRequest,
-            RefreshTokenResponse, RegisterRequest, SearchUserByIdRequest, SearchUserByIdResponse,
-            SearchUserByLocationRequest, SearchUserByLocationResponse, SearchUserByNameRequest,
-            SearchUserByNameResponse, UpdateLocationRequest, UserInfo,
+            ActiveDevice, AuthResponse, BlockUserRequest, BlockedUserInfo, ChangePasswordRequest,
+            ClaimAccountRequest, CreateTempUserRequest, DeleteAccountRequest,
+            DeleteAccountResponse, DeleteLocationHistoryRequest, DeleteLocationHistoryResponse,
+            ListActiveDevicesResponse, ListBlockedUsersResponse, LocationHistoryItem,
+            LocationHistoryRequest, LocationHistoryResponse, LoginRequest, LogoutAllResponse,
+            LogoutRequest, RefreshTokenRequest, RefreshTokenResponse, RegisterRequest,
+            RequestPasswordResetRequest, ResetPasswordRequest, SearchUserByIdRequest,
+            SearchUserByIdResponse, SearchUserByLocationRequest, SearchUserByLocationResponse,
+            SearchUserByNameRequest, SearchUserByNameResponse, UpdateLocationRequest,
+            UpdateLocationVisibilityRequest, UpdateLocationVisibilityResponse,
+            UpdateProfileRequest, UserInfo, UserProfileResponse,
         },
     },
-    utils::response::{ApiResponse, AppError, PaginatedResponse, PaginationMeta, success_response},
+    services::{
+        location_history_service::LocationPoint,
+        user_service::{NearbyUserSearch, ProfileUpdate, UserProfileRow},
+    },
+    utils::{
+        client_ip::ClientIp,
+        jwt::TokenClaims,
+        response::{ApiResponse, AppError, PaginatedResponse, PaginationMeta, success_response},
+    },
     validators::{common_validator, user_validator},
 };
 
 // 用户注册
 #[debug_handler]
 pub async fn register(
+    Extension(ClientIp(client_ip)): Extension<ClientIp>,
     State(state): State<Arc<AppState>>,
     Json(payload): Json<RegisterRequest>,
 ) -> Result<Json<ApiResponse<AuthResponse>>, AppError> {
@@ -49,6 +68,7 @@ pub async fn register(
             &payload.login_id,
             &payload.password,
             &payload.nickname.unwrap_or_else(|| "用户".to_string()),
+            client_ip,
         )
         .await?;
 
@@ -71,6 +91,7 @@ pub async fn register(
 // 用户登录
 #[debug_handler]
 pub async fn login(
+    Extension(ClientIp(client_ip)): Extension<ClientIp>,
     State(state): State<Arc<AppState>>,
     Json(payload): Json<LoginRequest>,
 ) -> Result<Json<ApiResponse<AuthResponse>>, AppError> {
@@ -82,7 +103,7 @@ pub async fn login(
     // 1. 调用登录接口
     let user_login_result = state
         .user_service
-        .login(&payload.login_id, &payload.password)
+        .login(&payload.login_id, &payload.password, client_ip)
         .await?;
 
     // 2. 组装响应
@@ -104,6 +125,7 @@ pub async fn login(
 // 创建临时用户
 #[debug_handler]
 pub async fn create_temp_user(
+    Extension(ClientIp(client_ip)): Extension<ClientIp>,
     State(state): State<Arc<AppState>>,
     Json(payload): Json<CreateTempUserRequest>,
 ) -> Result<Json<ApiResponse<AuthResponse>>, AppError> {
@@ -120,7 +142,10 @@ pub async fn create_temp_user(
     // 调用service层实现临时用户创建
     let user_login_result = state
         .user_service
-        .create_temp_user(&payload.nickname.unwrap_or_else(|| "临时用户".to_string()))
+        .create_temp_user(
+            &payload.nickname.unwrap_or_else(|| "临时用户".to_string()),
+            client_ip,
+        )
         .await?;
 
     // 构建响应
@@ -156,9 +181,12 @@ pub async fn search_user_by_location(
     common_validator::validate_search_radius(payload.radius, 10000.0)?; // 最大半径10公里
 
     // 参数转换
-    let longitude = payload.longitude;
-    let latitude = payload.latitude;
-    let radius = payload.radius;
+    let search = NearbyUserSearch {
+        longitude: payload.longitude,
+        latitude: payload.latitude,
+        radius: payload.radius,
+        friends_only: payload.friends_only.unwrap_or(false),
+    };
     let cursor = payload.pagination.cursor.unwrap_or(0);
     let limit = payload.pagination.limit.unwrap_or(10);
 
@@ -170,14 +198,7 @@ pub async fn search_user_by_location(
     // 调用service层方法
     let (users, has_more, next_cursor) = state
         .user_service
-        .find_nearby_users(
-            longitude,
-            latitude,
-            radius,
-            cursor,
-            limit,
-            current_user.user_id,
-        )
+        .find_nearby_users(search, cursor, limit, current_user.user_id)
         .await?;
 
     // 转换为API响应模型
@@ -192,6 +213,9 @@ pub async fn search_user_by_location(
             distance: user.distance,
             location_name: user.location_name,
             online_status: user.online_status,
+            avatar_url: user.avatar_url,
+            bio: user.bio,
+            gender: user.gender,
         })
         .collect();
 
@@ -254,6 +278,9 @@ pub async fn search_user_by_name(
             distance: user.distance,
             location_name: user.location_name,
             online_status: user.online_status,
+            avatar_url: user.avatar_url,
+            bio: user.bio,
+            gender: user.gender,
         })
         .collect();
 
@@ -295,6 +322,15 @@ pub async fn search_user_by_id(
         return Err(AppError::BadRequest("无效的用户ID".to_string()));
     }
 
+    // 存在屏蔽关系时视为用户不存在
+    if state
+        .block_service
+        .is_blocked_between(current_user.user_id, user_id)
+        .await?
+    {
+        return Err(AppError::NotFound("用户不存在".to_string()));
+    }
+
     // 调用service层方法
     let user = state
         .user_service
@@ -316,12 +352,260 @@ pub async fn search_user_by_id(
         distance: user.distance,
         location_name: user.location_name,
         online_status: user.online_status,
+        avatar_url: user.avatar_url,
+        bio: user.bio,
+        gender: user.gender,
     };
 
     // 直接返回UserInfo作为SearchUserByIdResponse
     Ok(success_response(user_info))
 }
 
+// 获取当前用户的完整资料
+#[debug_handler]
+pub async fn get_profile(
+    Extension(current_user): Extension<AuthUser>,
+    State(state): State<Arc<AppState>>,
+) -> Result<Json<ApiResponse<UserProfileResponse>>, AppError> {
+    info!(
+        "【Controller】开始获取用户资料: user_id={}",
+        current_user.user_id
+    );
+
+    let profile = state.user_service.get_profile(current_user.user_id).await?;
+
+    Ok(success_response(to_profile_response(profile)))
+}
+
+// 更新当前用户的资料
+#[debug_handler]
+pub async fn update_profile(
+    Extension(current_user): Extension<AuthUser>,
+    State(state): State<Arc<AppState>>,
+    Json(payload): Json<UpdateProfileRequest>,
+) -> Result<Json<ApiResponse<UserProfileResponse>>, AppError> {
+    info!(
+        "【Controller】开始更新用户资料: user_id={}",
+        current_user.user_id
+    );
+    debug!("【Controller】更新资料请求参数: {:?}", payload);
+
+    // 验证请求参数
+    if let Some(nickname) = &payload.nickname {
+        user_validator::validate_nickname(nickname)?;
+    }
+    if let Some(avatar_url) = &payload.avatar_url {
+        user_validator::validate_avatar_url(avatar_url)?;
+    }
+    if let Some(bio) = &payload.bio {
+        user_validator::validate_bio(bio)?;
+    }
+    if let Some(gender) = &payload.gender {
+        user_validator::validate_gender(gender)?;
+    }
+    if let Some(level) = &payload.location_privacy {
+        user_validator::validate_location_privacy(level)?;
+    }
+    let birthday = payload
+        .birthday
+        .as_deref()
+        .map(user_validator::validate_birthday)
+        .transpose()?;
+
+    let update = ProfileUpdate {
+        nickname: payload.nickname,
+        avatar_url: payload.avatar_url,
+        bio: payload.bio,
+        gender: payload.gender,
+        birthday,
+        location_privacy: payload.location_privacy,
+    };
+    let profile_changed = update.nickname.is_some() || update.avatar_url.is_some();
+
+    let profile = state
+        .user_service
+        .update_profile(current_user.user_id, update)
+        .await?;
+
+    // 昵称或头像变更时通知在线联系人刷新缓存
+    if profile_changed
+        && let Err(e) = state
+            .ws_service
+            .send_profile_update(
+                profile.user_id,
+                &profile.nickname,
+                profile.avatar_url.as_deref(),
+            )
+            .await
+    {
+        warn!("【Controller】推送资料变更失败: {:?}", e);
+    }
+
+    info!(
+        "【Controller】用户资料更新成功: user_id={}",
+        current_user.user_id
+    );
+    Ok(success_response(to_profile_response(profile)))
+}
+
+// 设置位置可见范围（隐身模式）
+#[debug_handler]
+pub async fn update_location_visibility(
+    Extension(current_user): Extension<AuthUser>,
+    State(state): State<Arc<AppState>>,
+    Json(payload): Json<UpdateLocationVisibilityRequest>,
+) -> Result<Json<ApiResponse<UpdateLocationVisibilityResponse>>, AppError> {
+    info!(
+        "【Controller】开始设置位置可见范围: user_id={}, 范围={}, 时长={:?}分钟",
+        current_user.user_id, payload.visibility, payload.duration_minutes
+    );
+
+    user_validator::validate_location_visibility(&payload.visibility, payload.duration_minutes)?;
+
+    let expires_at = payload
+        .duration_minutes
+        .map(|minutes| Utc::now() + Duration::minutes(minutes));
+
+    state
+        .user_service
+        .update_location_visibility(current_user.user_id, &payload.visibility, expires_at)
+        .await?;
+
+    // 同步在线状态，已隐身的用户对其他人显示为下线
+    if let Err(e) = state
+        .ws_service
+        .refresh_user_status(current_user.user_id)
+        .await
+    {
+        warn!("【Controller】同步在线状态失败: {:?}", e);
+    }
+
+    Ok(success_response(UpdateLocationVisibilityResponse {
+        visibility: payload.visibility,
+        expires_at,
+    }))
+}
+
+fn to_profile_response(profile: UserProfileRow) -> UserProfileResponse {
+    UserProfileResponse {
+        user_id: profile.user_id,
+        login_id: profile.login_id,
+        nickname: profile.nickname,
+        avatar_url: profile.avatar_url,
+        bio: profile.bio,
+        gender: profile.gender,
+        birthday: profile.birthday,
+        is_temp: profile.is_temp,
+        location_privacy: profile.location_privacy,
+        location_visibility: profile.location_visibility,
+        location_visibility_expires_at: profile.location_visibility_expires_at,
+        created_at: profile.created_at,
+        deletion_scheduled_at: profile.deletion_scheduled_at,
+    }
+}
+
+// 公开JWT验证公钥（JWKS），供其他服务验证本服务签发的令牌
+pub async fn jwks(State(state): State<Arc<AppState>>) -> Response {
+    let jwks = state.config_service.config.jwt_keys.jwks();
+    ([(header::CACHE_CONTROL, "public, max-age=300")], Json(jwks)).into_response()
+}
+
+// 申请注销账号，宽限期结束后删除账号及其内容
+#[debug_handler]
+pub async fn delete_account(
+    Extension(current_user): Extension<AuthUser>,
+    State(state): State<Arc<AppState>>,
+    Json(payload): Json<DeleteAccountRequest>,
+) -> Result<Json<ApiResponse<DeleteAccountResponse>>, AppError> {
+    info!(
+        "【Controller】开始申请注销账号: user_id={}",
+        current_user.user_id
+    );
+
+    // 正式用户需要二次确认密码
+    let profile = state.user_service.get_profile(current_user.user_id).await?;
+    if !profile.is_temp {
+        let password = payload
+            .password
+            .as_deref()
+            .ok_or_else(|| AppError::BadRequest("请输入密码确认注销".to_string()))?;
+        state
+            .user_service
+            .verify_user_password(current_user.user_id, password)
+            .await?;
+    }
+
+    let deletion_scheduled_at = state
+        .account_service
+        .request_deletion(current_user.user_id)
+        .await?;
+
+    info!(
+        "【Controller】申请注销账号成功: user_id={}, 计划删除时间={}",
+        current_user.user_id, deletion_scheduled_at
+    );
+    Ok(success_response(DeleteAccountResponse {
+        deletion_scheduled_at,
+    }))
+}
+
+// 撤销注销申请
+#[debug_handler]
+pub async fn cancel_account_deletion(
+    Extension(current_user): Extension<AuthUser>,
+    State(state): State<Arc<AppState>>,
+) -> Result<Json<ApiResponse<()>>, AppError> {
+    info!(
+        "【Controller】开始撤销注销申请: user_id={}",
+        current_user.user_id
+    );
+
+    state
+        .account_service
+        .cancel_deletion(current_user.user_id)
+        .await?;
+
+    info!(
+        "【Controller】撤销注销申请成功: user_id={}",
+        current_user.user_id
+    );
+    Ok(success_response(()))
+}
+
+// 导出个人数据，以JSON附件形式下载
+#[debug_handler]
+pub async fn export_data(
+    Extension(current_user): Extension<AuthUser>,
+    State(state): State<Arc<AppState>>,
+) -> Result<Response, AppError> {
+    info!(
+        "【Controller】开始导出个人数据: user_id={}",
+        current_user.user_id
+    );
+
+    let profile = state.user_service.get_profile(current_user.user_id).await?;
+    let export = state.account_service.export_user_data(profile).await?;
+
+    let filename = format!(
+        "geotalk-export-{}-{}.json",
+        current_user.user_id,
+        export.exported_at.format("%Y%m%d%H%M%S")
+    );
+
+    info!(
+        "【Controller】导出个人数据成功: user_id={}",
+        current_user.user_id
+    );
+    Ok((
+        [(
+            header::CONTENT_DISPOSITION,
+            format!("attachment; filename=\"{}\"", filename),
+        )],
+        Json(export),
+    )
+        .into_response())
+}
+
 // 更新用户位置
 #[debug_handler]
 pub async fn update_location(
@@ -337,7 +621,19 @@ pub async fn update_location(
     // 验证位置参数
     common_validator::validate_location_params(payload.latitude, payload.longitude)?;
 
-    common_validator::validate_location_name(&payload.location_name)?;
+    if let Some(location_name) = &payload.location_name {
+        common_validator::validate_location_name(location_name)?;
+    }
+    user_validator::validate_motion_params(payload.accuracy, payload.speed, payload.heading)?;
+
+    let location_name = state
+        .geocode_service
+        .resolve_location_name(
+            payload.latitude,
+            payload.longitude,
+            payload.location_name.as_deref(),
+        )
+        .await?;
 
     // 调用service层方法
     state
@@ -346,7 +642,23 @@ pub async fn update_location(
             current_user.user_id,
             payload.latitude,
             payload.longitude,
-            &payload.location_name,
+            &location_name,
+        )
+        .await?;
+
+    // 追加到位置轨迹
+    state
+        .location_history_service
+        .record_point(
+            current_user.user_id,
+            LocationPoint {
+                latitude: payload.latitude,
+                longitude: payload.longitude,
+                accuracy: payload.accuracy,
+                speed: payload.speed,
+                heading: payload.heading,
+                location_name: &location_name,
+            },
         )
         .await?;
 
@@ -357,6 +669,214 @@ pub async fn update_location(
     Ok(success_response(()))
 }
 
+// 获取当前用户的位置轨迹
+#[debug_handler]
+pub async fn location_history(
+    Extension(current_user): Extension<AuthUser>,
+    State(state): State<Arc<AppState>>,
+    Json(payload): Json<LocationHistoryRequest>,
+) -> Result<Json<ApiResponse<LocationHistoryResponse>>, AppError> {
+    let cursor = payload.pagination.cursor.unwrap_or(0);
+    let limit = payload.pagination.limit.unwrap_or(50);
+
+    info!(
+        "【Controller】开始获取位置轨迹: user_id={}, 开始={:?}, 结束={:?}, 游标={}, 限制={}",
+        current_user.user_id, payload.start_time, payload.end_time, cursor, limit
+    );
+
+    common_validator::validate_time_range(payload.start_time, payload.end_time)?;
+    // 验证分页参数
+    common_validator::validate_pagination(cursor, limit, 500)?;
+
+    let (rows, has_more, next_cursor) = state
+        .location_history_service
+        .list_history(
+            current_user.user_id,
+            payload.start_time,
+            payload.end_time,
+            cursor,
+            limit,
+        )
+        .await?;
+
+    let items = rows
+        .into_iter()
+        .map(|row| LocationHistoryItem {
+            id: row.id,
+            latitude: row.latitude,
+            longitude: row.longitude,
+            accuracy: row.accuracy,
+            speed: row.speed,
+            heading: row.heading,
+            location_name: row.location_name,
+            recorded_at: row.recorded_at,
+        })
+        .collect();
+
+    Ok(success_response(PaginatedResponse {
+        items,
+        pagination: PaginationMeta {
+            has_more,
+            next_cursor,
+        },
+    }))
+}
+
+// 删除当前用户指定时间范围内的位置轨迹
+#[debug_handler]
+pub async fn delete_location_history(
+    Extension(current_user): Extension<AuthUser>,
+    State(state): State<Arc<AppState>>,
+    Json(payload): Json<DeleteLocationHistoryRequest>,
+) -> Result<Json<ApiResponse<DeleteLocationHistoryResponse>>, AppError> {
+    info!(
+        "【Controller】开始删除位置轨迹: user_id={}, 开始={:?}, 结束={:?}",
+        current_user.user_id, payload.start_time, payload.end_time
+    );
+
+    common_validator::validate_time_range(payload.start_time, payload.end_time)?;
+
+    let deleted_count = state
+        .location_history_service
+        .delete_history(current_user.user_id, payload.start_time, payload.end_time)
+        .await?;
+
+    Ok(success_response(DeleteLocationHistoryResponse {
+        deleted_count,
+    }))
+}
+
+// 临时用户认领为正式账号
+#[debug_handler]
+pub async fn claim_account(
+    Extension(current_user): Extension<AuthUser>,
+    State(state): State<Arc<AppState>>,
+    Json(payload): Json<ClaimAccountRequest>,
+) -> Result<Json<ApiResponse<AuthResponse>>, AppError> {
+    info!(
+        "【Controller】开始认领临时账号: user_id={}, login_id={}",
+        current_user.user_id, payload.login_id
+    );
+
+    // 验证请求参数
+    user_validator::validate_user_registration(
+        &payload.login_id,
+        Some(&payload.password),
+        payload.nickname.as_deref(),
+    )?;
+
+    let user_login_result = state
+        .user_service
+        .claim_account(
+            current_user.user_id,
+            &payload.login_id,
+            &payload.password,
+            payload.nickname.as_deref(),
+        )
+        .await?;
+
+    let response = AuthResponse {
+        user_id: user_login_result.user_id,
+        nickname: user_login_result.nickname,
+        access_token: user_login_result.access_token,
+        refresh_token: user_login_result.refresh_token,
+        expires_at: user_login_result.expires_at,
+    };
+
+    info!(
+        "【Controller】临时账号认领成功: user_id={}",
+        user_login_result.user_id
+    );
+    Ok(success_response(response))
+}
+
+// 修改密码：其他设备的令牌和WebSocket连接全部失效，当前设备使用返回的新令牌
+#[debug_handler]
+pub async fn change_password(
+    Extension(current_user): Extension<AuthUser>,
+    State(state): State<Arc<AppState>>,
+    Json(payload): Json<ChangePasswordRequest>,
+) -> Result<Json<ApiResponse<AuthResponse>>, AppError> {
+    info!(
+        "【Controller】开始修改密码: user_id={}",
+        current_user.user_id
+    );
+
+    user_validator::validate_password(&payload.new_password)?;
+
+    let user_login_result = state
+        .user_service
+        .change_password(
+            current_user.user_id,
+            &payload.current_password,
+            &payload.new_password,
+        )
+        .await?;
+
+    state
+        .ws_service
+        .close_user_sessions(current_user.user_id, "password_changed")
+        .await;
+
+    let response = AuthResponse {
+        user_id: user_login_result.user_id,
+        nickname: user_login_result.nickname,
+        access_token: user_login_result.access_token,
+        refresh_token: user_login_result.refresh_token,
+        expires_at: user_login_result.expires_at,
+    };
+
+    info!(
+        "【Controller】修改密码成功: user_id={}",
+        current_user.user_id
+    );
+    Ok(success_response(response))
+}
+
+// 申请密码重置验证码，无论账号是否存在都返回成功
+#[debug_handler]
+pub async fn request_password_reset(
+    State(state): State<Arc<AppState>>,
+    Json(payload): Json<RequestPasswordResetRequest>,
+) -> Result<Json<ApiResponse<()>>, AppError> {
+    info!(
+        "【Controller】开始申请密码重置: login_id={}",
+        payload.login_id
+    );
+
+    state
+        .user_service
+        .request_password_reset(&payload.login_id)
+        .await?;
+
+    Ok(success_response(()))
+}
+
+// 使用验证码重置密码，成功后所有设备需要重新登录
+#[debug_handler]
+pub async fn reset_password(
+    State(state): State<Arc<AppState>>,
+    Json(payload): Json<ResetPasswordRequest>,
+) -> Result<Json<ApiResponse<()>>, AppError> {
+    info!("【Controller】开始重置密码: login_id={}", payload.login_id);
+
+    user_validator::validate_reset_code(&payload.code)?;
+    user_validator::validate_password(&payload.new_password)?;
+
+    let user_id = state
+        .user_service
+        .reset_password(&payload.login_id, &payload.code, &payload.new_password)
+        .await?;
+
+    state
+        .ws_service
+        .close_user_sessions(user_id, "password_reset")
+        .await;
+
+    info!("【Controller】重置密码成功: user_id={}", user_id);
+    Ok(success_response(()))
+}
+
 // 刷新令牌
 #[debug_handler]
 pub async fn refresh_token(
@@ -388,3 +908,194 @@ pub async fn refresh_token(
     );
     Ok(success_response(response))
 }
+
+// 退出登录：注销当前访问令牌并关闭使用该令牌的WebSocket连接
+#[debug_handler]
+pub async fn logout(
+    Extension(current_user): Extension<AuthUser>,
+    Extension(claims): Extension<TokenClaims>,
+    State(state): State<Arc<AppState>>,
+    Json(payload): Json<LogoutRequest>,
+) -> Result<Json<ApiResponse<()>>, AppError> {
+    info!(
+        "【Controller】开始退出登录: user_id={}",
+        current_user.user_id
+    );
+
+    // 访问令牌加入黑名单直到过期；令牌缺少jti无法单独注销时，使该用户此前签发的所有访问令牌失效
+    if claims.jti.is_empty() {
+        warn!(
+            "【Controller】令牌缺少jti，注销该用户所有已签发的访问令牌: user_id={}",
+            current_user.user_id
+        );
+        state
+            .user_service
+            .revoke_issued_tokens(current_user.user_id)
+            .await?;
+    } else {
+        let expires_at = DateTime::from_timestamp(claims.exp as i64, 0).unwrap_or_else(Utc::now);
+        state
+            .token_denylist_service
+            .revoke(&claims.jti, current_user.user_id, expires_at)
+            .await?;
+    }
+
+    // 吊销对应的刷新令牌，避免继续换取新的访问令牌
+    if let Some(refresh_token) = payload.refresh_token.as_deref() {
+        state
+            .user_service
+            .revoke_token_family(current_user.user_id, refresh_token)
+            .await?;
+    }
+
+    if claims.jti.is_empty() {
+        state
+            .ws_service
+            .close_user_sessions(current_user.user_id, "logout")
+            .await;
+    } else {
+        state
+            .ws_service
+            .close_token_sessions(current_user.user_id, &claims.jti, "logout")
+            .await;
+    }
+
+    info!(
+        "【Controller】退出登录成功: user_id={}",
+        current_user.user_id
+    );
+    Ok(success_response(()))
+}
+
+// 退出所有设备：吊销当前用户的全部刷新令牌和已签发的访问令牌，并关闭所有WebSocket连接
+#[debug_handler]
+pub async fn logout_all(
+    Extension(current_user): Extension<AuthUser>,
+    State(state): State<Arc<AppState>>,
+) -> Result<Json<ApiResponse<LogoutAllResponse>>, AppError> {
+    info!(
+        "【Controller】开始退出所有设备: user_id={}",
+        current_user.user_id
+    );
+
+    let revoked_count = state
+        .user_service
+        .revoke_all_token_families(current_user.user_id)
+        .await?;
+
+    state
+        .ws_service
+        .close_user_sessions(current_user.user_id, "logout_all")
+        .await;
+
+    info!(
+        "【Controller】退出所有设备成功: user_id={}, 吊销数={}",
+        current_user.user_id, revoked_count
+    );
+
+    Ok(success_response(LogoutAllResponse { revoked_count }))
+}
+
+// 屏蔽用户
+#[debug_handler]
+pub async fn block_user(
+    Extension(current_user): Extension<AuthUser>,
+    State(state): State<Arc<AppState>>,
+    Json(payload): Json<BlockUserRequest>,
+) -> Result<Json<ApiResponse<()>>, AppError> {
+    info!(
+        "【Controller】开始屏蔽用户: user_id={}, 目标用户ID={}",
+        current_user.user_id, payload.user_id
+    );
+
+    if payload.user_id <= 0 {
+        return Err(AppError::BadRequest("无效的用户ID".to_string()));
+    }
+
+    state
+        .block_service
+        .block_user(current_user.user_id, payload.user_id)
+        .await?;
+
+    Ok(success_response(()))
+}
+
+// 取消屏蔽用户
+#[debug_handler]
+pub async fn unblock_user(
+    Extension(current_user): Extension<AuthUser>,
+    State(state): State<Arc<AppState>>,
+    Json(payload): Json<BlockUserRequest>,
+) -> Result<Json<ApiResponse<()>>, AppError> {
+    info!(
+        "【Controller】开始取消屏蔽用户: user_id={}, 目标用户ID={}",
+        current_user.user_id, payload.user_id
+    );
+
+    state
+        .block_service
+        .unblock_user(current_user.user_id, payload.user_id)
+        .await?;
+
+    Ok(success_response(()))
+}
+
+// 获取屏蔽列表
+#[debug_handler]
+pub async fn list_blocked_users(
+    Extension(current_user): Extension<AuthUser>,
+    State(state): State<Arc<AppState>>,
+) -> Result<Json<ApiResponse<ListBlockedUsersResponse>>, AppError> {
+    info!(
+        "【Controller】开始获取屏蔽列表: user_id={}",
+        current_user.user_id
+    );
+
+    let users = state
+        .block_service
+        .list_blocked_users(current_user.user_id)
+        .await?
+        .into_iter()
+        .map(|row| BlockedUserInfo {
+            user_id: row.user_id,
+            nickname: row.nickname,
+            avatar_url: row.avatar_url,
+            blocked_at: row.blocked_at,
+        })
+        .collect();
+
+    Ok(success_response(ListBlockedUsersResponse { users }))
+}
+
+// 获取当前用户在整个集群内在线的设备列表
+#[debug_handler]
+pub async fn list_active_devices(
+    Extension(current_user): Extension<AuthUser>,
+    State(state): State<Arc<AppState>>,
+) -> Result<Json<ApiResponse<ListActiveDevicesResponse>>, AppError> {
+    info!(
+        "【Controller】开始获取在线设备列表: user_id={}",
+        current_user.user_id
+    );
+
+    let devices = state
+        .ws_service
+        .list_user_devices(current_user.user_id)
+        .await?
+        .into_iter()
+        .map(|device| ActiveDevice {
+            session_id: device.session_id.to_string(),
+            device: device.device,
+            connected_at: device.connected_at,
+            last_active: device.last_active,
+        })
+        .collect::<Vec<_>>();
+
+    info!(
+        "【Controller】获取在线设备列表成功: user_id={}, 设备数={}",
+        current_user.user_id,
+        devices.len()
+    );
+
+    Ok(success_response(ListActiveDevicesResponse { devices }))
+}
@@ -9,19 +9,32 @@ mod validators;
 mod ws;
 
 use axum::{Router, http::Method, routing::get};
-use config::app_config::AppConfig;
+use config::app_config::{
+    AppConfig, ClusterConfig, EventBusKind, GeocodeConfig, HotspotConfig, LocationHistoryConfig,
+    LoginGuardConfig, NotifierConfig, PasswordHashConfig, parse_duration_secs,
+};
 use config::db::Database;
 use dotenv::dotenv;
 use middleware::auth::{AppState, auth_middleware};
-use routes::{checkin_routes, group_routes, message_routes, user_routes};
+use middleware::rate_limit::rate_limit_middleware;
+use routes::{
+    admin_routes, checkin_routes, friend_routes, group_routes, location_routes, message_routes,
+    user_routes, well_known_routes,
+};
+use services::notifier::create_notifier;
 use services::{
-    CheckinService, ConfigService, GroupService, MessageService, UserService, WsService,
+    AccountService, AdminService, BlockService, CheckinService, ClusterService, ConfigService,
+    EventService, FriendService, GeocodeService, GroupService, HotspotService,
+    LocationHistoryService, LoginGuardService, MessageService, RateLimitService,
+    TokenDenylistService, UserService, WsService,
 };
-use std::{net::SocketAddr, sync::Arc};
+use std::{net::SocketAddr, sync::Arc, time::Duration};
 use tokio::net::TcpListener;
 use tower_http::cors::{Any, CorsLayer};
-use tracing::info;
+use tracing::{error, info, warn};
 use tracing_subscriber::{layer::SubscriberExt, util::SubscriberInitExt};
+use utils::jwt::JwtKeys;
+use ws::event_bus::create_event_bus;
 use ws::handler::ws_handler;
 use ws::session::SessionManager;
 
@@ -40,16 +53,36 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
 
     info!("初始化配置...");
     // 创建应用配置
-    let app_config = AppConfig::new(
-        std::env::var("JWT_SECRET").unwrap_or_else(|_| "devkey".into()),
-        std::env::var("JWT_EXPIRES_IN").unwrap_or_else(|_| "3600".into()),
+    let jwt_keys = Arc::new(load_jwt_keys()?);
+    info!("JWT签名密钥: kid={}", jwt_keys.signing_kid());
+
+    let mut app_config = AppConfig::new(
+        jwt_keys,
+        std::env::var("JWT_EXPIRES_IN")
+            .ok()
+            .and_then(|v| parse_duration_secs(&v))
+            .unwrap_or(3600),
         std::env::var("DATABASE_URL").expect("数据库连接URL未设置"),
         std::env::var("REDIS_URL").ok(),
         std::env::var("PORT")
             .unwrap_or_else(|_| "8080".into())
             .parse()
             .unwrap_or(8080),
+        EventBusKind::from_config(&std::env::var("EVENT_BUS").unwrap_or_default()),
+        password_hash_config(),
+    );
+    app_config.login_guard = login_guard_config();
+    app_config.trust_forwarded_for = std::env::var("TRUST_FORWARDED_FOR")
+        .map(|v| v == "true" || v == "1")
+        .unwrap_or(false);
+    app_config.notifier = NotifierConfig::from_config(
+        &std::env::var("NOTIFIER").unwrap_or_default(),
+        std::env::var("NOTIFIER_FILE").ok(),
     );
+    app_config.location_history = location_history_config();
+    app_config.geocode = geocode_config();
+    app_config.hotspot = hotspot_config();
+    app_config.cluster = cluster_config();
     let port = app_config.server_port;
 
     info!("连接数据库...");
@@ -60,14 +93,196 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
 
     // 创建服务
     let config_service = Arc::new(ConfigService::new(app_config.clone()));
-    let user_service = Arc::new(UserService::new(db.clone(), config_service.clone()));
+    let login_guard_service = Arc::new(LoginGuardService::new(&db, app_config.login_guard));
+    let notifier = create_notifier(&app_config.notifier);
+    let user_service = Arc::new(UserService::new(
+        db.clone(),
+        config_service.clone(),
+        login_guard_service.clone(),
+        notifier,
+    ));
     let group_service = Arc::new(GroupService::new(db.clone()));
     let message_service = Arc::new(MessageService::new(db.pg_pool.clone()));
     let checkin_service = Arc::new(CheckinService::new(db.clone()));
+    let event_service = Arc::new(EventService::new(db.clone()));
+    let token_denylist_service = Arc::new(TokenDenylistService::new(db.clone()));
+    let account_service = Arc::new(AccountService::new(db.clone()));
+    let block_service = Arc::new(BlockService::new(db.clone()));
+    let friend_service = Arc::new(FriendService::new(db.clone()));
+    let admin_service = Arc::new(AdminService::new(db.clone()));
+    let location_history_service = Arc::new(LocationHistoryService::new(
+        db.clone(),
+        app_config.location_history,
+    ));
+    let geocode_service = Arc::new(GeocodeService::new(db.clone(), app_config.geocode.clone()));
+    let hotspot_service = Arc::new(HotspotService::new(db.clone(), app_config.hotspot));
+    let cluster_service = Arc::new(ClusterService::new(db.clone(), app_config.cluster));
+    let rate_limit_service = Arc::new(RateLimitService::new(&db));
+
+    // 地名库为空时导入离线逆地理编码数据
+    match geocode_service.import_gazetteer().await {
+        Ok(0) => {}
+        Ok(count) => info!("已导入{}个地名库地点", count),
+        Err(e) => error!("导入地名库失败: {:?}", e),
+    }
+
+    // 创建事件总线，多节点部署时通过Redis分发WebSocket事件
+    info!("初始化事件总线: {:?}", app_config.event_bus);
+    let event_bus = create_event_bus(app_config.event_bus, db.redis_client.clone())?;
+
     let ws_service = Arc::new(WsService::new(
         session_manager.clone().as_ref().clone(),
         config_service.clone(),
+        event_service.clone(),
+        token_denylist_service.clone(),
+        event_bus,
+        db.clone(),
     ));
+    ws_service.spawn_event_dispatcher();
+
+    // 定期清理过期的用户事件日志
+    let purge_event_service = event_service.clone();
+    tokio::spawn(async move {
+        let mut interval = tokio::time::interval(Duration::from_secs(3600));
+        loop {
+            interval.tick().await;
+            match purge_event_service.purge_expired_events().await {
+                Ok(count) => info!("已清理{}条过期用户事件", count),
+                Err(e) => error!("清理过期用户事件失败: {:?}", e),
+            }
+        }
+    });
+
+    // 定期清理过期的令牌黑名单记录
+    let purge_denylist_service = token_denylist_service.clone();
+    tokio::spawn(async move {
+        let mut interval = tokio::time::interval(Duration::from_secs(3600));
+        loop {
+            interval.tick().await;
+            match purge_denylist_service.purge_expired().await {
+                Ok(count) => info!("已清理{}条过期令牌黑名单记录", count),
+                Err(e) => error!("清理过期令牌黑名单失败: {:?}", e),
+            }
+        }
+    });
+
+    // 定期清理内存中过期的登录尝试记录
+    let purge_login_guard_service = login_guard_service.clone();
+    tokio::spawn(async move {
+        let mut interval = tokio::time::interval(Duration::from_secs(300));
+        loop {
+            interval.tick().await;
+            let count = purge_login_guard_service.purge_expired();
+            if count > 0 {
+                info!("已清理{}条过期登录尝试记录", count);
+            }
+        }
+    });
+
+    // 定期清理内存中已补满的限流令牌桶
+    let purge_rate_limit_service = rate_limit_service.clone();
+    tokio::spawn(async move {
+        let mut interval = tokio::time::interval(Duration::from_secs(300));
+        loop {
+            interval.tick().await;
+            let count = purge_rate_limit_service.purge_expired();
+            if count > 0 {
+                info!("已清理{}个空闲限流令牌桶", count);
+            }
+        }
+    });
+
+    // 定期清理超过保留期的位置轨迹
+    let purge_location_history_service = location_history_service.clone();
+    tokio::spawn(async move {
+        let mut interval = tokio::time::interval(Duration::from_secs(3600));
+        loop {
+            interval.tick().await;
+            match purge_location_history_service.purge_expired().await {
+                Ok(count) => info!("已清理{}条过期位置轨迹", count),
+                Err(e) => error!("清理过期位置轨迹失败: {:?}", e),
+            }
+        }
+    });
+
+    // 定期刷新热力图预聚合数据，并清理超过保留期的部分
+    let rollup_hotspot_service = hotspot_service.clone();
+    let rollup_interval = Duration::from_secs(app_config.hotspot.rollup_interval_secs);
+    tokio::spawn(async move {
+        let mut interval = tokio::time::interval(rollup_interval);
+        loop {
+            interval.tick().await;
+            match rollup_hotspot_service.refresh_rollups().await {
+                Ok(Some(count)) => info!("已刷新{}个热力图单元", count),
+                Ok(None) => info!("其他节点正在刷新热力图，跳过本次统计"),
+                Err(e) => error!("刷新热力图失败: {:?}", e),
+            }
+            match rollup_hotspot_service.purge_expired().await {
+                Ok(count) => info!("已清理{}个过期热力图单元", count),
+                Err(e) => error!("清理过期热力图失败: {:?}", e),
+            }
+        }
+    });
+
+    // 定期清理内存中过期的瓦片聚合缓存
+    let purge_cluster_service = cluster_service.clone();
+    tokio::spawn(async move {
+        let mut interval = tokio::time::interval(Duration::from_secs(300));
+        loop {
+            interval.tick().await;
+            let count = purge_cluster_service.purge_expired();
+            if count > 0 {
+                info!("已清理{}个过期瓦片聚合缓存", count);
+            }
+        }
+    });
+
+    // 定期恢复已到期的位置可见范围，并向能重新看到这些用户的在线用户同步在线状态
+    let visibility_user_service = user_service.clone();
+    let visibility_ws_service = ws_service.clone();
+    tokio::spawn(async move {
+        let mut interval = tokio::time::interval(Duration::from_secs(60));
+        loop {
+            interval.tick().await;
+            match visibility_user_service
+                .reset_expired_location_visibility()
+                .await
+            {
+                Ok(user_ids) => {
+                    for user_id in &user_ids {
+                        if let Err(e) = visibility_ws_service.refresh_user_status(*user_id).await {
+                            error!("同步在线状态失败: user_id={}, error={:?}", user_id, e);
+                        }
+                    }
+                    if !user_ids.is_empty() {
+                        info!("已恢复{}个用户到期的位置可见范围", user_ids.len());
+                    }
+                }
+                Err(e) => error!("恢复位置可见范围失败: {:?}", e),
+            }
+        }
+    });
+
+    // 定期删除注销宽限期已结束的账号，并关闭其仍在线的连接
+    let purge_account_service = account_service.clone();
+    let purge_ws_service = ws_service.clone();
+    tokio::spawn(async move {
+        let mut interval = tokio::time::interval(Duration::from_secs(3600));
+        loop {
+            interval.tick().await;
+            match purge_account_service.purge_due_deletions().await {
+                Ok(user_ids) => {
+                    for user_id in &user_ids {
+                        purge_ws_service
+                            .close_user_sessions(*user_id, "account_deleted")
+                            .await;
+                    }
+                    info!("已删除{}个注销宽限期结束的账号", user_ids.len());
+                }
+                Err(e) => error!("删除注销账号失败: {:?}", e),
+            }
+        }
+    });
 
     // 创建统一的应用状态
     let state = Arc::new(AppState {
@@ -76,6 +291,17 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
         group_service,
         message_service,
         checkin_service,
+        account_service,
+        block_service,
+        friend_service,
+        admin_service,
+        location_history_service,
+        geocode_service,
+        hotspot_service,
+        cluster_service,
+        event_service,
+        token_denylist_service,
+        rate_limit_service,
         session_manager,
         ws_service,
     });
@@ -91,6 +317,10 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
     let group_routes = group_routes();
     let message_routes = message_routes();
     let checkin_routes = checkin_routes();
+    let friend_routes = friend_routes();
+    let location_routes = location_routes();
+    let admin_routes = admin_routes();
+    let well_known_routes = well_known_routes();
     let ws_route = Router::new().route("/", get(ws_handler));
 
     // 创建主路由
@@ -101,9 +331,18 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
                 .nest("/v1/user", user_routes)
                 .nest("/v1/group", group_routes)
                 .nest("/v1/chat", message_routes)
-                .nest("/v1/checkin", checkin_routes),
+                .nest("/v1/checkin", checkin_routes)
+                .nest("/v1/friend", friend_routes)
+                .nest("/v1/location", location_routes)
+                .nest("/admin", admin_routes),
         )
         .nest("/ws", ws_route)
+        .nest("/.well-known", well_known_routes)
+        // 限流在认证之后执行，以便按用户限流
+        .layer(axum::middleware::from_fn_with_state(
+            state.clone(),
+            rate_limit_middleware,
+        ))
         .layer(cors)
         .layer(axum::middleware::from_fn_with_state(
             state.clone(),
@@ -120,7 +359,153 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
     info!("启动服务器，监听端口 {}", port);
 
     let listener = TcpListener::bind(&addr).await?;
-    axum::serve(listener, app.with_state(state)).await?;
+    // 保留连接的对端地址，用于确定客户端IP
+    axum::serve(
+        listener,
+        app.with_state(state)
+            .into_make_service_with_connect_info::<SocketAddr>(),
+    )
+    .await?;
 
     Ok(())
 }
+
+// 从环境变量读取Argon2工作因子，未设置时使用默认值
+fn password_hash_config() -> PasswordHashConfig {
+    let defaults = PasswordHashConfig::default();
+    let read = |name: &str, default: u32| {
+        std::env::var(name)
+            .ok()
+            .and_then(|v| v.parse().ok())
+            .unwrap_or(default)
+    };
+
+    PasswordHashConfig {
+        memory_kib: read("ARGON2_MEMORY_KIB", defaults.memory_kib),
+        iterations: read("ARGON2_ITERATIONS", defaults.iterations),
+        parallelism: read("ARGON2_PARALLELISM", defaults.parallelism),
+    }
+}
+
+// 加载JWT密钥集：JWT_KEYS_DIR目录中的Ed25519密钥，JWT_ACTIVE_KID指定签名密钥
+fn load_jwt_keys() -> Result<JwtKeys, String> {
+    let keys_dir = std::env::var("JWT_KEYS_DIR").ok().filter(|s| !s.is_empty());
+
+    if std::env::var("JWT_SECRET").is_ok_and(|s| !s.is_empty()) {
+        warn!("JWT_SECRET已不再使用，旧版HS256令牌不再被接受");
+    }
+    if cfg!(not(debug_assertions)) && keys_dir.is_none() {
+        return Err("生产环境必须配置JWT_KEYS_DIR".to_string());
+    }
+
+    match keys_dir {
+        Some(dir) => {
+            let signing_kid = std::env::var("JWT_ACTIVE_KID").ok();
+            JwtKeys::load(std::path::Path::new(&dir), signing_kid.as_deref())
+        }
+        None => {
+            warn!("未配置JWT_KEYS_DIR，使用临时生成的开发密钥，重启后已签发的令牌全部失效");
+            JwtKeys::generate_ephemeral()
+        }
+    }
+}
+
+// 从环境变量读取防暴力破解参数，未设置时使用默认值
+fn login_guard_config() -> LoginGuardConfig {
+    let defaults = LoginGuardConfig::default();
+    let read_u32 = |name: &str, default: u32| {
+        std::env::var(name)
+            .ok()
+            .and_then(|v| v.parse().ok())
+            .unwrap_or(default)
+    };
+    let read_u64 = |name: &str, default: u64| {
+        std::env::var(name)
+            .ok()
+            .and_then(|v| v.parse().ok())
+            .unwrap_or(default)
+    };
+
+    LoginGuardConfig {
+        failure_window_secs: read_u64("LOGIN_FAILURE_WINDOW_SECS", defaults.failure_window_secs),
+        max_failures_per_login_id: read_u32(
+            "LOGIN_MAX_FAILURES_PER_ID",
+            defaults.max_failures_per_login_id,
+        ),
+        max_failures_per_ip: read_u32("LOGIN_MAX_FAILURES_PER_IP", defaults.max_failures_per_ip),
+        lockout_secs: read_u64("LOGIN_LOCKOUT_SECS", defaults.lockout_secs),
+        max_lockout_secs: read_u64("LOGIN_MAX_LOCKOUT_SECS", defaults.max_lockout_secs),
+        max_signups_per_ip: read_u32("SIGNUP_MAX_PER_IP", defaults.max_signups_per_ip),
+        signup_window_secs: read_u64("SIGNUP_WINDOW_SECS", defaults.signup_window_secs),
+    }
+}
+
+// 从环境变量读取位置轨迹的保留和去重参数，未设置时使用默认值
+fn location_history_config() -> LocationHistoryConfig {
+    let defaults = LocationHistoryConfig::default();
+
+    LocationHistoryConfig {
+        retention_days: std::env::var("LOCATION_HISTORY_RETENTION_DAYS")
+            .ok()
+            .and_then(|v| v.parse().ok())
+            .unwrap_or(defaults.retention_days),
+        dedup_distance_meters: std::env::var("LOCATION_HISTORY_DEDUP_METERS")
+            .ok()
+            .and_then(|v| v.parse().ok())
+            .unwrap_or(defaults.dedup_distance_meters),
+        dedup_interval_secs: std::env::var("LOCATION_HISTORY_DEDUP_SECS")
+            .ok()
+            .and_then(|v| v.parse().ok())
+            .unwrap_or(defaults.dedup_interval_secs),
+    }
+}
+
+// 从环境变量读取离线逆地理编码参数，未设置时使用默认值
+fn geocode_config() -> GeocodeConfig {
+    let defaults = GeocodeConfig::default();
+
+    GeocodeConfig {
+        gazetteer_file: std::env::var("GAZETTEER_FILE")
+            .ok()
+            .filter(|v| !v.is_empty()),
+        max_distance_meters: std::env::var("GEOCODE_MAX_DISTANCE_METERS")
+            .ok()
+            .and_then(|v| v.parse().ok())
+            .unwrap_or(defaults.max_distance_meters),
+    }
+}
+
+// 从环境变量读取热力图预聚合参数，未设置时使用默认值
+fn hotspot_config() -> HotspotConfig {
+    let defaults = HotspotConfig::default();
+
+    HotspotConfig {
+        rollup_interval_secs: std::env::var("HOTSPOT_ROLLUP_INTERVAL_SECS")
+            .ok()
+            .and_then(|v| v.parse().ok())
+            .filter(|&v| v > 0)
+            .unwrap_or(defaults.rollup_interval_secs),
+        retention_days: std::env::var("HOTSPOT_RETENTION_DAYS")
+            .ok()
+            .and_then(|v| v.parse().ok())
+            .unwrap_or(defaults.retention_days),
+    }
+}
+
+// 从环境变量读取地图标记聚合参数，未设置时使用默认值
+fn cluster_config() -> ClusterConfig {
+    let defaults = ClusterConfig::default();
+
+    ClusterConfig {
+        cache_ttl_secs: std::env::var("CLUSTER_CACHE_TTL_SECS")
+            .ok()
+            .and_then(|v| v.parse().ok())
+            .filter(|&v| v > 0)
+            .unwrap_or(defaults.cache_ttl_secs),
+        point_threshold: std::env::var("CLUSTER_POINT_THRESHOLD")
+            .ok()
+            .and_then(|v| v.parse().ok())
+            .filter(|&v: &i64| v > 0)
+            .unwrap_or(defaults.point_threshold),
+    }
+}
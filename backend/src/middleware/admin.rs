@@ -0,0 +1,35 @@
+use crate::{
+    models::{api::user::AuthUser, constants::platform_roles},
+    utils::response::AppError,
+};
+use axum::{body::Body, extract::Request, middleware::Next, response::Response};
+use tracing::warn;
+
+// 管理接口中间件，需放在认证中间件之内：只允许版主和管理员访问
+pub async fn admin_middleware(
+    request: Request<Body>,
+    next: Next,
+) -> Result<Response<Body>, AppError> {
+    let Some(user) = request.extensions().get::<AuthUser>() else {
+        return Err(AppError::Unauthorized("未认证".to_string()));
+    };
+
+    if !platform_roles::is_staff(&user.platform_role) {
+        warn!(
+            "非管理员访问管理接口: user_id={}, path={}",
+            user.user_id,
+            request.uri().path()
+        );
+        return Err(AppError::Forbidden("没有管理权限".to_string()));
+    }
+
+    Ok(next.run(request).await)
+}
+
+// 只有管理员（不含版主）可以执行的操作
+pub fn require_admin(user: &AuthUser) -> Result<(), AppError> {
+    if user.platform_role != platform_roles::ADMIN {
+        return Err(AppError::Forbidden("该操作需要管理员权限".to_string()));
+    }
+    Ok(())
+}
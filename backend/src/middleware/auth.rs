@@ -1,17 +1,22 @@
 use crate::{
     models::api::user::AuthUser,
-    services::{CheckinService, ConfigService, GroupService, MessageService, UserService, WsService},
-    utils::{jwt::verify_jwt_token, response::AppError},
+    services::{
+        AccountService, AdminService, BlockService, CheckinService, ClusterService, ConfigService,
+        EventService, FriendService, GeocodeService, GroupService, HotspotService,
+        LocationHistoryService, MessageService, RateLimitService, TokenDenylistService,
+        UserService, WsService,
+    },
+    utils::{client_ip::resolve_client_ip, jwt::verify_access_token, response::AppError},
     ws::session::SessionManager,
 };
 use axum::{
     body::Body,
-    extract::{Request, State},
+    extract::{ConnectInfo, Request, State},
     http::header,
     middleware::Next,
     response::Response,
 };
-use std::sync::Arc;
+use std::{net::SocketAddr, sync::Arc};
 use tracing::error;
 
 pub struct AppState {
@@ -20,6 +25,17 @@ pub struct AppState {
     pub group_service: Arc<GroupService>,
     pub message_service: Arc<MessageService>,
     pub checkin_service: Arc<CheckinService>,
+    pub account_service: Arc<AccountService>,
+    pub block_service: Arc<BlockService>,
+    pub friend_service: Arc<FriendService>,
+    pub admin_service: Arc<AdminService>,
+    pub location_history_service: Arc<LocationHistoryService>,
+    pub geocode_service: Arc<GeocodeService>,
+    pub hotspot_service: Arc<HotspotService>,
+    pub cluster_service: Arc<ClusterService>,
+    pub event_service: Arc<EventService>,
+    pub token_denylist_service: Arc<TokenDenylistService>,
+    pub rate_limit_service: Arc<RateLimitService>,
     pub session_manager: Arc<SessionManager>,
     pub ws_service: Arc<WsService>,
 }
@@ -30,6 +46,17 @@ pub async fn auth_middleware(
     mut request: Request<Body>,
     next: Next,
 ) -> Result<Response<Body>, AppError> {
+    // 记录客户端IP，供登录保护等功能使用
+    let peer = request
+        .extensions()
+        .get::<ConnectInfo<SocketAddr>>()
+        .map(|ConnectInfo(addr)| *addr);
+    let client_ip = resolve_client_ip(
+        request.headers(),
+        peer,
+        state.config_service.config.trust_forwarded_for,
+    );
+    request.extensions_mut().insert(client_ip);
     // 从路径判断是否需要认证
     let path = request.uri().path();
 
@@ -58,13 +85,18 @@ pub async fn auth_middleware(
         return Err(AppError::Unauthorized("令牌为空".to_string()));
     }
 
-    // 验证token - 从config_service获取jwt_secret
-    let jwt_secret = &state.config_service.config.jwt_secret;
-    let claims = match verify_jwt_token(token, jwt_secret) {
+    // 验证token - 从config_service获取JWT密钥集
+    let jwt_keys = &state.config_service.config.jwt_keys;
+    let claims = match verify_access_token(token, jwt_keys) {
         Ok(claims) => claims,
         Err(_) => return Err(AppError::Unauthorized("令牌无效".to_string())),
     };
 
+    // 检查令牌是否已注销
+    if state.token_denylist_service.is_revoked(&claims.jti).await? {
+        return Err(AppError::Unauthorized("令牌已注销".to_string()));
+    }
+
     // 获取用户ID
     let user_id = claims.sub;
 
@@ -78,10 +110,21 @@ pub async fn auth_middleware(
             AppError::InternalServerError("服务器错误".to_string())
         })?;
 
+    // 修改或重置密码前签发的令牌不再有效
+    if user.is_token_outdated(claims.iat) {
+        return Err(AppError::Unauthorized("令牌已失效，请重新登录".to_string()));
+    }
+
+    if user.banned_at.is_some() {
+        return Err(AppError::Forbidden("账号已被封禁".to_string()));
+    }
+
     request.extensions_mut().insert(AuthUser {
         user_id: user.user_id,
         nickname: user.nickname,
+        platform_role: user.platform_role,
     });
+    request.extensions_mut().insert(claims);
 
     // 执行请求
     Ok(next.run(request).await)
@@ -1 +1,3 @@
+pub mod admin;
 pub mod auth;
+pub mod rate_limit;
\ No newline at end of file
@@ -0,0 +1,41 @@
+use crate::{
+    config::routes::find_rate_limit_policy,
+    middleware::auth::AppState,
+    models::api::user::AuthUser,
+    utils::{client_ip::ClientIp, response::AppError},
+};
+use axum::{
+    body::Body,
+    extract::{Request, State},
+    middleware::Next,
+    response::Response,
+};
+use std::sync::Arc;
+
+// 限流中间件，需放在认证中间件之内：已认证请求按用户限流，公开路由按客户端IP限流
+pub async fn rate_limit_middleware(
+    State(state): State<Arc<AppState>>,
+    request: Request<Body>,
+    next: Next,
+) -> Result<Response<Body>, AppError> {
+    // 没有配置策略的路径不限流
+    let Some(policy) = find_rate_limit_policy(request.uri().path()) else {
+        return Ok(next.run(request).await);
+    };
+
+    let subject = match request.extensions().get::<AuthUser>() {
+        Some(user) => format!("user:{}", user.user_id),
+        None => {
+            let ip = request
+                .extensions()
+                .get::<ClientIp>()
+                .map(|ClientIp(ip)| ip.to_string())
+                .unwrap_or_default();
+            format!("ip:{}", ip)
+        }
+    };
+
+    state.rate_limit_service.acquire(&policy, &subject).await?;
+
+    Ok(next.run(request).await)
+}
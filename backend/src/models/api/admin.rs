@@ -0,0 +1,63 @@
+use crate::utils::response::{PaginatedResponse, Pagination};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+// 管理API请求/响应模型，reason会写入审计日志
+#[derive(Debug, Serialize, Deserialize)]
+pub struct AdminUserActionRequest {
+    pub user_id: i64,
+    pub reason: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct AdminGroupActionRequest {
+    pub group_id: String,
+    pub reason: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct AdminDeleteCheckinRequest {
+    pub checkin_id: String,
+    pub reason: Option<String>,
+}
+
+// target_type为group（群组消息）或conversation（私聊消息）
+#[derive(Debug, Serialize, Deserialize)]
+pub struct AdminDeleteMessageRequest {
+    pub message_id: String,
+    pub target_type: String,
+    pub reason: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct AdminListRequest {
+    #[serde(flatten)]
+    pub pagination: Pagination,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RegistrationInfo {
+    pub user_id: i64,
+    pub login_id: String,
+    pub nickname: String,
+    pub is_temp: bool,
+    pub platform_role: String,
+    pub banned_at: Option<DateTime<Utc>>,
+    pub created_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AuditLogInfo {
+    pub id: i64,
+    pub admin_id: Option<i64>,
+    pub admin_nickname: Option<String>,
+    pub action: String,
+    pub target_type: String,
+    pub target_id: String,
+    pub reason: Option<String>,
+    pub detail: Option<serde_json::Value>,
+    pub created_at: DateTime<Utc>,
+}
+
+pub type ListRegistrationsResponse = PaginatedResponse<RegistrationInfo>;
+pub type ListAuditLogsResponse = PaginatedResponse<AuditLogInfo>;
@@ -1,3 +1,4 @@
+use super::location::{CheckinMarker, ClusterLayer};
 use crate::utils::response::{PaginatedResponse, Pagination};
 use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
@@ -8,7 +9,8 @@ pub struct CreateCheckinRequest {
     pub description: String,
     pub latitude: f64,
     pub longitude: f64,
-    pub location_name: String,
+    // 未提供时根据坐标生成
+    pub location_name: Option<String>,
     pub tags: Option<Vec<String>>,
 }
 
@@ -39,11 +41,15 @@ pub struct SearchCheckinsByLocationRequest {
     pub radius: f64,
     #[serde(flatten)]
     pub pagination: Pagination,
+    // 指定时按该缩放级别聚合返回，不分页
+    pub zoom: Option<u32>,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
 pub struct UserCheckinsHistoryRequest {
     pub user_id: Option<i64>,
+    // 只返回好友的签到
+    pub friends_only: Option<bool>,
     #[serde(flatten)]
     pub pagination: Pagination,
 }
@@ -98,5 +104,14 @@ pub struct CheckInInfo {
 }
 
 pub type UserCheckinsHistoryResponse = PaginatedResponse<CheckInInfo>;
-pub type SearchCheckinByLocationResponse = PaginatedResponse<CheckInInfo>;
+
+// 聚合模式下items为空，结果在clusters中
+#[derive(Debug, Serialize, Deserialize)]
+pub struct SearchCheckinByLocationResponse {
+    #[serde(flatten)]
+    pub page: PaginatedResponse<CheckInInfo>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub clusters: Option<ClusterLayer<CheckinMarker>>,
+}
+
 pub type SearchCheckinsByTagsResponse = PaginatedResponse<CheckInInfo>;
@@ -0,0 +1,66 @@
+use crate::utils::response::{PaginatedResponse, Pagination};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+// 好友API请求/响应模型
+#[derive(Debug, Serialize, Deserialize)]
+pub struct SendFriendRequestRequest {
+    pub user_id: i64,
+}
+
+// status为pending表示等待对方处理，accepted表示对方已向自己发出申请，双方直接成为好友
+#[derive(Debug, Serialize, Deserialize)]
+pub struct SendFriendRequestResponse {
+    pub request_id: String,
+    pub status: String,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct RespondFriendRequestRequest {
+    pub request_id: String,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct RemoveFriendRequest {
+    pub user_id: i64,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ListFriendsRequest {
+    #[serde(flatten)]
+    pub pagination: Pagination,
+}
+
+// outgoing为true时返回自己发出的申请，否则返回收到的申请
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ListFriendRequestsRequest {
+    pub outgoing: Option<bool>,
+    #[serde(flatten)]
+    pub pagination: Pagination,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FriendInfo {
+    pub user_id: i64,
+    pub nickname: String,
+    pub avatar_url: Option<String>,
+    pub last_active: Option<DateTime<Utc>>,
+    pub online_status: String,
+    pub friends_since: DateTime<Utc>,
+}
+
+// user_id/nickname/avatar_url为对方的信息
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FriendRequestInfo {
+    pub request_id: String,
+    pub requester_id: i64,
+    pub addressee_id: i64,
+    pub user_id: i64,
+    pub nickname: String,
+    pub avatar_url: Option<String>,
+    pub status: String,
+    pub created_at: DateTime<Utc>,
+}
+
+pub type ListFriendsResponse = PaginatedResponse<FriendInfo>;
+pub type ListFriendRequestsResponse = PaginatedResponse<FriendRequestInfo>;
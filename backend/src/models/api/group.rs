@@ -2,6 +2,8 @@ use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
 use uuid::Uuid;
 
+use super::location::{ClusterLayer, GroupMarker};
+
 // API请求模型
 #[derive(Debug, Serialize, Deserialize)]
 pub struct CreateGroupRequest {
@@ -10,7 +12,8 @@ pub struct CreateGroupRequest {
     pub password: Option<String>,
     pub latitude: f64,
     pub longitude: f64,
-    pub location_name: String,
+    // 未提供时根据坐标生成
+    pub location_name: Option<String>,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -123,6 +126,8 @@ pub struct SearchGroupByLocationRequest {
     pub radius: f64,
     pub cursor: Option<i64>,
     pub limit: Option<i64>,
+    // 指定时按该缩放级别聚合返回，不分页
+    pub zoom: Option<u32>,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -142,6 +147,9 @@ pub struct SearchGroupByLocationResponse {
     pub groups: Vec<GroupInfo>,
     pub has_more: bool,
     pub next_cursor: Option<i64>,
+    // 聚合模式下groups为空，结果在clusters中
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub clusters: Option<ClusterLayer<GroupMarker>>,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -0,0 +1,134 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use super::{checkin::CheckInInfo, group::GroupInfo, user::UserInfo};
+use crate::utils::response::PaginatedResponse;
+
+// 位置API请求/响应模型
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ReverseGeocodeRequest {
+    pub latitude: f64,
+    pub longitude: f64,
+}
+
+// 缺失的行政级别为空，location_name为拼接后的地址名称
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ReverseGeocodeResponse {
+    pub country: String,
+    pub province: Option<String>,
+    pub city: Option<String>,
+    pub district: Option<String>,
+    pub location_name: String,
+    // 与地名库中最近地点的距离（米）
+    pub distance: f64,
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+pub struct GeoPoint {
+    pub latitude: f64,
+    pub longitude: f64,
+}
+
+// 地图可视范围，由西南角和东北角确定，不支持跨越180度经线
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+pub struct BoundingBox {
+    pub south_west: GeoPoint,
+    pub north_east: GeoPoint,
+}
+
+// 未指定时间时统计最近24小时；precision为geohash精度(1-6)，未指定时按范围大小自动选择
+#[derive(Debug, Serialize, Deserialize)]
+pub struct HeatmapRequest {
+    #[serde(flatten)]
+    pub bounds: BoundingBox,
+    pub start_time: Option<DateTime<Utc>>,
+    pub end_time: Option<DateTime<Utc>>,
+    pub precision: Option<u32>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct HeatmapCell {
+    pub geohash: String,
+    // 单元中心点
+    pub latitude: f64,
+    pub longitude: f64,
+    pub bounds: BoundingBox,
+    pub checkin_count: i64,
+    pub message_count: i64,
+    // 按小时累计的活跃用户数
+    pub active_user_count: i64,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct HeatmapResponse {
+    pub precision: u32,
+    pub cells: Vec<HeatmapCell>,
+}
+
+// types为要查询的内容类型(users/groups/checkins)，未指定时全部查询；
+// 三类内容各自独立分页，翻页时只需传入对应类型的游标；
+// 指定zoom时群组和签到改为按该缩放级别聚合返回，不分页
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ViewportRequest {
+    #[serde(flatten)]
+    pub bounds: BoundingBox,
+    pub types: Option<Vec<String>>,
+    pub user_cursor: Option<i64>,
+    pub group_cursor: Option<i64>,
+    pub checkin_cursor: Option<i64>,
+    // 每类内容的条数
+    pub limit: Option<i64>,
+    pub zoom: Option<u32>,
+}
+
+// 未查询的类型不返回；距离为到可视范围中心的距离
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ViewportResponse {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub users: Option<PaginatedResponse<UserInfo>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub groups: Option<PaginatedResponse<GroupInfo>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub checkins: Option<PaginatedResponse<CheckInInfo>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub group_clusters: Option<ClusterLayer<GroupMarker>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub checkin_clusters: Option<ClusterLayer<CheckinMarker>>,
+}
+
+// 聚合后的标记：位置为聚合内各点的平均坐标，bounds为包含所有点的最小范围
+#[derive(Debug, Serialize, Deserialize)]
+pub struct MapCluster {
+    pub count: i64,
+    pub latitude: f64,
+    pub longitude: f64,
+    pub bounds: BoundingBox,
+}
+
+// 未聚合的签到标记，详情通过签到接口获取
+#[derive(Debug, Serialize, Deserialize)]
+pub struct CheckinMarker {
+    pub id: i64,
+    pub checkin_id: String,
+    pub user_id: i64,
+    pub latitude: f64,
+    pub longitude: f64,
+}
+
+// 未聚合的群组标记，详情通过群组接口获取
+#[derive(Debug, Serialize, Deserialize)]
+pub struct GroupMarker {
+    pub group_id: Uuid,
+    pub name: String,
+    pub latitude: f64,
+    pub longitude: f64,
+}
+
+// 按瓦片网格聚合的结果：点数达到阈值的网格单元返回聚合，其余返回单个点
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ClusterLayer<T> {
+    pub zoom: u32,
+    pub clusters: Vec<MapCluster>,
+    pub points: Vec<T>,
+}
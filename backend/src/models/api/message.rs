@@ -90,6 +90,27 @@ pub struct MarkReadResponse {
     pub marked_count: Option<i64>,
 }
 
+// ============ 离线事件同步相关请求/响应模型 ============
+
+/// 同步离线事件请求
+/// - cursor: 客户端已收到的最后一个事件序列号，返回序列号更大的事件
+#[derive(Debug, Serialize, Deserialize)]
+pub struct SyncEventsRequest {
+    #[serde(flatten)]
+    pub pagination: Pagination,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct SyncEventItem {
+    pub seq: i64,
+    pub event_type: String,
+    /// 完整的WebSocket消息
+    pub payload: serde_json::Value,
+    pub created_at: DateTime<Utc>,
+}
+
+pub type SyncEventsResponse = PaginatedResponse<SyncEventItem>;
+
 // ============ 通用响应模型 ============
 
 #[derive(Debug, Serialize, Deserialize)]
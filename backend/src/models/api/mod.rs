@@ -1,5 +1,8 @@
 // API模型子模块
+pub mod admin;
 pub mod checkin;
+pub mod friend;
 pub mod group;
+pub mod location;
 pub mod message;
 pub mod user;
@@ -1,11 +1,12 @@
 use crate::utils::response::{PaginatedResponse, Pagination};
-use chrono::{DateTime, Utc};
+use chrono::{DateTime, NaiveDate, Utc};
 use serde::{Deserialize, Serialize};
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct AuthUser {
     pub user_id: i64,
     pub nickname: String,
+    pub platform_role: String,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -31,6 +32,13 @@ pub struct CreateTempUserRequest {
     pub nickname: Option<String>,
 }
 
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ClaimAccountRequest {
+    pub login_id: String,
+    pub password: String,
+    pub nickname: Option<String>,
+}
+
 #[derive(Debug, Serialize, Deserialize)]
 pub struct AuthResponse {
     pub user_id: i64,
@@ -47,16 +55,104 @@ pub struct RefreshTokenResponse {
     pub expires_at: DateTime<Utc>,
 }
 
+#[derive(Debug, Serialize, Deserialize)]
+pub struct LogoutRequest {
+    // 同时吊销该刷新令牌所在的令牌家族
+    #[serde(default)]
+    pub refresh_token: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ChangePasswordRequest {
+    pub current_password: String,
+    pub new_password: String,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct RequestPasswordResetRequest {
+    pub login_id: String,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ResetPasswordRequest {
+    pub login_id: String,
+    pub code: String,
+    pub new_password: String,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct LogoutAllResponse {
+    pub revoked_count: u64,
+}
+
+// 位置按对方的隐私级别模糊，hidden时坐标和距离为null，非精确级别不返回位置名称
 #[derive(Debug, Serialize, Deserialize)]
 pub struct UserInfo {
     pub user_id: i64,
     pub nickname: String,
     pub last_active: Option<DateTime<Utc>>,
-    pub latitude: f64,
-    pub longitude: f64,
-    pub distance: f64,
-    pub location_name: String,
+    pub latitude: Option<f64>,
+    pub longitude: Option<f64>,
+    pub distance: Option<f64>,
+    pub location_name: Option<String>,
     pub online_status: String,
+    pub avatar_url: Option<String>,
+    pub bio: Option<String>,
+    pub gender: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct UserProfileResponse {
+    pub user_id: i64,
+    pub login_id: String,
+    pub nickname: String,
+    pub avatar_url: Option<String>,
+    pub bio: Option<String>,
+    pub gender: Option<String>,
+    pub birthday: Option<NaiveDate>,
+    pub is_temp: bool,
+    pub location_privacy: String,
+    pub location_visibility: String,
+    pub location_visibility_expires_at: Option<DateTime<Utc>>,
+    pub created_at: DateTime<Utc>,
+    pub deletion_scheduled_at: Option<DateTime<Utc>>,
+}
+
+// visibility为everyone/friends_and_groups/invisible，duration_minutes为空表示一直有效
+#[derive(Debug, Serialize, Deserialize)]
+pub struct UpdateLocationVisibilityRequest {
+    pub visibility: String,
+    pub duration_minutes: Option<i64>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct UpdateLocationVisibilityResponse {
+    pub visibility: String,
+    // 到期后恢复为everyone
+    pub expires_at: Option<DateTime<Utc>>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct DeleteAccountRequest {
+    // 正式用户需要输入当前密码确认，临时用户可以不提供
+    pub password: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct DeleteAccountResponse {
+    pub deletion_scheduled_at: DateTime<Utc>,
+}
+
+// 未提供的字段保持不变，avatar_url/bio/gender/birthday传空字符串表示清除
+#[derive(Debug, Serialize, Deserialize)]
+pub struct UpdateProfileRequest {
+    pub nickname: Option<String>,
+    pub avatar_url: Option<String>,
+    pub bio: Option<String>,
+    pub gender: Option<String>,
+    pub birthday: Option<String>,
+    // exact/neighbourhood/city/hidden
+    pub location_privacy: Option<String>,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -64,6 +160,8 @@ pub struct SearchUserByLocationRequest {
     pub latitude: f64,
     pub longitude: f64,
     pub radius: f64,
+    // 只搜索好友
+    pub friends_only: Option<bool>,
     #[serde(flatten)]
     pub pagination: Pagination,
 }
@@ -88,5 +186,76 @@ pub type SearchUserByIdResponse = UserInfo;
 pub struct UpdateLocationRequest {
     pub latitude: f64,
     pub longitude: f64,
-    pub location_name: String,
+    // 未提供时根据坐标生成
+    pub location_name: Option<String>,
+    // 定位精度（米）、速度（米/秒）和方向（度），设备无法提供时省略
+    pub accuracy: Option<f64>,
+    pub speed: Option<f64>,
+    pub heading: Option<f64>,
+}
+
+// 未指定start_time/end_time时不限制对应方向的时间范围
+#[derive(Debug, Serialize, Deserialize)]
+pub struct LocationHistoryRequest {
+    pub start_time: Option<DateTime<Utc>>,
+    pub end_time: Option<DateTime<Utc>>,
+    #[serde(flatten)]
+    pub pagination: Pagination,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct LocationHistoryItem {
+    pub id: i64,
+    pub latitude: f64,
+    pub longitude: f64,
+    pub accuracy: Option<f64>,
+    pub speed: Option<f64>,
+    pub heading: Option<f64>,
+    pub location_name: Option<String>,
+    pub recorded_at: DateTime<Utc>,
+}
+
+pub type LocationHistoryResponse = PaginatedResponse<LocationHistoryItem>;
+
+// 未指定时间范围时删除全部轨迹
+#[derive(Debug, Serialize, Deserialize)]
+pub struct DeleteLocationHistoryRequest {
+    pub start_time: Option<DateTime<Utc>>,
+    pub end_time: Option<DateTime<Utc>>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct DeleteLocationHistoryResponse {
+    pub deleted_count: u64,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct BlockUserRequest {
+    pub user_id: i64,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct BlockedUserInfo {
+    pub user_id: i64,
+    pub nickname: String,
+    pub avatar_url: Option<String>,
+    pub blocked_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ListBlockedUsersResponse {
+    pub users: Vec<BlockedUserInfo>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ActiveDevice {
+    pub session_id: String,
+    pub device: Option<String>,
+    pub connected_at: DateTime<Utc>,
+    pub last_active: DateTime<Utc>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ListActiveDevicesResponse {
+    pub devices: Vec<ActiveDevice>,
 }
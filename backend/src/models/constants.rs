@@ -33,4 +33,92 @@ pub mod group_event_types {
     pub fn is_valid_event_type(event_type: &str) -> bool {
         all_event_types().contains(&event_type)
     }
-} 
\ No newline at end of file
+}
+
+// WebSocket输入状态/订阅的目标类型常量
+pub mod ws_target_types {
+    pub const CONVERSATION: &str = "conversation";
+    pub const GROUP: &str = "group";
+
+    // 检查目标类型是否有效
+    pub fn is_valid_target_type(target_type: &str) -> bool {
+        [CONVERSATION, GROUP].contains(&target_type)
+    }
+}
+
+// 用户性别常量
+pub mod genders {
+    pub const MALE: &str = "male";
+    pub const FEMALE: &str = "female";
+    pub const OTHER: &str = "other";
+
+    // 检查性别是否有效
+    pub fn is_valid_gender(gender: &str) -> bool {
+        [MALE, FEMALE, OTHER].contains(&gender)
+    }
+}
+
+// 位置隐私级别常量，决定其他用户看到的位置精度
+pub mod location_privacy {
+    pub const EXACT: &str = "exact";
+    pub const NEIGHBOURHOOD: &str = "neighbourhood";
+    pub const CITY: &str = "city";
+    pub const HIDDEN: &str = "hidden";
+
+    // 检查隐私级别是否有效
+    pub fn is_valid_level(level: &str) -> bool {
+        [EXACT, NEIGHBOURHOOD, CITY, HIDDEN].contains(&level)
+    }
+}
+
+// 位置可见范围常量，决定谁能在附近的人中看到用户及其在线状态
+pub mod location_visibility {
+    pub const EVERYONE: &str = "everyone";
+    pub const FRIENDS_AND_GROUPS: &str = "friends_and_groups";
+    pub const INVISIBLE: &str = "invisible";
+
+    // 检查可见范围是否有效
+    pub fn is_valid_visibility(visibility: &str) -> bool {
+        [EVERYONE, FRIENDS_AND_GROUPS, INVISIBLE].contains(&visibility)
+    }
+}
+
+// 好友申请状态常量
+pub mod friend_request_status {
+    pub const PENDING: &str = "pending";
+    pub const ACCEPTED: &str = "accepted";
+    pub const DECLINED: &str = "declined";
+}
+
+// 平台角色常量，与群组角色无关；普通用户的角色为user
+pub mod platform_roles {
+    pub const MODERATOR: &str = "moderator";
+    pub const ADMIN: &str = "admin";
+
+    // 检查角色是否可以使用管理接口
+    pub fn is_staff(role: &str) -> bool {
+        [MODERATOR, ADMIN].contains(&role)
+    }
+}
+
+// 管理操作审计日志的操作类型常量
+pub mod admin_actions {
+    pub const BAN_USER: &str = "ban_user";
+    pub const UNBAN_USER: &str = "unban_user";
+    pub const DELETE_GROUP: &str = "delete_group";
+    pub const DISSOLVE_GROUP: &str = "dissolve_group";
+    pub const DELETE_CHECKIN: &str = "delete_checkin";
+    pub const DELETE_MESSAGE: &str = "delete_message";
+}
+
+// 地图可视范围查询的内容类型常量
+pub mod viewport_types {
+    pub const USERS: &str = "users";
+    pub const GROUPS: &str = "groups";
+    pub const CHECKINS: &str = "checkins";
+
+    // 检查内容类型是否有效
+    pub fn is_valid_type(viewport_type: &str) -> bool {
+        [USERS, GROUPS, CHECKINS].contains(&viewport_type)
+    }
+}
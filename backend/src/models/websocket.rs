@@ -1,3 +1,6 @@
+use crate::models::api::message::{
+    MarkReadRequest, SendDirectMessageRequest, SendGroupMessageRequest,
+};
 use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
 
@@ -6,6 +9,9 @@ pub struct WebSocketMessage {
     pub message_type: WebSocketMessageType,
     pub data: Option<serde_json::Value>,
     pub timestamp: DateTime<Utc>,
+    /// 用户事件序列号，仅持久化的事件携带，用于断线重连补发
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub seq: Option<i64>,
 }
 
 #[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
@@ -26,6 +32,14 @@ pub enum WebSocketMessageType {
     GroupJoined,
     GroupLeft,
     NewCheckin,
+    Ack,
+    SyncRequired,
+    SessionRevoked,
+    ProfileUpdated,
+    FriendRequest,
+    FriendAccepted,
+    FriendRemoved,
+    GroupDissolved,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -39,6 +53,84 @@ pub struct WebSocketMessageResponse {
 #[derive(Debug, Serialize, Deserialize)]
 pub struct WebSocketConnectMessage {
     pub token: String,
+    /// 客户端设备描述（如 "iPhone 15"），用于在线设备列表展示
+    #[serde(default)]
+    pub device: Option<String>,
+    /// 客户端已收到的最后一个事件序列号，提供时服务端补发之后的事件
+    #[serde(default)]
+    pub last_seq: Option<i64>,
+}
+
+// ============ 客户端上行命令 ============
+
+/// 客户端上行命令
+/// - request_id: 客户端生成的关联ID，服务端在ack/error帧中原样返回
+/// - command: 命令类型，其余字段为命令参数
+#[derive(Debug, Deserialize)]
+pub struct WebSocketCommand {
+    pub request_id: String,
+    #[serde(flatten)]
+    pub command: WebSocketCommandType,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(tag = "command", rename_all = "snake_case")]
+pub enum WebSocketCommandType {
+    Ping,
+    Disconnect,
+    SendDirectMessage(SendDirectMessageRequest),
+    SendGroupMessage(SendGroupMessageRequest),
+    Typing(TypingCommand),
+    MarkRead(MarkReadRequest),
+    Subscribe(SubscribeCommand),
+}
+
+impl WebSocketCommandType {
+    /// 命令名称，与上行消息中的command字段一致
+    pub fn name(&self) -> &'static str {
+        match self {
+            WebSocketCommandType::Ping => "ping",
+            WebSocketCommandType::Disconnect => "disconnect",
+            WebSocketCommandType::SendDirectMessage(_) => "send_direct_message",
+            WebSocketCommandType::SendGroupMessage(_) => "send_group_message",
+            WebSocketCommandType::Typing(_) => "typing",
+            WebSocketCommandType::MarkRead(_) => "mark_read",
+            WebSocketCommandType::Subscribe(_) => "subscribe",
+        }
+    }
+}
+
+/// 输入状态命令
+#[derive(Debug, Serialize, Deserialize)]
+pub struct TypingCommand {
+    /// "conversation" 或 "group"
+    pub target_type: String,
+    /// 会话ID或群组ID
+    pub target_id: String,
+    pub is_typing: bool,
+}
+
+/// 订阅命令，设置当前连接关注的会话或群组，target_id为空时取消关注
+#[derive(Debug, Serialize, Deserialize)]
+pub struct SubscribeCommand {
+    /// "conversation" 或 "group"
+    pub target_type: String,
+    pub target_id: Option<String>,
+}
+
+/// 命令执行成功的ack帧数据
+#[derive(Debug, Serialize, Deserialize)]
+pub struct CommandAck {
+    pub request_id: String,
+    pub result: serde_json::Value,
+}
+
+/// 命令执行失败的error帧数据
+#[derive(Debug, Serialize, Deserialize)]
+pub struct CommandError {
+    pub request_id: Option<String>,
+    pub code: i32,
+    pub error: String,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -76,11 +168,19 @@ pub struct UserStatusEvent {
     pub last_active: DateTime<Utc>,
 }
 
+#[derive(Debug, Serialize, Deserialize)]
+pub struct UserProfileEvent {
+    pub user_id: i64,
+    pub nickname: String,
+    pub avatar_url: Option<String>,
+}
+
 #[derive(Debug, Serialize, Deserialize)]
 pub struct UserTypingEvent {
     pub user_id: i64,
     pub target_id: String,   // 会话ID或群组ID
     pub target_type: String, // "conversation" or "group"
+    pub is_typing: bool,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -101,6 +201,23 @@ pub struct GroupMembershipEvent {
     pub timestamp: DateTime<Utc>,
 }
 
+// 群组被平台管理员解散
+#[derive(Debug, Serialize, Deserialize)]
+pub struct GroupDissolvedEvent {
+    pub group_id: String,
+    pub group_name: String,
+    pub timestamp: DateTime<Utc>,
+}
+
+// 好友关系变化，user_id/nickname为发起操作的用户
+#[derive(Debug, Serialize, Deserialize)]
+pub struct FriendEvent {
+    pub request_id: Option<String>,
+    pub user_id: i64,
+    pub nickname: String,
+    pub timestamp: DateTime<Utc>,
+}
+
 #[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct NewCheckinEvent {
     pub checkin_id: String,
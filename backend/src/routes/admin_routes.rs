@@ -0,0 +1,23 @@
+use axum::{Router, routing::post};
+
+use crate::{
+    controllers::admin_controller,
+    middleware::{admin::admin_middleware, auth::AppState},
+};
+use std::sync::Arc;
+
+pub fn admin_routes() -> Router<Arc<AppState>> {
+    Router::new()
+        .route("/user/ban", post(admin_controller::ban_user))
+        .route("/user/unban", post(admin_controller::unban_user))
+        .route(
+            "/user/registrations",
+            post(admin_controller::list_registrations),
+        )
+        .route("/group/delete", post(admin_controller::delete_group))
+        .route("/group/dissolve", post(admin_controller::dissolve_group))
+        .route("/checkin/delete", post(admin_controller::delete_checkin))
+        .route("/message/delete", post(admin_controller::delete_message))
+        .route("/audit_logs", post(admin_controller::list_audit_logs))
+        .route_layer(axum::middleware::from_fn(admin_middleware))
+}
@@ -0,0 +1,14 @@
+use axum::{Router, routing::post};
+
+use crate::{controllers::friend_controller, middleware::auth::AppState};
+use std::sync::Arc;
+
+pub fn friend_routes() -> Router<Arc<AppState>> {
+    Router::new()
+        .route("/request", post(friend_controller::send_request))
+        .route("/accept", post(friend_controller::accept_request))
+        .route("/decline", post(friend_controller::decline_request))
+        .route("/remove", post(friend_controller::remove_friend))
+        .route("/list", post(friend_controller::list_friends))
+        .route("/requests", post(friend_controller::list_requests))
+}
@@ -0,0 +1,14 @@
+use axum::{Router, routing::post};
+
+use crate::{controllers::location_controller, middleware::auth::AppState};
+use std::sync::Arc;
+
+pub fn location_routes() -> Router<Arc<AppState>> {
+    Router::new()
+        .route(
+            "/reverse_geocode",
+            post(location_controller::reverse_geocode),
+        )
+        .route("/heatmap", post(location_controller::heatmap))
+        .route("/viewport", post(location_controller::viewport))
+}
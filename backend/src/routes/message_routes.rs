@@ -34,4 +34,5 @@ pub fn message_routes() -> Router<Arc<AppState>> {
             "/user/mark-read",
             post(message_controller::mark_messages_read),
         )
+        .route("/sync", post(message_controller::sync_events))
 }
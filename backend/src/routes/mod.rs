@@ -1,9 +1,17 @@
 pub mod user_routes;
+pub mod admin_routes;
 pub mod group_routes;
 pub mod message_routes;
 pub mod checkin_routes;
+pub mod friend_routes;
+pub mod location_routes;
+pub mod well_known_routes;
 
 pub use user_routes::*;
+pub use admin_routes::*;
 pub use group_routes::*;
 pub use message_routes::*;
-pub use checkin_routes::*; 
\ No newline at end of file
+pub use checkin_routes::*;
+pub use friend_routes::*;
+pub use location_routes::*;
+pub use well_known_routes::*; 
\ No newline at end of file
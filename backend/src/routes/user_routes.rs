@@ -8,12 +8,24 @@ pub fn user_routes() -> Router<Arc<AppState>> {
         .route("/register", post(user_controller::register))
         .route("/login", post(user_controller::login))
         .route("/create_temp_user", post(user_controller::create_temp_user))
+        .route("/claim_account", post(user_controller::claim_account))
         .route("/refresh-token", post(user_controller::refresh_token))
+        .route(
+            "/password_reset/request",
+            post(user_controller::request_password_reset),
+        )
+        .route(
+            "/password_reset/confirm",
+            post(user_controller::reset_password),
+        )
+        .route("/change_password", post(user_controller::change_password))
         .route(
             "/search_by_name",
             post(user_controller::search_user_by_name),
         )
         .route("/search_by_id", post(user_controller::search_user_by_id))
+        .route("/profile", post(user_controller::get_profile))
+        .route("/profile/update", post(user_controller::update_profile))
         .route(
             "/search_by_location",
             post(user_controller::search_user_by_location),
@@ -22,4 +34,28 @@ pub fn user_routes() -> Router<Arc<AppState>> {
             "/update_location",
             post(user_controller::update_location),
         )
+        .route("/location_history", post(user_controller::location_history))
+        .route(
+            "/location_history/delete",
+            post(user_controller::delete_location_history),
+        )
+        .route(
+            "/location_visibility",
+            post(user_controller::update_location_visibility),
+        )
+        .route(
+            "/active_devices",
+            post(user_controller::list_active_devices),
+        )
+        .route("/block", post(user_controller::block_user))
+        .route("/unblock", post(user_controller::unblock_user))
+        .route("/blocked_list", post(user_controller::list_blocked_users))
+        .route("/delete_account", post(user_controller::delete_account))
+        .route(
+            "/cancel_deletion",
+            post(user_controller::cancel_account_deletion),
+        )
+        .route("/export", post(user_controller::export_data))
+        .route("/logout", post(user_controller::logout))
+        .route("/logout_all", post(user_controller::logout_all))
 }
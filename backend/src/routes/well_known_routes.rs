@@ -0,0 +1,8 @@
+use axum::{Router, routing::get};
+
+use crate::{controllers::user_controller, middleware::auth::AppState};
+use std::sync::Arc;
+
+pub fn well_known_routes() -> Router<Arc<AppState>> {
+    Router::new().route("/jwks.json", get(user_controller::jwks))
+}
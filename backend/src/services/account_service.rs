@@ -0,0 +1,453 @@
+use crate::{
+    config::db::Database, models::constants::group_roles, services::user_service::UserProfileRow,
+    utils::response::AppError,
+};
+use chrono::{DateTime, Duration, Utc};
+use serde::Serialize;
+use sqlx::PgConnection;
+use tracing::{error, info, warn};
+use uuid::Uuid;
+
+// 申请注销后的宽限期天数，期间可以撤销
+pub const ACCOUNT_DELETION_GRACE_DAYS: i64 = 30;
+
+// =============== 数据导出结构体 ===============
+
+#[derive(Debug, Serialize)]
+pub struct ExportConversation {
+    pub conversation_id: Uuid,
+    pub peer_id: i64,
+    pub created_at: DateTime<Utc>,
+    pub last_message_at: Option<DateTime<Utc>>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ExportDirectMessage {
+    pub message_id: Uuid,
+    pub conversation_id: Uuid,
+    pub sender_id: i64,
+    pub recipient_id: i64,
+    pub content: String,
+    pub message_type: String,
+    pub created_at: DateTime<Utc>,
+    pub read_at: Option<DateTime<Utc>>,
+    pub latitude: f64,
+    pub longitude: f64,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ExportGroupMessage {
+    pub message_id: Uuid,
+    pub group_id: Uuid,
+    pub content: String,
+    pub message_type: String,
+    pub created_at: DateTime<Utc>,
+    pub latitude: f64,
+    pub longitude: f64,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ExportCheckin {
+    pub checkin_id: Uuid,
+    pub description: Option<String>,
+    pub latitude: f64,
+    pub longitude: f64,
+    pub location_name: Option<String>,
+    pub created_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ExportLocation {
+    pub latitude: f64,
+    pub longitude: f64,
+    pub location_name: Option<String>,
+    pub updated_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ExportLocationHistory {
+    pub latitude: f64,
+    pub longitude: f64,
+    pub accuracy: Option<f64>,
+    pub speed: Option<f64>,
+    pub heading: Option<f64>,
+    pub location_name: Option<String>,
+    pub recorded_at: DateTime<Utc>,
+}
+
+// 用户个人数据导出档案
+#[derive(Debug, Serialize)]
+pub struct UserDataExport {
+    pub exported_at: DateTime<Utc>,
+    pub profile: UserProfileRow,
+    pub conversations: Vec<ExportConversation>,
+    pub direct_messages: Vec<ExportDirectMessage>,
+    pub group_messages: Vec<ExportGroupMessage>,
+    pub checkins: Vec<ExportCheckin>,
+    pub locations: Vec<ExportLocation>,
+    pub location_history: Vec<ExportLocationHistory>,
+}
+
+#[derive(Debug)]
+pub struct AccountService {
+    db: Database,
+}
+
+impl AccountService {
+    pub fn new(db: Database) -> Self {
+        Self { db }
+    }
+
+    // =============== 账号注销 ===============
+
+    // 申请注销账号，返回计划删除时间
+    pub async fn request_deletion(&self, user_id: i64) -> Result<DateTime<Utc>, AppError> {
+        let scheduled_at = Utc::now() + Duration::days(ACCOUNT_DELETION_GRACE_DAYS);
+
+        // 重复申请时保留最初的计划删除时间
+        let scheduled_at = sqlx::query_scalar!(
+            r#"
+            UPDATE users
+            SET deletion_scheduled_at = COALESCE(deletion_scheduled_at, $1), updated_at = $2
+            WHERE user_id = $3
+            RETURNING deletion_scheduled_at as "deletion_scheduled_at!"
+            "#,
+            scheduled_at,
+            Utc::now(),
+            user_id
+        )
+        .fetch_optional(&self.db.pg_pool)
+        .await
+        .map_err(|e| {
+            error!("申请注销账号失败: {:?}", e);
+            AppError::InternalServerError("申请注销账号失败".to_string())
+        })?
+        .ok_or_else(|| AppError::NotFound("用户不存在".to_string()))?;
+
+        info!(
+            "【Service】用户申请注销账号: user_id={}, 计划删除时间={}",
+            user_id, scheduled_at
+        );
+
+        Ok(scheduled_at)
+    }
+
+    // 撤销注销申请
+    pub async fn cancel_deletion(&self, user_id: i64) -> Result<(), AppError> {
+        let result = sqlx::query!(
+            r#"
+            UPDATE users
+            SET deletion_scheduled_at = NULL, updated_at = $1
+            WHERE user_id = $2 AND deletion_scheduled_at IS NOT NULL
+            "#,
+            Utc::now(),
+            user_id
+        )
+        .execute(&self.db.pg_pool)
+        .await
+        .map_err(|e| {
+            error!("撤销注销申请失败: {:?}", e);
+            AppError::InternalServerError("撤销注销申请失败".to_string())
+        })?;
+
+        if result.rows_affected() == 0 {
+            return Err(AppError::BadRequest("当前没有待处理的注销申请".to_string()));
+        }
+
+        info!("【Service】用户撤销注销申请: user_id={}", user_id);
+        Ok(())
+    }
+
+    // 删除宽限期已结束的账号，返回已删除的用户ID
+    pub async fn purge_due_deletions(&self) -> Result<Vec<i64>, AppError> {
+        let user_ids = sqlx::query_scalar!(
+            r#"
+            SELECT user_id FROM users
+            WHERE deletion_scheduled_at IS NOT NULL AND deletion_scheduled_at <= NOW()
+            "#
+        )
+        .fetch_all(&self.db.pg_pool)
+        .await
+        .map_err(|e| AppError::InternalServerError(format!("查询待删除账号失败: {:?}", e)))?;
+
+        let mut deleted = Vec::with_capacity(user_ids.len());
+        for user_id in user_ids {
+            match self.delete_account(user_id).await {
+                Ok(()) => deleted.push(user_id),
+                Err(e) => error!("删除账号失败: user_id={}, error={:?}", user_id, e),
+            }
+        }
+
+        Ok(deleted)
+    }
+
+    // 删除账号及其内容，删除前先移交该用户拥有的群组
+    async fn delete_account(&self, user_id: i64) -> Result<(), AppError> {
+        let mut tx = self.db.pg_pool.begin().await.map_err(|e| {
+            error!("开启事务失败: {:?}", e);
+            AppError::InternalServerError("数据库操作失败".to_string())
+        })?;
+
+        // 锁定用户并确认注销申请仍然有效，避免与撤销操作竞争
+        let still_scheduled = sqlx::query_scalar!(
+            r#"
+            SELECT user_id FROM users
+            WHERE user_id = $1 AND deletion_scheduled_at <= NOW()
+            FOR UPDATE
+            "#,
+            user_id
+        )
+        .fetch_optional(&mut *tx)
+        .await
+        .map_err(|e| AppError::InternalServerError(format!("查询账号失败: {:?}", e)))?;
+        if still_scheduled.is_none() {
+            return Ok(());
+        }
+
+        Self::hand_off_owned_groups(&mut tx, user_id).await?;
+
+        // 删除用户产生的内容
+        sqlx::query!(r#"DELETE FROM checkin_likes WHERE user_id = $1"#, user_id)
+            .execute(&mut *tx)
+            .await
+            .map_err(|e| AppError::InternalServerError(format!("删除点赞记录失败: {:?}", e)))?;
+
+        sqlx::query!(r#"DELETE FROM checkins WHERE user_id = $1"#, user_id)
+            .execute(&mut *tx)
+            .await
+            .map_err(|e| AppError::InternalServerError(format!("删除签到失败: {:?}", e)))?;
+
+        sqlx::query!(
+            r#"DELETE FROM group_messages WHERE sender_id = $1"#,
+            user_id
+        )
+        .execute(&mut *tx)
+        .await
+        .map_err(|e| AppError::InternalServerError(format!("删除群组消息失败: {:?}", e)))?;
+
+        sqlx::query!(
+            r#"DELETE FROM direct_messages WHERE sender_id = $1 OR recipient_id = $1"#,
+            user_id
+        )
+        .execute(&mut *tx)
+        .await
+        .map_err(|e| AppError::InternalServerError(format!("删除私信失败: {:?}", e)))?;
+
+        sqlx::query!(r#"DELETE FROM user_locations WHERE user_id = $1"#, user_id)
+            .execute(&mut *tx)
+            .await
+            .map_err(|e| AppError::InternalServerError(format!("删除位置记录失败: {:?}", e)))?;
+
+        // 其余关联数据（会话、群组成员、令牌、事件日志等）随用户记录级联删除
+        sqlx::query!(r#"DELETE FROM users WHERE user_id = $1"#, user_id)
+            .execute(&mut *tx)
+            .await
+            .map_err(|e| AppError::InternalServerError(format!("删除用户失败: {:?}", e)))?;
+
+        tx.commit().await.map_err(|e| {
+            error!("提交事务失败: {:?}", e);
+            AppError::InternalServerError("数据库操作失败".to_string())
+        })?;
+
+        info!("【Service】账号已删除: user_id={}", user_id);
+        Ok(())
+    }
+
+    // 将用户拥有的群组移交给管理员或最早加入的成员，没有其他成员的群组直接删除
+    async fn hand_off_owned_groups(conn: &mut PgConnection, user_id: i64) -> Result<(), AppError> {
+        let group_ids = sqlx::query_scalar!(
+            r#"SELECT group_id FROM groups WHERE owner_id = $1"#,
+            user_id
+        )
+        .fetch_all(&mut *conn)
+        .await
+        .map_err(|e| AppError::InternalServerError(format!("查询用户群组失败: {:?}", e)))?;
+
+        for group_id in group_ids {
+            let successor_id = sqlx::query_scalar!(
+                r#"
+                SELECT user_id FROM group_members
+                WHERE group_id = $1 AND user_id != $2
+                ORDER BY (role = $3) DESC, joined_at ASC
+                LIMIT 1
+                "#,
+                group_id,
+                user_id,
+                group_roles::ADMIN
+            )
+            .fetch_optional(&mut *conn)
+            .await
+            .map_err(|e| AppError::InternalServerError(format!("查询群组成员失败: {:?}", e)))?;
+
+            let Some(successor_id) = successor_id else {
+                sqlx::query!(r#"DELETE FROM groups WHERE group_id = $1"#, group_id)
+                    .execute(&mut *conn)
+                    .await
+                    .map_err(|e| AppError::InternalServerError(format!("删除群组失败: {:?}", e)))?;
+                warn!(
+                    "群组没有其他成员，随群主账号一起删除: group_id={}",
+                    group_id
+                );
+                continue;
+            };
+
+            sqlx::query!(
+                r#"UPDATE groups SET owner_id = $1, updated_at = $2 WHERE group_id = $3"#,
+                successor_id,
+                Utc::now(),
+                group_id
+            )
+            .execute(&mut *conn)
+            .await
+            .map_err(|e| AppError::InternalServerError(format!("移交群组失败: {:?}", e)))?;
+
+            sqlx::query!(
+                r#"UPDATE group_members SET role = $1 WHERE group_id = $2 AND user_id = $3"#,
+                group_roles::OWNER,
+                group_id,
+                successor_id
+            )
+            .execute(&mut *conn)
+            .await
+            .map_err(|e| AppError::InternalServerError(format!("更新成员角色失败: {:?}", e)))?;
+
+            info!(
+                "群组已移交: group_id={}, 原群主={}, 新群主={}",
+                group_id, user_id, successor_id
+            );
+        }
+
+        Ok(())
+    }
+
+    // =============== 数据导出 ===============
+
+    // 导出用户的个人数据
+    pub async fn export_user_data(
+        &self,
+        profile: UserProfileRow,
+    ) -> Result<UserDataExport, AppError> {
+        let user_id = profile.user_id;
+        let pool = &self.db.pg_pool;
+        let map_err = |e: sqlx::Error| {
+            error!("导出用户数据失败: {:?}", e);
+            AppError::InternalServerError("导出用户数据失败".to_string())
+        };
+
+        let conversations = sqlx::query_as!(
+            ExportConversation,
+            r#"
+            SELECT
+                conversation_id,
+                CASE WHEN user1_id = $1 THEN user2_id ELSE user1_id END as "peer_id!",
+                created_at,
+                last_message_at
+            FROM conversations
+            WHERE user1_id = $1 OR user2_id = $1
+            ORDER BY created_at ASC
+            "#,
+            user_id
+        )
+        .fetch_all(pool)
+        .await
+        .map_err(map_err)?;
+
+        // 收到的消息按发送者的隐私级别模糊位置
+        let direct_messages = sqlx::query_as!(
+            ExportDirectMessage,
+            r#"
+            SELECT dm.message_id, dm.conversation_id, dm.sender_id, dm.recipient_id, dm.content,
+                   dm.message_type, dm.created_at, dm.read_at,
+                   CASE WHEN dm.sender_id = $1 THEN dm.latitude
+                        ELSE COALESCE(fuzz_coordinate(dm.latitude, u.location_privacy), 0)
+                   END as "latitude!: f64",
+                   CASE WHEN dm.sender_id = $1 THEN dm.longitude
+                        ELSE COALESCE(fuzz_coordinate(dm.longitude, u.location_privacy), 0)
+                   END as "longitude!: f64"
+            FROM direct_messages dm
+            JOIN users u ON u.user_id = dm.sender_id
+            WHERE dm.sender_id = $1 OR dm.recipient_id = $1
+            ORDER BY dm.created_at ASC
+            "#,
+            user_id
+        )
+        .fetch_all(pool)
+        .await
+        .map_err(map_err)?;
+
+        let group_messages = sqlx::query_as!(
+            ExportGroupMessage,
+            r#"
+            SELECT message_id, group_id, content, message_type, created_at, latitude, longitude
+            FROM group_messages
+            WHERE sender_id = $1
+            ORDER BY created_at ASC
+            "#,
+            user_id
+        )
+        .fetch_all(pool)
+        .await
+        .map_err(map_err)?;
+
+        let checkins = sqlx::query_as!(
+            ExportCheckin,
+            r#"
+            SELECT checkin_id, description, latitude, longitude, location_name, created_at
+            FROM checkins
+            WHERE user_id = $1
+            ORDER BY created_at ASC
+            "#,
+            user_id
+        )
+        .fetch_all(pool)
+        .await
+        .map_err(map_err)?;
+
+        let locations = sqlx::query_as!(
+            ExportLocation,
+            r#"
+            SELECT latitude, longitude, location_name, updated_at
+            FROM user_locations
+            WHERE user_id = $1
+            ORDER BY updated_at ASC
+            "#,
+            user_id
+        )
+        .fetch_all(pool)
+        .await
+        .map_err(map_err)?;
+
+        let location_history = sqlx::query_as!(
+            ExportLocationHistory,
+            r#"
+            SELECT latitude, longitude, accuracy, speed, heading, location_name, recorded_at
+            FROM user_location_history
+            WHERE user_id = $1
+            ORDER BY recorded_at ASC
+            "#,
+            user_id
+        )
+        .fetch_all(pool)
+        .await
+        .map_err(map_err)?;
+
+        info!(
+            "【Service】导出用户数据: user_id={}, 私信{}条, 群消息{}条, 签到{}条",
+            user_id,
+            direct_messages.len(),
+            group_messages.len(),
+            checkins.len()
+        );
+
+        Ok(UserDataExport {
+            exported_at: Utc::now(),
+            profile,
+            conversations,
+            direct_messages,
+            group_messages,
+            checkins,
+            locations,
+            location_history,
+        })
+    }
+}
@@ -0,0 +1,497 @@
+use crate::{
+    config::db::Database,
+    models::constants::{admin_actions, platform_roles, ws_target_types},
+    utils::response::{AppError, paginate},
+};
+use chrono::{DateTime, Utc};
+use serde_json::json;
+use sqlx::PgConnection;
+use tracing::{error, info};
+use uuid::Uuid;
+
+// 最近注册的用户
+#[derive(Debug, Clone)]
+pub struct RegistrationRow {
+    pub id: i64,
+    pub user_id: i64,
+    pub login_id: String,
+    pub nickname: String,
+    pub is_temp: bool,
+    pub platform_role: String,
+    pub banned_at: Option<DateTime<Utc>>,
+    pub created_at: DateTime<Utc>,
+}
+
+// 审计日志
+#[derive(Debug, Clone)]
+pub struct AuditLogRow {
+    pub id: i64,
+    pub admin_id: Option<i64>,
+    pub admin_nickname: Option<String>,
+    pub action: String,
+    pub target_type: String,
+    pub target_id: String,
+    pub reason: Option<String>,
+    pub detail: Option<serde_json::Value>,
+    pub created_at: DateTime<Utc>,
+}
+
+// 被删除的群组，member_ids为删除前的成员
+#[derive(Debug, Clone)]
+pub struct DeletedGroup {
+    pub name: String,
+    pub member_ids: Vec<i64>,
+}
+
+// 执行管理操作的管理员
+#[derive(Debug, Clone, Copy)]
+pub struct AdminActor<'a> {
+    pub user_id: i64,
+    pub platform_role: &'a str,
+    pub reason: Option<&'a str>,
+}
+
+// 一条待写入的审计日志
+struct AuditEntry<'a> {
+    action: &'a str,
+    target_type: &'a str,
+    target_id: String,
+    detail: serde_json::Value,
+}
+
+// 平台管理：封禁用户、删除违规内容，每个操作与其审计日志在同一事务中写入
+#[derive(Debug)]
+pub struct AdminService {
+    db: Database,
+}
+
+impl AdminService {
+    pub fn new(db: Database) -> Self {
+        Self { db }
+    }
+
+    // =============== 用户管理 ===============
+
+    // 封禁用户并吊销其所有刷新令牌和已签发的访问令牌；版主只能封禁普通用户，管理员不能被封禁
+    pub async fn ban_user(&self, actor: AdminActor<'_>, user_id: i64) -> Result<(), AppError> {
+        if actor.user_id == user_id {
+            return Err(AppError::BadRequest("不能封禁自己".to_string()));
+        }
+
+        let mut tx = self.db.pg_pool.begin().await.map_err(|e| {
+            error!("开启事务失败: {:?}", e);
+            AppError::InternalServerError("数据库操作失败".to_string())
+        })?;
+
+        let target = sqlx::query!(
+            r#"SELECT platform_role, banned_at FROM users WHERE user_id = $1 FOR UPDATE"#,
+            user_id
+        )
+        .fetch_optional(&mut *tx)
+        .await
+        .map_err(|e| {
+            error!("查询用户失败: {:?}", e);
+            AppError::InternalServerError("封禁用户失败".to_string())
+        })?
+        .ok_or_else(|| AppError::NotFound("用户不存在".to_string()))?;
+
+        if target.platform_role == platform_roles::ADMIN {
+            return Err(AppError::Forbidden("不能封禁管理员".to_string()));
+        }
+        if target.platform_role == platform_roles::MODERATOR
+            && actor.platform_role != platform_roles::ADMIN
+        {
+            return Err(AppError::Forbidden("只有管理员可以封禁版主".to_string()));
+        }
+        if target.banned_at.is_some() {
+            return Err(AppError::Conflict("用户已被封禁".to_string()));
+        }
+
+        // 封禁前签发的访问令牌同时失效，解封后也不会恢复
+        let now = Utc::now();
+        sqlx::query!(
+            r#"
+            UPDATE users
+            SET banned_at = $1, ban_reason = $2, tokens_revoked_at = $1, updated_at = $1
+            WHERE user_id = $3
+            "#,
+            now,
+            actor.reason,
+            user_id
+        )
+        .execute(&mut *tx)
+        .await
+        .map_err(|e| {
+            error!("封禁用户失败: {:?}", e);
+            AppError::InternalServerError("封禁用户失败".to_string())
+        })?;
+
+        let revoked = sqlx::query!(
+            r#"
+            UPDATE refresh_token_families
+            SET revoked_at = $1, revoked_reason = 'ban'
+            WHERE user_id = $2 AND revoked_at IS NULL
+            "#,
+            now,
+            user_id
+        )
+        .execute(&mut *tx)
+        .await
+        .map_err(|e| {
+            error!("吊销刷新令牌家族失败: {:?}", e);
+            AppError::InternalServerError("封禁用户失败".to_string())
+        })?;
+
+        let entry = AuditEntry {
+            action: admin_actions::BAN_USER,
+            target_type: "user",
+            target_id: user_id.to_string(),
+            detail: json!({ "revoked_token_families": revoked.rows_affected() }),
+        };
+        Self::record_audit(&mut tx, actor, entry).await?;
+        tx.commit().await.map_err(|e| {
+            error!("提交事务失败: {:?}", e);
+            AppError::InternalServerError("数据库操作失败".to_string())
+        })?;
+
+        info!(
+            "【Service】封禁用户成功: admin_id={}, user_id={}",
+            actor.user_id, user_id
+        );
+        Ok(())
+    }
+
+    // 解除封禁，用户需重新登录
+    pub async fn unban_user(&self, actor: AdminActor<'_>, user_id: i64) -> Result<(), AppError> {
+        let mut tx = self.db.pg_pool.begin().await.map_err(|e| {
+            error!("开启事务失败: {:?}", e);
+            AppError::InternalServerError("数据库操作失败".to_string())
+        })?;
+
+        let result = sqlx::query!(
+            r#"
+            UPDATE users
+            SET banned_at = NULL, ban_reason = NULL, updated_at = $1
+            WHERE user_id = $2 AND banned_at IS NOT NULL
+            "#,
+            Utc::now(),
+            user_id
+        )
+        .execute(&mut *tx)
+        .await
+        .map_err(|e| {
+            error!("解除封禁失败: {:?}", e);
+            AppError::InternalServerError("解除封禁失败".to_string())
+        })?;
+        if result.rows_affected() == 0 {
+            return Err(AppError::BadRequest("用户不存在或未被封禁".to_string()));
+        }
+
+        let entry = AuditEntry {
+            action: admin_actions::UNBAN_USER,
+            target_type: "user",
+            target_id: user_id.to_string(),
+            detail: json!({}),
+        };
+        Self::record_audit(&mut tx, actor, entry).await?;
+        tx.commit().await.map_err(|e| {
+            error!("提交事务失败: {:?}", e);
+            AppError::InternalServerError("数据库操作失败".to_string())
+        })?;
+
+        info!(
+            "【Service】解除封禁成功: admin_id={}, user_id={}",
+            actor.user_id, user_id
+        );
+        Ok(())
+    }
+
+    // 获取最近注册的用户，按注册时间倒序
+    pub async fn list_registrations(
+        &self,
+        cursor: i64,
+        limit: i64,
+    ) -> Result<(Vec<RegistrationRow>, bool, Option<i64>), AppError> {
+        let rows = sqlx::query_as!(
+            RegistrationRow,
+            r#"
+            SELECT
+                id,
+                user_id,
+                login_id as "login_id!",
+                nickname as "nickname!",
+                is_temp,
+                platform_role,
+                banned_at,
+                created_at
+            FROM users
+            WHERE ($1::bigint = 0 OR id < $1::bigint)
+            ORDER BY id DESC
+            LIMIT $2
+            "#,
+            cursor,
+            limit + 1
+        )
+        .fetch_all(&self.db.pg_pool)
+        .await
+        .map_err(|e| {
+            error!("获取注册用户失败: {:?}", e);
+            AppError::InternalServerError("获取注册用户失败".to_string())
+        })?;
+
+        Ok(paginate(rows, limit, |row| row.id))
+    }
+
+    // =============== 内容管理 ===============
+
+    // 删除群组及其成员、消息和事件记录，返回群组名称和原成员
+    pub async fn delete_group(
+        &self,
+        actor: AdminActor<'_>,
+        group_id: Uuid,
+        dissolve: bool,
+    ) -> Result<DeletedGroup, AppError> {
+        let mut tx = self.db.pg_pool.begin().await.map_err(|e| {
+            error!("开启事务失败: {:?}", e);
+            AppError::InternalServerError("数据库操作失败".to_string())
+        })?;
+
+        let group = sqlx::query!(
+            r#"SELECT name, owner_id FROM groups WHERE group_id = $1 FOR UPDATE"#,
+            group_id
+        )
+        .fetch_optional(&mut *tx)
+        .await
+        .map_err(|e| {
+            error!("查询群组失败: {:?}", e);
+            AppError::InternalServerError("删除群组失败".to_string())
+        })?
+        .ok_or_else(|| AppError::NotFound("群组不存在".to_string()))?;
+
+        let member_ids = sqlx::query_scalar!(
+            r#"SELECT user_id FROM group_members WHERE group_id = $1"#,
+            group_id
+        )
+        .fetch_all(&mut *tx)
+        .await
+        .map_err(|e| {
+            error!("查询群组成员失败: {:?}", e);
+            AppError::InternalServerError("删除群组失败".to_string())
+        })?;
+
+        // 成员、消息和事件记录随群组级联删除
+        sqlx::query!(r#"DELETE FROM groups WHERE group_id = $1"#, group_id)
+            .execute(&mut *tx)
+            .await
+            .map_err(|e| {
+                error!("删除群组失败: {:?}", e);
+                AppError::InternalServerError("删除群组失败".to_string())
+            })?;
+
+        let entry = AuditEntry {
+            action: if dissolve {
+                admin_actions::DISSOLVE_GROUP
+            } else {
+                admin_actions::DELETE_GROUP
+            },
+            target_type: "group",
+            target_id: group_id.to_string(),
+            detail: json!({
+                "name": group.name,
+                "owner_id": group.owner_id,
+                "member_count": member_ids.len(),
+            }),
+        };
+        Self::record_audit(&mut tx, actor, entry).await?;
+        tx.commit().await.map_err(|e| {
+            error!("提交事务失败: {:?}", e);
+            AppError::InternalServerError("数据库操作失败".to_string())
+        })?;
+
+        info!(
+            "【Service】删除群组成功: admin_id={}, group_id={}, 解散={}",
+            actor.user_id, group_id, dissolve
+        );
+        Ok(DeletedGroup {
+            name: group.name,
+            member_ids,
+        })
+    }
+
+    // 删除任意签到
+    pub async fn delete_checkin(
+        &self,
+        actor: AdminActor<'_>,
+        checkin_id: Uuid,
+    ) -> Result<(), AppError> {
+        let mut tx = self.db.pg_pool.begin().await.map_err(|e| {
+            error!("开启事务失败: {:?}", e);
+            AppError::InternalServerError("数据库操作失败".to_string())
+        })?;
+
+        let owner_id = sqlx::query_scalar!(
+            r#"DELETE FROM checkins WHERE checkin_id = $1 RETURNING user_id"#,
+            checkin_id
+        )
+        .fetch_optional(&mut *tx)
+        .await
+        .map_err(|e| {
+            error!("删除签到失败: {:?}", e);
+            AppError::InternalServerError("删除签到失败".to_string())
+        })?
+        .ok_or_else(|| AppError::NotFound("签到不存在".to_string()))?;
+
+        let entry = AuditEntry {
+            action: admin_actions::DELETE_CHECKIN,
+            target_type: "checkin",
+            target_id: checkin_id.to_string(),
+            detail: json!({ "user_id": owner_id }),
+        };
+        Self::record_audit(&mut tx, actor, entry).await?;
+        tx.commit().await.map_err(|e| {
+            error!("提交事务失败: {:?}", e);
+            AppError::InternalServerError("数据库操作失败".to_string())
+        })?;
+
+        info!(
+            "【Service】删除签到成功: admin_id={}, checkin_id={}",
+            actor.user_id, checkin_id
+        );
+        Ok(())
+    }
+
+    // 删除任意群组消息或私聊消息，target_type为group或conversation
+    pub async fn delete_message(
+        &self,
+        actor: AdminActor<'_>,
+        message_id: Uuid,
+        target_type: &str,
+    ) -> Result<(), AppError> {
+        let mut tx = self.db.pg_pool.begin().await.map_err(|e| {
+            error!("开启事务失败: {:?}", e);
+            AppError::InternalServerError("数据库操作失败".to_string())
+        })?;
+
+        let detail = if target_type == ws_target_types::GROUP {
+            let message = sqlx::query!(
+                r#"DELETE FROM group_messages WHERE message_id = $1 RETURNING sender_id, group_id"#,
+                message_id
+            )
+            .fetch_optional(&mut *tx)
+            .await
+            .map_err(|e| {
+                error!("删除群组消息失败: {:?}", e);
+                AppError::InternalServerError("删除消息失败".to_string())
+            })?
+            .ok_or_else(|| AppError::NotFound("消息不存在".to_string()))?;
+            json!({ "sender_id": message.sender_id, "group_id": message.group_id })
+        } else {
+            let message = sqlx::query!(
+                r#"DELETE FROM direct_messages WHERE message_id = $1 RETURNING sender_id, conversation_id"#,
+                message_id
+            )
+            .fetch_optional(&mut *tx)
+            .await
+            .map_err(|e| {
+                error!("删除私聊消息失败: {:?}", e);
+                AppError::InternalServerError("删除消息失败".to_string())
+            })?
+            .ok_or_else(|| AppError::NotFound("消息不存在".to_string()))?;
+            json!({
+                "sender_id": message.sender_id,
+                "conversation_id": message.conversation_id,
+            })
+        };
+
+        let entry = AuditEntry {
+            action: admin_actions::DELETE_MESSAGE,
+            target_type: if target_type == ws_target_types::GROUP {
+                "group_message"
+            } else {
+                "direct_message"
+            },
+            target_id: message_id.to_string(),
+            detail,
+        };
+        Self::record_audit(&mut tx, actor, entry).await?;
+        tx.commit().await.map_err(|e| {
+            error!("提交事务失败: {:?}", e);
+            AppError::InternalServerError("数据库操作失败".to_string())
+        })?;
+
+        info!(
+            "【Service】删除消息成功: admin_id={}, message_id={}",
+            actor.user_id, message_id
+        );
+        Ok(())
+    }
+
+    // =============== 审计日志 ===============
+
+    // 获取审计日志，按时间倒序
+    pub async fn list_audit_logs(
+        &self,
+        cursor: i64,
+        limit: i64,
+    ) -> Result<(Vec<AuditLogRow>, bool, Option<i64>), AppError> {
+        let rows = sqlx::query_as!(
+            AuditLogRow,
+            r#"
+            SELECT
+                l.id,
+                l.admin_id,
+                u.nickname as "admin_nickname?",
+                l.action,
+                l.target_type,
+                l.target_id,
+                l.reason,
+                l.detail,
+                l.created_at
+            FROM admin_audit_logs l
+            LEFT JOIN users u ON u.user_id = l.admin_id
+            WHERE ($1::bigint = 0 OR l.id < $1::bigint)
+            ORDER BY l.id DESC
+            LIMIT $2
+            "#,
+            cursor,
+            limit + 1
+        )
+        .fetch_all(&self.db.pg_pool)
+        .await
+        .map_err(|e| {
+            error!("获取审计日志失败: {:?}", e);
+            AppError::InternalServerError("获取审计日志失败".to_string())
+        })?;
+
+        Ok(paginate(rows, limit, |row| row.id))
+    }
+
+    // =============== 辅助函数 ===============
+
+    async fn record_audit(
+        conn: &mut PgConnection,
+        actor: AdminActor<'_>,
+        entry: AuditEntry<'_>,
+    ) -> Result<(), AppError> {
+        sqlx::query!(
+            r#"
+            INSERT INTO admin_audit_logs (admin_id, action, target_type, target_id, reason, detail, created_at)
+            VALUES ($1, $2, $3, $4, $5, $6, $7)
+            "#,
+            actor.user_id,
+            entry.action,
+            entry.target_type,
+            entry.target_id,
+            actor.reason,
+            entry.detail,
+            Utc::now()
+        )
+        .execute(&mut *conn)
+        .await
+        .map_err(|e| {
+            error!("写入审计日志失败: {:?}", e);
+            AppError::InternalServerError("写入审计日志失败".to_string())
+        })?;
+        Ok(())
+    }
+}
@@ -0,0 +1,187 @@
+use crate::{config::db::Database, utils::response::AppError};
+use chrono::{DateTime, Utc};
+use tracing::{error, info};
+
+// 被屏蔽的用户
+#[derive(Debug, Clone)]
+pub struct BlockedUserRow {
+    pub user_id: i64,
+    pub nickname: String,
+    pub avatar_url: Option<String>,
+    pub blocked_at: DateTime<Utc>,
+}
+
+// 用户屏蔽：屏蔽关系对双方生效，互相搜索不到、不能发起私信或好友申请、收不到对方的在线状态。
+// 私信、搜索、签到和在线状态的过滤分别在对应服务的查询中完成
+#[derive(Debug)]
+pub struct BlockService {
+    db: Database,
+}
+
+impl BlockService {
+    pub fn new(db: Database) -> Self {
+        Self { db }
+    }
+
+    // =============== 验证函数 ===============
+
+    // 检查两个用户之间是否存在屏蔽关系（任一方向）
+    pub async fn is_blocked_between(&self, user_a: i64, user_b: i64) -> Result<bool, AppError> {
+        let blocked = sqlx::query_scalar!(
+            r#"
+            SELECT EXISTS(
+                SELECT 1 FROM user_blocks
+                WHERE (blocker_id = $1 AND blocked_id = $2)
+                   OR (blocker_id = $2 AND blocked_id = $1)
+            ) as "blocked!"
+            "#,
+            user_a,
+            user_b
+        )
+        .fetch_one(&self.db.pg_pool)
+        .await
+        .map_err(|e| {
+            error!("查询屏蔽关系失败: {:?}", e);
+            AppError::InternalServerError("查询屏蔽关系失败".to_string())
+        })?;
+
+        Ok(blocked)
+    }
+
+    // =============== 业务函数 ===============
+
+    // 屏蔽用户，重复屏蔽直接返回成功
+    pub async fn block_user(&self, blocker_id: i64, blocked_id: i64) -> Result<(), AppError> {
+        if blocker_id == blocked_id {
+            return Err(AppError::BadRequest("不能屏蔽自己".to_string()));
+        }
+
+        let user_exists = sqlx::query_scalar!(
+            r#"SELECT EXISTS(SELECT 1 FROM users WHERE user_id = $1) as "exists!""#,
+            blocked_id
+        )
+        .fetch_one(&self.db.pg_pool)
+        .await
+        .map_err(|e| {
+            error!("查询用户失败: {:?}", e);
+            AppError::InternalServerError("屏蔽用户失败".to_string())
+        })?;
+        if !user_exists {
+            return Err(AppError::NotFound("用户不存在".to_string()));
+        }
+
+        let mut tx = self.db.pg_pool.begin().await.map_err(|e| {
+            error!("开启事务失败: {:?}", e);
+            AppError::InternalServerError("数据库操作失败".to_string())
+        })?;
+
+        sqlx::query!(
+            r#"
+            INSERT INTO user_blocks (blocker_id, blocked_id, created_at)
+            VALUES ($1, $2, $3)
+            ON CONFLICT (blocker_id, blocked_id) DO NOTHING
+            "#,
+            blocker_id,
+            blocked_id,
+            Utc::now()
+        )
+        .execute(&mut *tx)
+        .await
+        .map_err(|e| {
+            error!("屏蔽用户失败: {:?}", e);
+            AppError::InternalServerError("屏蔽用户失败".to_string())
+        })?;
+
+        // 屏蔽后解除好友关系，双方之间待处理的好友申请视为拒绝
+        sqlx::query!(
+            r#"
+            DELETE FROM friendships
+            WHERE (user_id = $1 AND friend_id = $2) OR (user_id = $2 AND friend_id = $1)
+            "#,
+            blocker_id,
+            blocked_id
+        )
+        .execute(&mut *tx)
+        .await
+        .map_err(|e| {
+            error!("解除好友关系失败: {:?}", e);
+            AppError::InternalServerError("屏蔽用户失败".to_string())
+        })?;
+
+        sqlx::query!(
+            r#"
+            UPDATE friend_requests
+            SET status = 'declined', responded_at = $3
+            WHERE status = 'pending'
+              AND ((requester_id = $1 AND addressee_id = $2)
+                OR (requester_id = $2 AND addressee_id = $1))
+            "#,
+            blocker_id,
+            blocked_id,
+            Utc::now()
+        )
+        .execute(&mut *tx)
+        .await
+        .map_err(|e| {
+            error!("更新好友申请失败: {:?}", e);
+            AppError::InternalServerError("屏蔽用户失败".to_string())
+        })?;
+
+        tx.commit().await.map_err(|e| {
+            error!("提交事务失败: {:?}", e);
+            AppError::InternalServerError("数据库操作失败".to_string())
+        })?;
+
+        info!(
+            "【Service】屏蔽用户成功: blocker_id={}, blocked_id={}",
+            blocker_id, blocked_id
+        );
+        Ok(())
+    }
+
+    // 取消屏蔽，未屏蔽时直接返回成功
+    pub async fn unblock_user(&self, blocker_id: i64, blocked_id: i64) -> Result<(), AppError> {
+        sqlx::query!(
+            r#"DELETE FROM user_blocks WHERE blocker_id = $1 AND blocked_id = $2"#,
+            blocker_id,
+            blocked_id
+        )
+        .execute(&self.db.pg_pool)
+        .await
+        .map_err(|e| {
+            error!("取消屏蔽失败: {:?}", e);
+            AppError::InternalServerError("取消屏蔽失败".to_string())
+        })?;
+
+        info!(
+            "【Service】取消屏蔽成功: blocker_id={}, blocked_id={}",
+            blocker_id, blocked_id
+        );
+        Ok(())
+    }
+
+    // 获取用户屏蔽的用户列表，按屏蔽时间倒序
+    pub async fn list_blocked_users(&self, user_id: i64) -> Result<Vec<BlockedUserRow>, AppError> {
+        sqlx::query_as!(
+            BlockedUserRow,
+            r#"
+            SELECT
+                u.user_id,
+                u.nickname as "nickname!",
+                u.avatar_url,
+                b.created_at as blocked_at
+            FROM user_blocks b
+            JOIN users u ON u.user_id = b.blocked_id
+            WHERE b.blocker_id = $1
+            ORDER BY b.created_at DESC
+            "#,
+            user_id
+        )
+        .fetch_all(&self.db.pg_pool)
+        .await
+        .map_err(|e| {
+            error!("获取屏蔽列表失败: {:?}", e);
+            AppError::InternalServerError("获取屏蔽列表失败".to_string())
+        })
+    }
+}
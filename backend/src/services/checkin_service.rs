@@ -7,7 +7,7 @@ use crate::{
         db::Database,
         location::{DEFAULT_LATITUDE, DEFAULT_LOCATION_NAME, DEFAULT_LONGITUDE},
     },
-    utils::response::AppError,
+    utils::{geo::Viewport, response::AppError},
 };
 
 #[derive(Debug, Clone)]
@@ -361,6 +361,11 @@ impl CheckinService {
             LEFT JOIN user_locations ul ON ul.user_id = $1
             LEFT JOIN checkin_tags ct ON ct.checkin_id = c.checkin_id
             WHERE c.checkin_id = $2
+            AND NOT EXISTS (
+                SELECT 1 FROM user_blocks b
+                WHERE (b.blocker_id = $1 AND b.blocked_id = c.user_id)
+                   OR (b.blocker_id = c.user_id AND b.blocked_id = $1)
+            )
             "#,
             user_id,
             Uuid::parse_str(checkin_id).map_err(|e| {
@@ -440,6 +445,58 @@ impl CheckinService {
         Ok(rows)
     }
 
+    // 获取好友的签到历史，按签到时间倒序
+    pub async fn search_friend_checkins(
+        &self,
+        user_id: i64,
+        cursor: i64,
+        limit: i64,
+    ) -> Result<Vec<CheckinRow>, AppError> {
+        let rows = sqlx::query_as!(
+            CheckinRow,
+            r#"
+            SELECT 
+                c.id as "id!",
+                c.checkin_id::text as "checkin_id!",
+                c.user_id as "user_id!",
+                u.nickname as "nickname!",
+                c.description,
+                c.latitude as "latitude!",
+                c.longitude as "longitude!",
+                COALESCE(c.location_name, '') as "location_name!",
+                c.created_at as "created_at!",
+                ST_Distance(
+                    ST_MakePoint(c.longitude, c.latitude)::geography,
+                    ST_MakePoint(COALESCE(ul.longitude, $2), COALESCE(ul.latitude, $3))::geography
+                ) as "distance!",
+                COALESCE(ARRAY(SELECT t.name FROM checkin_tags ct JOIN tags t ON ct.tag_id = t.tag_id WHERE ct.checkin_id = c.checkin_id), ARRAY[]::text[]) as "tags!",
+                COALESCE((SELECT COUNT(*) FROM checkin_likes WHERE checkin_id = c.checkin_id), 0) as "likes_count!",
+                0 as "comments_count!",
+                COALESCE((SELECT EXISTS(SELECT 1 FROM checkin_likes WHERE checkin_id = c.checkin_id AND user_id = $1)), false) as "liked_by_me!"
+            FROM checkins c
+            JOIN friendships f ON f.friend_id = c.user_id AND f.user_id = $1
+            JOIN users u ON c.user_id = u.user_id
+            LEFT JOIN user_locations ul ON ul.user_id = $1
+            WHERE ($4::bigint = 0 OR c.id < $4::bigint)
+            ORDER BY c.id DESC
+            LIMIT $5
+            "#,
+            user_id,
+            DEFAULT_LONGITUDE,
+            DEFAULT_LATITUDE,
+            cursor,
+            limit + 1
+        )
+        .fetch_all(&self.db.pg_pool)
+        .await
+        .map_err(|e| {
+            error!("查询好友签到历史失败: {:?}", e);
+            AppError::InternalServerError("查询签到历史失败".to_string())
+        })?;
+
+        Ok(rows)
+    }
+
     // 搜索附近签到
     pub async fn search_checkins_by_location(
         &self,
@@ -478,6 +535,11 @@ impl CheckinService {
                 ST_MakePoint(c.longitude, c.latitude)::geography,
                 ST_MakePoint($3, $2)::geography
             ) <= $4
+            AND NOT EXISTS (
+                SELECT 1 FROM user_blocks b
+                WHERE (b.blocker_id = $1 AND b.blocked_id = c.user_id)
+                   OR (b.blocker_id = c.user_id AND b.blocked_id = $1)
+            )
             ORDER BY c.id DESC
             LIMIT $6
             "#,
@@ -497,6 +559,67 @@ impl CheckinService {
         Ok(rows.into_iter().map(|row| row).collect())
     }
 
+    // 查找地图可视范围内的签到，距离相对于范围中心；多返回一条用于判断是否还有更多
+    pub async fn search_checkins_in_viewport(
+        &self,
+        user_id: i64,
+        viewport: Viewport,
+        cursor: i64,
+        limit: i64,
+    ) -> Result<Vec<CheckinRow>, AppError> {
+        let (center_latitude, center_longitude) = viewport.center();
+        let rows = sqlx::query_as!(
+            CheckinRow,
+            r#"
+            SELECT
+                c.id as "id!",
+                c.checkin_id::text as "checkin_id!",
+                c.user_id as "user_id!",
+                u.nickname as "nickname!",
+                c.description,
+                c.latitude as "latitude!",
+                c.longitude as "longitude!",
+                c.location_name as "location_name!",
+                c.created_at as "created_at!",
+                ST_Distance(
+                    ST_MakePoint(c.longitude, c.latitude)::geography,
+                    ST_MakePoint($7, $6)::geography
+                ) as "distance!",
+                ARRAY(SELECT t.name FROM checkin_tags ct JOIN tags t ON ct.tag_id = t.tag_id WHERE ct.checkin_id = c.checkin_id) as "tags!",
+                (SELECT COUNT(*) FROM checkin_likes WHERE checkin_id = c.checkin_id) as "likes_count!",
+                0 as "comments_count!",
+                (SELECT EXISTS(SELECT 1 FROM checkin_likes WHERE checkin_id = c.checkin_id AND user_id = $1)) as "liked_by_me!"
+            FROM checkins c
+            JOIN users u ON c.user_id = u.user_id
+            WHERE ($8::bigint = 0 OR c.id < $8::bigint)
+            AND ST_Intersects(c.geom::geometry, ST_MakeEnvelope($3, $2, $5, $4, 4326))
+            AND NOT EXISTS (
+                SELECT 1 FROM user_blocks b
+                WHERE (b.blocker_id = $1 AND b.blocked_id = c.user_id)
+                   OR (b.blocker_id = c.user_id AND b.blocked_id = $1)
+            )
+            ORDER BY c.id DESC
+            LIMIT $9
+            "#,
+            user_id,
+            viewport.south,
+            viewport.west,
+            viewport.north,
+            viewport.east,
+            center_latitude,
+            center_longitude,
+            cursor,
+            limit + 1
+        )
+        .fetch_all(&self.db.pg_pool)
+        .await
+        .map_err(|e| {
+            error!("查询可视范围内签到失败: {:?}", e);
+            AppError::InternalServerError("查询可视范围内签到失败".to_string())
+        })?;
+        Ok(rows)
+    }
+
     // 根据标签搜索签到
     pub async fn search_checkins_by_tags(
         &self,
@@ -538,6 +661,11 @@ impl CheckinService {
             JOIN tags t ON ct.tag_id = t.tag_id
             WHERE t.name = ANY($3)
             AND ($4::bigint = 0 OR c.id < $4::bigint)
+            AND NOT EXISTS (
+                SELECT 1 FROM user_blocks b
+                WHERE (b.blocker_id = $1 AND b.blocked_id = c.user_id)
+                   OR (b.blocker_id = c.user_id AND b.blocked_id = $1)
+            )
             ORDER BY c.id DESC
             LIMIT $5
             "#,
@@ -0,0 +1,550 @@
+use crate::{
+    config::{app_config::ClusterConfig, db::Database},
+    utils::{
+        geo::{self, Viewport},
+        redis::RedisExecutor,
+        response::AppError,
+    },
+};
+use futures::{StreamExt, TryStreamExt, stream};
+use redis::AsyncCommands;
+use serde::{Deserialize, Serialize, de::DeserializeOwned};
+use std::collections::{HashMap, HashSet};
+use std::future::Future;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+use tracing::{debug, error};
+use uuid::Uuid;
+
+// Redis中瓦片缓存键的前缀
+const CLUSTER_KEY_PREFIX: &str = "geotalk:cluster:";
+
+// 每个瓦片划分为GRID_SIZE×GRID_SIZE的网格，256像素瓦片下每个单元约64像素
+const GRID_SIZE: f64 = 4.0;
+
+// 支持聚合的最大缩放级别
+pub const MAX_CLUSTER_ZOOM: u32 = 22;
+
+// 一次请求最多覆盖的瓦片数
+pub const MAX_CLUSTER_TILES: u64 = 100;
+
+// 同时加载的瓦片数，每个瓦片依次执行两次查询，不超过连接池大小
+const TILE_LOAD_CONCURRENCY: usize = 4;
+
+// 聚合单元：位置为单元内点的平均坐标，范围为包含所有点的最小矩形
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ClusterRow {
+    pub count: i64,
+    pub latitude: f64,
+    pub longitude: f64,
+    pub south: f64,
+    pub west: f64,
+    pub north: f64,
+    pub east: f64,
+}
+
+// 未聚合的单个签到
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CheckinPointRow {
+    pub id: i64,
+    pub checkin_id: String,
+    pub user_id: i64,
+    pub latitude: f64,
+    pub longitude: f64,
+}
+
+// 未聚合的单个群组
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GroupPointRow {
+    pub id: i64,
+    pub group_id: Uuid,
+    pub name: String,
+    pub latitude: f64,
+    pub longitude: f64,
+}
+
+// 可以按距离筛选的点
+pub trait MapPoint {
+    fn position(&self) -> (f64, f64);
+}
+
+impl MapPoint for CheckinPointRow {
+    fn position(&self) -> (f64, f64) {
+        (self.latitude, self.longitude)
+    }
+}
+
+impl MapPoint for GroupPointRow {
+    fn position(&self) -> (f64, f64) {
+        (self.latitude, self.longitude)
+    }
+}
+
+// 一个或多个瓦片的聚合结果：密集单元返回聚合，稀疏单元返回单个点
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TileClusters<P> {
+    pub clusters: Vec<ClusterRow>,
+    pub points: Vec<P>,
+}
+
+impl<P> TileClusters<P> {
+    // 按顺序合并多个瓦片的结果
+    fn merge(tiles: Vec<TileClusters<P>>) -> Self {
+        let mut result = TileClusters {
+            clusters: Vec::new(),
+            points: Vec::new(),
+        };
+        for tile in tiles {
+            result.clusters.extend(tile.clusters);
+            result.points.extend(tile.points);
+        }
+        result
+    }
+}
+
+impl<P: MapPoint> TileClusters<P> {
+    // 只保留圆形范围内的聚合和点，聚合按其平均位置判断
+    pub fn retain_within(&mut self, latitude: f64, longitude: f64, radius: f64) {
+        self.clusters.retain(|cluster| {
+            geo::distance_meters(latitude, longitude, cluster.latitude, cluster.longitude) <= radius
+        });
+        self.points.retain(|point| {
+            let (lat, lon) = point.position();
+            geo::distance_meters(latitude, longitude, lat, lon) <= radius
+        });
+    }
+}
+
+// 内存中缓存的瓦片聚合结果，未配置Redis时使用
+#[derive(Debug)]
+struct CachedTile {
+    json: String,
+    expires_at: Instant,
+}
+
+// 地图标记聚合：按缩放级别把范围拆分为瓦片，每个瓦片按网格聚合签到和群组并缓存。
+// 缓存与查看者无关；包含与查看者存在屏蔽关系的用户签到的瓦片不读缓存，
+// 排除这些用户后单独聚合，聚合数量、位置和范围都不包含他们的签到
+#[derive(Debug)]
+pub struct ClusterService {
+    db: Database,
+    config: ClusterConfig,
+    redis: Option<RedisExecutor>,
+    memory: Mutex<HashMap<String, CachedTile>>,
+}
+
+impl ClusterService {
+    pub fn new(db: Database, config: ClusterConfig) -> Self {
+        let redis = db.redis_client.clone().map(RedisExecutor::new);
+        Self {
+            db,
+            config,
+            redis,
+            memory: Mutex::new(HashMap::new()),
+        }
+    }
+
+    // =============== 对外接口 ===============
+
+    // 聚合范围内的签到，与查看者存在屏蔽关系的用户的签到不返回也不计入聚合
+    pub async fn checkin_clusters(
+        &self,
+        viewer_id: i64,
+        viewport: Viewport,
+        zoom: u32,
+    ) -> Result<TileClusters<CheckinPointRow>, AppError> {
+        let tiles = viewport.tiles(zoom);
+        let blocked: Vec<i64> = self
+            .blocked_user_ids(viewer_id)
+            .await?
+            .into_iter()
+            .collect();
+        let affected = self.tiles_with_checkins_of(&blocked, zoom, &tiles).await?;
+        let (blocked, affected) = (&blocked, &affected);
+
+        let tiles = stream::iter(tiles)
+            .map(|(x, y)| async move {
+                let bounds = geo::tile_bounds(zoom, x, y);
+                if affected.contains(&(x, y)) {
+                    return self.load_checkin_tile(bounds, blocked).await;
+                }
+                let key = format!("checkins:{}/{}/{}", zoom, x, y);
+                self.cached_tile(&key, || self.load_checkin_tile(bounds, &[]))
+                    .await
+            })
+            .buffered(TILE_LOAD_CONCURRENCY)
+            .try_collect()
+            .await?;
+        Ok(TileClusters::merge(tiles))
+    }
+
+    // 聚合范围内的群组
+    pub async fn group_clusters(
+        &self,
+        viewport: Viewport,
+        zoom: u32,
+    ) -> Result<TileClusters<GroupPointRow>, AppError> {
+        let tiles = stream::iter(viewport.tiles(zoom))
+            .map(|(x, y)| async move {
+                let bounds = geo::tile_bounds(zoom, x, y);
+                let key = format!("groups:{}/{}/{}", zoom, x, y);
+                self.cached_tile(&key, || self.load_group_tile(bounds))
+                    .await
+            })
+            .buffered(TILE_LOAD_CONCURRENCY)
+            .try_collect()
+            .await?;
+        Ok(TileClusters::merge(tiles))
+    }
+
+    // 清理内存中过期的瓦片缓存（Redis中的缓存会自动过期）
+    pub fn purge_expired(&self) -> usize {
+        let now = Instant::now();
+        let mut memory = self.memory.lock().unwrap_or_else(|e| e.into_inner());
+        let before = memory.len();
+        memory.retain(|_, tile| tile.expires_at > now);
+        before - memory.len()
+    }
+
+    // =============== 瓦片聚合 ===============
+
+    // 瓦片范围按左闭右开划分，边界上的点只属于一个瓦片（判断规则见Viewport::tile_contains）；
+    // excluded中用户的签到不参与聚合
+    async fn load_checkin_tile(
+        &self,
+        bounds: Viewport,
+        excluded: &[i64],
+    ) -> Result<TileClusters<CheckinPointRow>, AppError> {
+        let clusters = sqlx::query_as!(
+            ClusterRow,
+            r#"
+            WITH cells AS (
+                SELECT
+                    c.latitude,
+                    c.longitude,
+                    LEAST(floor((c.latitude - $1) / ($3 - $1) * $5), $5 - 1) as cell_row,
+                    LEAST(floor((c.longitude - $2) / ($4 - $2) * $5), $5 - 1) as cell_col
+                FROM checkins c
+                WHERE ST_Intersects(c.geom::geometry, ST_MakeEnvelope($2, $1, $4, $3, 4326))
+                AND c.latitude >= $1 AND (c.latitude < $3 OR $3 >= 90)
+                AND c.longitude >= $2 AND (c.longitude < $4 OR $4 >= 180)
+                AND NOT (c.user_id = ANY($7))
+            )
+            SELECT
+                COUNT(*) as "count!",
+                AVG(latitude) as "latitude!",
+                AVG(longitude) as "longitude!",
+                MIN(latitude) as "south!",
+                MIN(longitude) as "west!",
+                MAX(latitude) as "north!",
+                MAX(longitude) as "east!"
+            FROM cells
+            GROUP BY cell_row, cell_col
+            HAVING COUNT(*) >= $6
+            "#,
+            bounds.south,
+            bounds.west,
+            bounds.north,
+            bounds.east,
+            GRID_SIZE,
+            self.config.point_threshold,
+            excluded
+        )
+        .fetch_all(&self.db.pg_pool)
+        .await
+        .map_err(|e| {
+            error!("聚合签到失败: {:?}", e);
+            AppError::InternalServerError("聚合签到失败".to_string())
+        })?;
+
+        let points = sqlx::query_as!(
+            CheckinPointRow,
+            r#"
+            WITH cells AS (
+                SELECT
+                    c.id,
+                    c.checkin_id,
+                    c.user_id,
+                    c.latitude,
+                    c.longitude,
+                    COUNT(*) OVER (
+                        PARTITION BY
+                            LEAST(floor((c.latitude - $1) / ($3 - $1) * $5), $5 - 1),
+                            LEAST(floor((c.longitude - $2) / ($4 - $2) * $5), $5 - 1)
+                    ) as cell_count
+                FROM checkins c
+                WHERE ST_Intersects(c.geom::geometry, ST_MakeEnvelope($2, $1, $4, $3, 4326))
+                AND c.latitude >= $1 AND (c.latitude < $3 OR $3 >= 90)
+                AND c.longitude >= $2 AND (c.longitude < $4 OR $4 >= 180)
+                AND NOT (c.user_id = ANY($7))
+            )
+            SELECT
+                id as "id!",
+                checkin_id::text as "checkin_id!",
+                user_id as "user_id!",
+                latitude as "latitude!",
+                longitude as "longitude!"
+            FROM cells
+            WHERE cell_count < $6
+            ORDER BY id DESC
+            "#,
+            bounds.south,
+            bounds.west,
+            bounds.north,
+            bounds.east,
+            GRID_SIZE,
+            self.config.point_threshold,
+            excluded
+        )
+        .fetch_all(&self.db.pg_pool)
+        .await
+        .map_err(|e| {
+            error!("查询瓦片内签到失败: {:?}", e);
+            AppError::InternalServerError("聚合签到失败".to_string())
+        })?;
+
+        Ok(TileClusters { clusters, points })
+    }
+
+    async fn load_group_tile(
+        &self,
+        bounds: Viewport,
+    ) -> Result<TileClusters<GroupPointRow>, AppError> {
+        let clusters = sqlx::query_as!(
+            ClusterRow,
+            r#"
+            WITH cells AS (
+                SELECT
+                    g.latitude,
+                    g.longitude,
+                    LEAST(floor((g.latitude - $1) / ($3 - $1) * $5), $5 - 1) as cell_row,
+                    LEAST(floor((g.longitude - $2) / ($4 - $2) * $5), $5 - 1) as cell_col
+                FROM groups g
+                WHERE ST_Intersects(g.geom::geometry, ST_MakeEnvelope($2, $1, $4, $3, 4326))
+                AND g.latitude >= $1 AND (g.latitude < $3 OR $3 >= 90)
+                AND g.longitude >= $2 AND (g.longitude < $4 OR $4 >= 180)
+            )
+            SELECT
+                COUNT(*) as "count!",
+                AVG(latitude) as "latitude!",
+                AVG(longitude) as "longitude!",
+                MIN(latitude) as "south!",
+                MIN(longitude) as "west!",
+                MAX(latitude) as "north!",
+                MAX(longitude) as "east!"
+            FROM cells
+            GROUP BY cell_row, cell_col
+            HAVING COUNT(*) >= $6
+            "#,
+            bounds.south,
+            bounds.west,
+            bounds.north,
+            bounds.east,
+            GRID_SIZE,
+            self.config.point_threshold
+        )
+        .fetch_all(&self.db.pg_pool)
+        .await
+        .map_err(|e| {
+            error!("聚合群组失败: {:?}", e);
+            AppError::InternalServerError("聚合群组失败".to_string())
+        })?;
+
+        let points = sqlx::query_as!(
+            GroupPointRow,
+            r#"
+            WITH cells AS (
+                SELECT
+                    g.id,
+                    g.group_id,
+                    g.name,
+                    g.latitude,
+                    g.longitude,
+                    COUNT(*) OVER (
+                        PARTITION BY
+                            LEAST(floor((g.latitude - $1) / ($3 - $1) * $5), $5 - 1),
+                            LEAST(floor((g.longitude - $2) / ($4 - $2) * $5), $5 - 1)
+                    ) as cell_count
+                FROM groups g
+                WHERE ST_Intersects(g.geom::geometry, ST_MakeEnvelope($2, $1, $4, $3, 4326))
+                AND g.latitude >= $1 AND (g.latitude < $3 OR $3 >= 90)
+                AND g.longitude >= $2 AND (g.longitude < $4 OR $4 >= 180)
+            )
+            SELECT
+                id as "id!",
+                group_id as "group_id!",
+                name as "name!",
+                latitude as "latitude!",
+                longitude as "longitude!"
+            FROM cells
+            WHERE cell_count < $6
+            ORDER BY id DESC
+            "#,
+            bounds.south,
+            bounds.west,
+            bounds.north,
+            bounds.east,
+            GRID_SIZE,
+            self.config.point_threshold
+        )
+        .fetch_all(&self.db.pg_pool)
+        .await
+        .map_err(|e| {
+            error!("查询瓦片内群组失败: {:?}", e);
+            AppError::InternalServerError("聚合群组失败".to_string())
+        })?;
+
+        Ok(TileClusters { clusters, points })
+    }
+
+    // 包含指定用户签到的瓦片（只在给定瓦片中查找）
+    async fn tiles_with_checkins_of(
+        &self,
+        user_ids: &[i64],
+        zoom: u32,
+        tiles: &[(u32, u32)],
+    ) -> Result<HashSet<(u32, u32)>, AppError> {
+        if user_ids.is_empty() || tiles.is_empty() {
+            return Ok(HashSet::new());
+        }
+        let bounds: Vec<(u32, u32, Viewport)> = tiles
+            .iter()
+            .map(|&(x, y)| (x, y, geo::tile_bounds(zoom, x, y)))
+            .collect();
+        // 覆盖全部瓦片的范围
+        let extent = bounds
+            .iter()
+            .fold(bounds[0].2, |extent, (_, _, b)| Viewport {
+                south: extent.south.min(b.south),
+                west: extent.west.min(b.west),
+                north: extent.north.max(b.north),
+                east: extent.east.max(b.east),
+            });
+
+        let positions = sqlx::query!(
+            r#"
+            SELECT c.latitude, c.longitude
+            FROM checkins c
+            WHERE c.user_id = ANY($1)
+            AND ST_Intersects(c.geom::geometry, ST_MakeEnvelope($3, $2, $5, $4, 4326))
+            "#,
+            user_ids,
+            extent.south,
+            extent.west,
+            extent.north,
+            extent.east
+        )
+        .fetch_all(&self.db.pg_pool)
+        .await
+        .map_err(|e| {
+            error!("查询屏蔽用户签到失败: {:?}", e);
+            AppError::InternalServerError("聚合签到失败".to_string())
+        })?;
+
+        Ok(bounds
+            .into_iter()
+            .filter(|(_, _, b)| {
+                positions
+                    .iter()
+                    .any(|p| b.tile_contains(p.latitude, p.longitude))
+            })
+            .map(|(x, y, _)| (x, y))
+            .collect())
+    }
+
+    // 与查看者存在屏蔽关系（任一方向）的用户
+    async fn blocked_user_ids(&self, user_id: i64) -> Result<HashSet<i64>, AppError> {
+        let user_ids = sqlx::query_scalar!(
+            r#"
+            SELECT CASE WHEN blocker_id = $1 THEN blocked_id ELSE blocker_id END as "user_id!"
+            FROM user_blocks
+            WHERE blocker_id = $1 OR blocked_id = $1
+            "#,
+            user_id
+        )
+        .fetch_all(&self.db.pg_pool)
+        .await
+        .map_err(|e| {
+            error!("查询屏蔽关系失败: {:?}", e);
+            AppError::InternalServerError("查询屏蔽关系失败".to_string())
+        })?;
+
+        Ok(user_ids.into_iter().collect())
+    }
+
+    // =============== 瓦片缓存 ===============
+
+    // 读取瓦片缓存，未命中时加载并写入缓存
+    async fn cached_tile<P, F, Fut>(&self, key: &str, load: F) -> Result<TileClusters<P>, AppError>
+    where
+        P: Serialize + DeserializeOwned,
+        F: FnOnce() -> Fut,
+        Fut: Future<Output = Result<TileClusters<P>, AppError>>,
+    {
+        if let Some(tile) = self.cache_get(key).await {
+            return Ok(tile);
+        }
+        let tile = load().await?;
+        self.cache_put(key, &tile).await;
+        Ok(tile)
+    }
+
+    // 读取缓存；缓存出错时记录日志并视为未命中
+    async fn cache_get<P: DeserializeOwned>(&self, key: &str) -> Option<TileClusters<P>> {
+        let json = match &self.redis {
+            Some(redis) => {
+                let redis_key = format!("{}{}", CLUSTER_KEY_PREFIX, key);
+                redis
+                    .run(|mut conn| async move { conn.get::<_, Option<String>>(redis_key).await })
+                    .await
+                    .unwrap_or_else(|e| {
+                        error!("读取瓦片聚合缓存失败: key={}, error={:?}", key, e);
+                        None
+                    })
+            }
+            None => {
+                let memory = self.memory.lock().unwrap_or_else(|e| e.into_inner());
+                memory
+                    .get(key)
+                    .filter(|tile| tile.expires_at > Instant::now())
+                    .map(|tile| tile.json.clone())
+            }
+        }?;
+
+        debug!("瓦片聚合缓存命中: key={}", key);
+        serde_json::from_str(&json)
+            .map_err(|e| error!("解析瓦片聚合缓存失败: key={}, error={:?}", key, e))
+            .ok()
+    }
+
+    async fn cache_put<P: Serialize>(&self, key: &str, tile: &TileClusters<P>) {
+        let json = match serde_json::to_string(tile) {
+            Ok(json) => json,
+            Err(e) => {
+                error!("序列化瓦片聚合结果失败: key={}, error={:?}", key, e);
+                return;
+            }
+        };
+        let ttl_secs = self.config.cache_ttl_secs;
+
+        let Some(redis) = &self.redis else {
+            let mut memory = self.memory.lock().unwrap_or_else(|e| e.into_inner());
+            memory.insert(
+                key.to_string(),
+                CachedTile {
+                    json,
+                    expires_at: Instant::now() + Duration::from_secs(ttl_secs),
+                },
+            );
+            return;
+        };
+
+        let redis_key = format!("{}{}", CLUSTER_KEY_PREFIX, key);
+        if let Err(e) = redis
+            .run(|mut conn| async move { conn.set_ex::<_, _, ()>(redis_key, json, ttl_secs).await })
+            .await
+        {
+            error!("写入瓦片聚合缓存失败: key={}, error={:?}", key, e);
+        }
+    }
+}
@@ -0,0 +1,145 @@
+use crate::{config::db::Database, models::websocket::WebSocketMessage, utils::response::AppError};
+use chrono::{DateTime, Utc};
+use tracing::{debug, error};
+
+// 用户事件日志保留天数，超过后由后台任务清理
+pub const EVENT_RETENTION_DAYS: i32 = 7;
+
+#[derive(Debug, Clone)]
+pub struct UserEventRow {
+    pub seq: i64,
+    pub event_type: String,
+    pub payload: serde_json::Value,
+    pub created_at: DateTime<Utc>,
+}
+
+impl UserEventRow {
+    // 还原为带序列号的WebSocket消息
+    pub fn into_message(self) -> Option<WebSocketMessage> {
+        let mut message = serde_json::from_value::<WebSocketMessage>(self.payload).ok()?;
+        message.seq = Some(self.seq);
+        Some(message)
+    }
+}
+
+#[derive(Debug)]
+pub struct EventService {
+    db: Database,
+}
+
+impl EventService {
+    pub fn new(db: Database) -> Self {
+        Self { db }
+    }
+
+    // 为每个接收者追加一条事件，返回 (用户ID, 序列号)
+    pub async fn append_event(
+        &self,
+        user_ids: &[i64],
+        message: &WebSocketMessage,
+    ) -> Result<Vec<(i64, i64)>, AppError> {
+        if user_ids.is_empty() {
+            return Ok(Vec::new());
+        }
+
+        // 同一用户在一条语句中只能递增一次序列号
+        let mut user_ids = user_ids.to_vec();
+        user_ids.sort_unstable();
+        user_ids.dedup();
+
+        let event_type = serde_json::to_value(&message.message_type)
+            .ok()
+            .and_then(|v| v.as_str().map(str::to_string))
+            .unwrap_or_default();
+        let payload = serde_json::to_value(message).map_err(|e| {
+            error!("序列化用户事件失败: {:?}", e);
+            AppError::InternalServerError("序列化用户事件失败".to_string())
+        })?;
+
+        let rows = sqlx::query!(
+            r#"
+            WITH seqs AS (
+                INSERT INTO user_event_seqs (user_id, last_seq)
+                SELECT UNNEST($1::bigint[]), 1
+                ON CONFLICT (user_id) DO UPDATE SET last_seq = user_event_seqs.last_seq + 1
+                RETURNING user_id, last_seq
+            )
+            INSERT INTO user_events (user_id, seq, event_type, payload, created_at)
+            SELECT user_id, last_seq, $2, $3, $4 FROM seqs
+            RETURNING user_id, seq
+            "#,
+            &user_ids,
+            event_type,
+            payload,
+            message.timestamp
+        )
+        .fetch_all(&self.db.pg_pool)
+        .await
+        .map_err(|e| AppError::InternalServerError(format!("写入用户事件失败: {:?}", e)))?;
+
+        debug!("已为{}个用户写入{}事件", rows.len(), event_type);
+
+        Ok(rows.into_iter().map(|row| (row.user_id, row.seq)).collect())
+    }
+
+    // 获取用户最新的事件序列号
+    pub async fn get_latest_seq(&self, user_id: i64) -> Result<i64, AppError> {
+        let last_seq = sqlx::query_scalar!(
+            r#"SELECT last_seq FROM user_event_seqs WHERE user_id = $1"#,
+            user_id
+        )
+        .fetch_optional(&self.db.pg_pool)
+        .await
+        .map_err(|e| AppError::InternalServerError(format!("获取事件序列号失败: {:?}", e)))?;
+
+        Ok(last_seq.unwrap_or(0))
+    }
+
+    // 获取序列号大于since_seq的事件，按序列号升序
+    pub async fn get_events_since(
+        &self,
+        user_id: i64,
+        since_seq: i64,
+        limit: i64,
+    ) -> Result<(Vec<UserEventRow>, bool), AppError> {
+        let mut rows = sqlx::query_as!(
+            UserEventRow,
+            r#"
+            SELECT seq, event_type, payload, created_at
+            FROM user_events
+            WHERE user_id = $1 AND seq > $2
+            ORDER BY seq ASC
+            LIMIT $3
+            "#,
+            user_id,
+            since_seq,
+            limit + 1
+        )
+        .fetch_all(&self.db.pg_pool)
+        .await
+        .map_err(|e| AppError::InternalServerError(format!("获取用户事件失败: {:?}", e)))?;
+
+        let has_more = rows.len() > limit as usize;
+        if has_more {
+            rows.truncate(limit as usize);
+        }
+
+        Ok((rows, has_more))
+    }
+
+    // 清理超过保留期的事件
+    pub async fn purge_expired_events(&self) -> Result<u64, AppError> {
+        let result = sqlx::query!(
+            r#"
+            DELETE FROM user_events
+            WHERE created_at < NOW() - make_interval(days => $1)
+            "#,
+            EVENT_RETENTION_DAYS
+        )
+        .execute(&self.db.pg_pool)
+        .await
+        .map_err(|e| AppError::InternalServerError(format!("清理过期用户事件失败: {:?}", e)))?;
+
+        Ok(result.rows_affected())
+    }
+}
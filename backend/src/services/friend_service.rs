@@ -0,0 +1,418 @@
+use crate::{
+    config::db::Database,
+    models::constants::friend_request_status,
+    utils::response::{AppError, paginate},
+};
+use chrono::{DateTime, Utc};
+use sqlx::PgConnection;
+use tracing::{error, info};
+use uuid::Uuid;
+
+// 好友
+#[derive(Debug, Clone)]
+pub struct FriendRow {
+    pub id: i64,
+    pub user_id: i64,
+    pub nickname: String,
+    pub avatar_url: Option<String>,
+    pub last_active: Option<DateTime<Utc>>,
+    pub online_status: String,
+    pub friends_since: DateTime<Utc>,
+}
+
+// 好友申请，user_id/nickname/avatar_url为对方的信息
+#[derive(Debug, Clone)]
+pub struct FriendRequestRow {
+    pub id: i64,
+    pub request_id: Uuid,
+    pub requester_id: i64,
+    pub addressee_id: i64,
+    pub user_id: i64,
+    pub nickname: String,
+    pub avatar_url: Option<String>,
+    pub status: String,
+    pub created_at: DateTime<Utc>,
+}
+
+// 发送好友申请的结果：对方已向自己发出申请时直接成为好友，状态为accepted
+#[derive(Debug, Clone)]
+pub struct FriendRequestResult {
+    pub request_id: Uuid,
+    pub status: &'static str,
+}
+
+// 好友关系：一方发出申请，另一方同意后双方互为好友
+#[derive(Debug)]
+pub struct FriendService {
+    db: Database,
+}
+
+impl FriendService {
+    pub fn new(db: Database) -> Self {
+        Self { db }
+    }
+
+    // =============== 业务函数 ===============
+
+    // 发送好友申请
+    pub async fn send_request(
+        &self,
+        requester_id: i64,
+        addressee_id: i64,
+    ) -> Result<FriendRequestResult, AppError> {
+        if requester_id == addressee_id {
+            return Err(AppError::BadRequest("不能添加自己为好友".to_string()));
+        }
+
+        let mut tx = self.db.pg_pool.begin().await.map_err(|e| {
+            error!("开启事务失败: {:?}", e);
+            AppError::InternalServerError("数据库操作失败".to_string())
+        })?;
+
+        let relation = sqlx::query!(
+            r#"
+            SELECT
+                EXISTS(SELECT 1 FROM users WHERE user_id = $2) as "user_exists!",
+                EXISTS(
+                    SELECT 1 FROM user_blocks
+                    WHERE (blocker_id = $1 AND blocked_id = $2)
+                       OR (blocker_id = $2 AND blocked_id = $1)
+                ) as "blocked!",
+                EXISTS(
+                    SELECT 1 FROM friendships WHERE user_id = $1 AND friend_id = $2
+                ) as "is_friend!"
+            "#,
+            requester_id,
+            addressee_id
+        )
+        .fetch_one(&mut *tx)
+        .await
+        .map_err(|e| {
+            error!("查询好友关系失败: {:?}", e);
+            AppError::InternalServerError("发送好友申请失败".to_string())
+        })?;
+
+        if !relation.user_exists {
+            return Err(AppError::NotFound("用户不存在".to_string()));
+        }
+        if relation.blocked {
+            return Err(AppError::Forbidden("无法向该用户发送好友申请".to_string()));
+        }
+        if relation.is_friend {
+            return Err(AppError::Conflict("你们已经是好友".to_string()));
+        }
+
+        // 双方之间已有待处理的申请
+        let pending = sqlx::query!(
+            r#"
+            SELECT request_id, requester_id
+            FROM friend_requests
+            WHERE status = 'pending'
+              AND ((requester_id = $1 AND addressee_id = $2)
+                OR (requester_id = $2 AND addressee_id = $1))
+            FOR UPDATE
+            "#,
+            requester_id,
+            addressee_id
+        )
+        .fetch_optional(&mut *tx)
+        .await
+        .map_err(|e| {
+            error!("查询好友申请失败: {:?}", e);
+            AppError::InternalServerError("发送好友申请失败".to_string())
+        })?;
+
+        let result = match pending {
+            Some(pending) if pending.requester_id == requester_id => {
+                return Err(AppError::Conflict(
+                    "已发送过好友申请，请等待对方处理".to_string(),
+                ));
+            }
+            // 对方已向自己发出申请，视为同意
+            Some(pending) => {
+                Self::respond(&mut tx, pending.request_id, friend_request_status::ACCEPTED).await?;
+                Self::create_friendship(&mut tx, requester_id, addressee_id).await?;
+                FriendRequestResult {
+                    request_id: pending.request_id,
+                    status: friend_request_status::ACCEPTED,
+                }
+            }
+            None => {
+                let request_id = Uuid::new_v4();
+                sqlx::query!(
+                    r#"
+                    INSERT INTO friend_requests (request_id, requester_id, addressee_id, status, created_at)
+                    VALUES ($1, $2, $3, $4, $5)
+                    "#,
+                    request_id,
+                    requester_id,
+                    addressee_id,
+                    friend_request_status::PENDING,
+                    Utc::now()
+                )
+                .execute(&mut *tx)
+                .await
+                .map_err(|e| match e {
+                    // 并发申请被唯一索引拦截
+                    sqlx::Error::Database(db_err) if db_err.is_unique_violation() => {
+                        AppError::Conflict("已发送过好友申请，请等待对方处理".to_string())
+                    }
+                    e => {
+                        error!("保存好友申请失败: {:?}", e);
+                        AppError::InternalServerError("发送好友申请失败".to_string())
+                    }
+                })?;
+                FriendRequestResult {
+                    request_id,
+                    status: friend_request_status::PENDING,
+                }
+            }
+        };
+
+        tx.commit().await.map_err(|e| {
+            error!("提交事务失败: {:?}", e);
+            AppError::InternalServerError("数据库操作失败".to_string())
+        })?;
+
+        info!(
+            "【Service】发送好友申请成功: requester_id={}, addressee_id={}, status={}",
+            requester_id, addressee_id, result.status
+        );
+        Ok(result)
+    }
+
+    // 同意好友申请，返回申请人ID
+    pub async fn accept_request(&self, user_id: i64, request_id: Uuid) -> Result<i64, AppError> {
+        let mut tx = self.db.pg_pool.begin().await.map_err(|e| {
+            error!("开启事务失败: {:?}", e);
+            AppError::InternalServerError("数据库操作失败".to_string())
+        })?;
+
+        let requester_id = Self::lock_pending_request(&mut tx, user_id, request_id).await?;
+        Self::respond(&mut tx, request_id, friend_request_status::ACCEPTED).await?;
+        Self::create_friendship(&mut tx, user_id, requester_id).await?;
+
+        tx.commit().await.map_err(|e| {
+            error!("提交事务失败: {:?}", e);
+            AppError::InternalServerError("数据库操作失败".to_string())
+        })?;
+
+        info!(
+            "【Service】同意好友申请成功: user_id={}, requester_id={}",
+            user_id, requester_id
+        );
+        Ok(requester_id)
+    }
+
+    // 拒绝好友申请
+    pub async fn decline_request(&self, user_id: i64, request_id: Uuid) -> Result<(), AppError> {
+        let mut tx = self.db.pg_pool.begin().await.map_err(|e| {
+            error!("开启事务失败: {:?}", e);
+            AppError::InternalServerError("数据库操作失败".to_string())
+        })?;
+
+        Self::lock_pending_request(&mut tx, user_id, request_id).await?;
+        Self::respond(&mut tx, request_id, friend_request_status::DECLINED).await?;
+
+        tx.commit().await.map_err(|e| {
+            error!("提交事务失败: {:?}", e);
+            AppError::InternalServerError("数据库操作失败".to_string())
+        })?;
+
+        info!(
+            "【Service】拒绝好友申请成功: user_id={}, request_id={}",
+            user_id, request_id
+        );
+        Ok(())
+    }
+
+    // 删除好友，双方的好友关系同时解除
+    pub async fn remove_friend(&self, user_id: i64, friend_id: i64) -> Result<(), AppError> {
+        let result = sqlx::query!(
+            r#"
+            DELETE FROM friendships
+            WHERE (user_id = $1 AND friend_id = $2) OR (user_id = $2 AND friend_id = $1)
+            "#,
+            user_id,
+            friend_id
+        )
+        .execute(&self.db.pg_pool)
+        .await
+        .map_err(|e| {
+            error!("删除好友关系失败: {:?}", e);
+            AppError::InternalServerError("删除好友失败".to_string())
+        })?;
+        if result.rows_affected() == 0 {
+            return Err(AppError::NotFound("对方不是你的好友".to_string()));
+        }
+
+        info!(
+            "【Service】删除好友成功: user_id={}, friend_id={}",
+            user_id, friend_id
+        );
+        Ok(())
+    }
+
+    // 获取好友列表，按成为好友的时间倒序
+    pub async fn list_friends(
+        &self,
+        user_id: i64,
+        cursor: i64,
+        limit: i64,
+    ) -> Result<(Vec<FriendRow>, bool, Option<i64>), AppError> {
+        let rows = sqlx::query_as!(
+            FriendRow,
+            r#"
+            SELECT
+                f.id,
+                u.user_id,
+                u.nickname as "nickname!",
+                u.avatar_url,
+                u.last_active_at as last_active,
+                -- 好友设置了隐身或对当前用户不可见时显示为离线
+                CASE
+                    WHEN location_visible_to(u.user_id, $1)
+                        AND u.last_active_at > NOW() - INTERVAL '5 minutes' THEN 'online'
+                    ELSE 'offline'
+                END as "online_status!: String",
+                f.created_at as friends_since
+            FROM friendships f
+            JOIN users u ON u.user_id = f.friend_id
+            WHERE f.user_id = $1
+            AND ($2::bigint = 0 OR f.id < $2::bigint)
+            ORDER BY f.id DESC
+            LIMIT $3
+            "#,
+            user_id,
+            cursor,
+            limit + 1
+        )
+        .fetch_all(&self.db.pg_pool)
+        .await
+        .map_err(|e| {
+            error!("获取好友列表失败: {:?}", e);
+            AppError::InternalServerError("获取好友列表失败".to_string())
+        })?;
+
+        Ok(paginate(rows, limit, |row| row.id))
+    }
+
+    // 获取待处理的好友申请，outgoing为true时返回自己发出的申请
+    pub async fn list_pending_requests(
+        &self,
+        user_id: i64,
+        outgoing: bool,
+        cursor: i64,
+        limit: i64,
+    ) -> Result<(Vec<FriendRequestRow>, bool, Option<i64>), AppError> {
+        let rows = sqlx::query_as!(
+            FriendRequestRow,
+            r#"
+            SELECT
+                r.id,
+                r.request_id,
+                r.requester_id,
+                r.addressee_id,
+                u.user_id,
+                u.nickname as "nickname!",
+                u.avatar_url,
+                r.status,
+                r.created_at
+            FROM friend_requests r
+            JOIN users u ON u.user_id = CASE WHEN $2 THEN r.addressee_id ELSE r.requester_id END
+            WHERE r.status = 'pending'
+            AND CASE WHEN $2 THEN r.requester_id ELSE r.addressee_id END = $1
+            AND ($3::bigint = 0 OR r.id < $3::bigint)
+            ORDER BY r.id DESC
+            LIMIT $4
+            "#,
+            user_id,
+            outgoing,
+            cursor,
+            limit + 1
+        )
+        .fetch_all(&self.db.pg_pool)
+        .await
+        .map_err(|e| {
+            error!("获取好友申请失败: {:?}", e);
+            AppError::InternalServerError("获取好友申请失败".to_string())
+        })?;
+
+        Ok(paginate(rows, limit, |row| row.id))
+    }
+
+    // =============== 辅助函数 ===============
+
+    // 锁定发给该用户的待处理申请，返回申请人ID
+    async fn lock_pending_request(
+        conn: &mut PgConnection,
+        user_id: i64,
+        request_id: Uuid,
+    ) -> Result<i64, AppError> {
+        sqlx::query_scalar!(
+            r#"
+            SELECT requester_id FROM friend_requests
+            WHERE request_id = $1 AND addressee_id = $2 AND status = 'pending'
+            FOR UPDATE
+            "#,
+            request_id,
+            user_id
+        )
+        .fetch_optional(&mut *conn)
+        .await
+        .map_err(|e| {
+            error!("查询好友申请失败: {:?}", e);
+            AppError::InternalServerError("处理好友申请失败".to_string())
+        })?
+        .ok_or_else(|| AppError::NotFound("好友申请不存在或已处理".to_string()))
+    }
+
+    async fn respond(
+        conn: &mut PgConnection,
+        request_id: Uuid,
+        status: &str,
+    ) -> Result<(), AppError> {
+        sqlx::query!(
+            r#"
+            UPDATE friend_requests
+            SET status = $1, responded_at = $2
+            WHERE request_id = $3
+            "#,
+            status,
+            Utc::now(),
+            request_id
+        )
+        .execute(&mut *conn)
+        .await
+        .map_err(|e| {
+            error!("更新好友申请失败: {:?}", e);
+            AppError::InternalServerError("处理好友申请失败".to_string())
+        })?;
+        Ok(())
+    }
+
+    // 双向写入好友关系
+    async fn create_friendship(
+        conn: &mut PgConnection,
+        user_a: i64,
+        user_b: i64,
+    ) -> Result<(), AppError> {
+        sqlx::query!(
+            r#"
+            INSERT INTO friendships (user_id, friend_id, created_at)
+            VALUES ($1, $2, $3), ($2, $1, $3)
+            ON CONFLICT (user_id, friend_id) DO NOTHING
+            "#,
+            user_a,
+            user_b,
+            Utc::now()
+        )
+        .execute(&mut *conn)
+        .await
+        .map_err(|e| {
+            error!("保存好友关系失败: {:?}", e);
+            AppError::InternalServerError("保存好友关系失败".to_string())
+        })?;
+        Ok(())
+    }
+}
@@ -0,0 +1,320 @@
+use crate::{
+    config::{app_config::GeocodeConfig, db::Database, location::DEFAULT_LOCATION_NAME},
+    utils::response::AppError,
+};
+use tracing::{error, info, warn};
+
+// 每批导入的地点数
+const IMPORT_BATCH_SIZE: usize = 1000;
+
+// 逆地理编码结果，缺失的行政级别为空
+#[derive(Debug, Clone)]
+pub struct PlaceRow {
+    pub country: String,
+    pub province: Option<String>,
+    pub city: Option<String>,
+    pub district: Option<String>,
+    pub distance: f64,
+}
+
+impl PlaceRow {
+    // 按省、市、区县拼接地址名称，直辖市等省市同名时只保留一个；都缺失时使用国家名称。
+    // 中文和日文由大到小直接连接（如“北京市东城区”），韩文由大到小以空格分隔，
+    // 其他文字由小到大以逗号分隔（如“Westminster, London, England”）
+    pub fn display_name(&self) -> String {
+        let mut parts: Vec<&str> = Vec::new();
+        for part in [&self.province, &self.city, &self.district]
+            .into_iter()
+            .flatten()
+        {
+            if parts.last() != Some(&part.as_str()) {
+                parts.push(part);
+            }
+        }
+
+        if parts.is_empty() {
+            return self.country.clone();
+        }
+
+        let letters = || {
+            parts
+                .iter()
+                .flat_map(|part| part.chars())
+                .filter(|c| c.is_alphabetic())
+        };
+        if letters().all(is_han_or_kana) {
+            parts.concat()
+        } else if letters().all(|c| is_han_or_kana(c) || is_hangul(c)) {
+            parts.join(" ")
+        } else {
+            parts.reverse();
+            parts.join(", ")
+        }
+    }
+}
+
+// 汉字（含日文汉字）和日文假名
+fn is_han_or_kana(c: char) -> bool {
+    matches!(c,
+        '\u{3040}'..='\u{30FF}'
+        | '\u{3400}'..='\u{4DBF}'
+        | '\u{4E00}'..='\u{9FFF}'
+        | '\u{F900}'..='\u{FAFF}'
+        | '\u{20000}'..='\u{2FA1F}')
+}
+
+// 韩文字母和音节
+fn is_hangul(c: char) -> bool {
+    matches!(c,
+        '\u{1100}'..='\u{11FF}' | '\u{3130}'..='\u{318F}' | '\u{AC00}'..='\u{D7AF}')
+}
+
+// 离线逆地理编码：从本地导入的地名库中查找距离坐标最近的地点
+#[derive(Debug)]
+pub struct GeocodeService {
+    db: Database,
+    config: GeocodeConfig,
+}
+
+impl GeocodeService {
+    pub fn new(db: Database, config: GeocodeConfig) -> Self {
+        Self { db, config }
+    }
+
+    // 地名库为空时从配置的文件导入，返回导入的地点数。
+    // 文件每行为“纬度\t经度\t国家\t省份\t城市\t区县”，#开头的行为注释
+    pub async fn import_gazetteer(&self) -> Result<u64, AppError> {
+        let Some(path) = self.config.gazetteer_file.as_deref() else {
+            return Ok(0);
+        };
+
+        let existing =
+            sqlx::query_scalar!(r#"SELECT EXISTS(SELECT 1 FROM gazetteer_places) as "exists!""#)
+                .fetch_one(&self.db.pg_pool)
+                .await
+                .map_err(|e| AppError::InternalServerError(format!("查询地名库失败: {:?}", e)))?;
+        if existing {
+            info!("【Service】地名库已有数据，跳过导入: {}", path);
+            return Ok(0);
+        }
+
+        let content = tokio::fs::read_to_string(path)
+            .await
+            .map_err(|e| AppError::InternalServerError(format!("读取地名库文件失败: {:?}", e)))?;
+
+        let mut places = Vec::new();
+        for (index, line) in content.lines().enumerate() {
+            if line.trim().is_empty() || line.starts_with('#') {
+                continue;
+            }
+            match parse_place(line) {
+                Some(place) => places.push(place),
+                None => warn!("地名库第{}行格式错误，已跳过", index + 1),
+            }
+        }
+
+        let mut tx = self
+            .db
+            .pg_pool
+            .begin()
+            .await
+            .map_err(|e| AppError::InternalServerError(format!("开始导入事务失败: {:?}", e)))?;
+
+        let mut imported = 0;
+        for batch in places.chunks(IMPORT_BATCH_SIZE) {
+            let latitudes: Vec<f64> = batch.iter().map(|p| p.latitude).collect();
+            let longitudes: Vec<f64> = batch.iter().map(|p| p.longitude).collect();
+            let countries: Vec<String> = batch.iter().map(|p| p.country.clone()).collect();
+            let provinces: Vec<String> = batch.iter().map(|p| p.province.clone()).collect();
+            let cities: Vec<String> = batch.iter().map(|p| p.city.clone()).collect();
+            let districts: Vec<String> = batch.iter().map(|p| p.district.clone()).collect();
+
+            let result = sqlx::query!(
+                r#"
+                INSERT INTO gazetteer_places (latitude, longitude, country, province, city, district)
+                SELECT lat, lon, country, NULLIF(province, ''), NULLIF(city, ''), NULLIF(district, '')
+                FROM UNNEST($1::float8[], $2::float8[], $3::text[], $4::text[], $5::text[], $6::text[])
+                    AS t(lat, lon, country, province, city, district)
+                "#,
+                &latitudes,
+                &longitudes,
+                &countries,
+                &provinces,
+                &cities,
+                &districts
+            )
+            .execute(&mut *tx)
+            .await
+            .map_err(|e| AppError::InternalServerError(format!("导入地名库失败: {:?}", e)))?;
+            imported += result.rows_affected();
+        }
+
+        tx.commit()
+            .await
+            .map_err(|e| AppError::InternalServerError(format!("提交导入事务失败: {:?}", e)))?;
+
+        Ok(imported)
+    }
+
+    // 逆地理编码，超过最大距离没有地点时返回None
+    pub async fn reverse_geocode(
+        &self,
+        latitude: f64,
+        longitude: f64,
+    ) -> Result<Option<PlaceRow>, AppError> {
+        sqlx::query_as!(
+            PlaceRow,
+            r#"
+            SELECT
+                country,
+                province,
+                city,
+                district,
+                ST_Distance(geom, ST_SetSRID(ST_MakePoint($2, $1), 4326)::geography) as "distance!"
+            FROM gazetteer_places
+            WHERE ST_DWithin(geom, ST_SetSRID(ST_MakePoint($2, $1), 4326)::geography, $3)
+            ORDER BY ST_Distance(geom, ST_SetSRID(ST_MakePoint($2, $1), 4326)::geography)
+            LIMIT 1
+            "#,
+            latitude,
+            longitude,
+            self.config.max_distance_meters
+        )
+        .fetch_optional(&self.db.pg_pool)
+        .await
+        .map_err(|e| {
+            error!("逆地理编码失败: {:?}", e);
+            AppError::InternalServerError("逆地理编码失败".to_string())
+        })
+    }
+
+    // 确定位置名称：客户端提供时使用去除首尾空白后的名称，否则通过逆地理编码生成，
+    // 地名库中找不到时使用默认位置名称
+    pub async fn resolve_location_name(
+        &self,
+        latitude: f64,
+        longitude: f64,
+        location_name: Option<&str>,
+    ) -> Result<String, AppError> {
+        if let Some(name) = location_name.map(str::trim).filter(|s| !s.is_empty()) {
+            return Ok(name.to_string());
+        }
+
+        let name = match self.reverse_geocode(latitude, longitude).await? {
+            Some(place) => place.display_name(),
+            None => DEFAULT_LOCATION_NAME.to_string(),
+        };
+        info!(
+            "【Service】已生成位置名称: 位置=({}, {}), 名称={}",
+            longitude, latitude, name
+        );
+        Ok(name)
+    }
+}
+
+// 待导入的地点，缺失的行政级别为空字符串
+struct GazetteerLine {
+    latitude: f64,
+    longitude: f64,
+    country: String,
+    province: String,
+    city: String,
+    district: String,
+}
+
+fn parse_place(line: &str) -> Option<GazetteerLine> {
+    let fields: Vec<&str> = line.split('\t').map(str::trim).collect();
+    if fields.len() < 3 || fields[2].is_empty() {
+        return None;
+    }
+
+    let latitude: f64 = fields[0].parse().ok()?;
+    let longitude: f64 = fields[1].parse().ok()?;
+    if !(-90.0..=90.0).contains(&latitude) || !(-180.0..=180.0).contains(&longitude) {
+        return None;
+    }
+
+    let field = |i: usize| fields.get(i).copied().unwrap_or_default().to_string();
+    Some(GazetteerLine {
+        latitude,
+        longitude,
+        country: field(2),
+        province: field(3),
+        city: field(4),
+        district: field(5),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn place(province: Option<&str>, city: Option<&str>, district: Option<&str>) -> PlaceRow {
+        PlaceRow {
+            country: "国家".to_string(),
+            province: province.map(str::to_string),
+            city: city.map(str::to_string),
+            district: district.map(str::to_string),
+            distance: 0.0,
+        }
+    }
+
+    #[test]
+    fn display_name_joins_chinese_without_separator() {
+        let row = place(Some("河南省"), Some("郑州市"), Some("金水区"));
+        assert_eq!(row.display_name(), "河南省郑州市金水区");
+    }
+
+    #[test]
+    fn display_name_merges_same_province_and_city() {
+        let row = place(Some("北京市"), Some("北京市"), Some("东城区"));
+        assert_eq!(row.display_name(), "北京市东城区");
+    }
+
+    #[test]
+    fn display_name_uses_spaces_for_korean() {
+        let row = place(Some("서울특별시"), None, Some("종로구"));
+        assert_eq!(row.display_name(), "서울특별시 종로구");
+    }
+
+    #[test]
+    fn display_name_lists_latin_from_smallest() {
+        let row = place(Some("England"), Some("London"), Some("Westminster"));
+        assert_eq!(row.display_name(), "Westminster, London, England");
+    }
+
+    #[test]
+    fn display_name_falls_back_to_country() {
+        assert_eq!(place(None, None, None).display_name(), "国家");
+    }
+
+    #[test]
+    fn parse_place_reads_all_fields() {
+        let line = parse_place("34.80\t113.66\t中国\t河南省\t郑州市\t金水区").unwrap();
+        assert_eq!(line.latitude, 34.80);
+        assert_eq!(line.longitude, 113.66);
+        assert_eq!(line.country, "中国");
+        assert_eq!(line.province, "河南省");
+        assert_eq!(line.city, "郑州市");
+        assert_eq!(line.district, "金水区");
+    }
+
+    #[test]
+    fn parse_place_leaves_missing_levels_empty() {
+        let line = parse_place("35.07\t112.60\t中国\t河南省\t济源市\t").unwrap();
+        assert_eq!(line.district, "");
+
+        let line = parse_place("1.29\t103.85\tSingapore").unwrap();
+        assert_eq!(line.province, "");
+        assert_eq!(line.city, "");
+    }
+
+    #[test]
+    fn parse_place_rejects_invalid_lines() {
+        assert!(parse_place("34.80\t113.66").is_none());
+        assert!(parse_place("34.80\t113.66\t\t河南省").is_none());
+        assert!(parse_place("abc\t113.66\t中国").is_none());
+        assert!(parse_place("91.0\t113.66\t中国").is_none());
+        assert!(parse_place("34.80\t181.0\t中国").is_none());
+    }
+}
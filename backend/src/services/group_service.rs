@@ -11,7 +11,7 @@ use crate::{
         location::{DEFAULT_LATITUDE, DEFAULT_LONGITUDE},
     },
     models::constants::group_roles,
-    utils::response::AppError,
+    utils::{geo::Viewport, response::AppError},
 };
 
 // =============== 请求参数结构体 ===============
@@ -760,6 +760,73 @@ impl GroupService {
         Ok((groups, has_more, next_cursor))
     }
 
+    // 查找地图可视范围内的群组，距离相对于范围中心
+    pub async fn search_groups_in_viewport(
+        &self,
+        user_id: i64,
+        viewport: Viewport,
+        cursor: i64,
+        limit: i64,
+    ) -> Result<(Vec<GroupRow>, bool, Option<i64>), AppError> {
+        let (center_latitude, center_longitude) = viewport.center();
+        let mut rows = sqlx::query_as!(
+            GroupRow,
+            r#"
+            SELECT
+                g.id,
+                g.group_id,
+                g.name,
+                g.description,
+                g.owner_id,
+                u.nickname as creator_name,
+                g.created_at,
+                g.updated_at as last_active_at,
+                g.latitude,
+                g.longitude,
+                g.location_name,
+                (SELECT COUNT(*) FROM group_members WHERE group_id = g.group_id) as "member_count!",
+                ST_Distance(
+                    ST_MakePoint(g.longitude, g.latitude)::geography,
+                    ST_MakePoint($7, $6)::geography
+                ) as "distance!",
+                (g.password_hash IS NOT NULL) as "is_password_required!",
+                (EXISTS(SELECT 1 FROM group_members WHERE group_id = g.group_id AND user_id = $1)) as "is_member!",
+                COALESCE((SELECT role FROM group_members WHERE group_id = g.group_id AND user_id = $1), '') as "user_role!"
+            FROM groups g
+            JOIN users u ON g.owner_id = u.user_id
+            WHERE ($8::bigint = 0 OR g.id < $8::bigint)
+            AND ST_Intersects(g.geom::geometry, ST_MakeEnvelope($3, $2, $5, $4, 4326))
+            ORDER BY g.id DESC
+            LIMIT $9
+            "#,
+            user_id,
+            viewport.south,
+            viewport.west,
+            viewport.north,
+            viewport.east,
+            center_latitude,
+            center_longitude,
+            cursor,
+            limit + 1
+        )
+        .fetch_all(&self.db.pg_pool)
+        .await
+        .map_err(|e| {
+            tracing::error!("查询可视范围内群组失败: {:?}", e);
+            AppError::InternalServerError("查询可视范围内群组失败".to_string())
+        })?;
+
+        let has_more = rows.len() > limit as usize;
+        rows.truncate(limit as usize);
+        let next_cursor = if has_more {
+            rows.last().map(|group| group.id)
+        } else {
+            None
+        };
+
+        Ok((rows, has_more, next_cursor))
+    }
+
     // 根据名称搜索群组
     pub async fn search_group_by_name(
         &self,
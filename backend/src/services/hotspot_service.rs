@@ -0,0 +1,317 @@
+use crate::{
+    config::{
+        app_config::HotspotConfig,
+        db::Database,
+        location::{DEFAULT_LATITUDE, DEFAULT_LONGITUDE},
+    },
+    utils::{geohash, response::AppError},
+};
+use chrono::{DateTime, Utc};
+use tracing::{error, info};
+
+// 预聚合使用的geohash精度，查询时只能合并为更粗的单元
+pub const ROLLUP_PRECISION: u32 = 6;
+
+// 自动选择精度时覆盖范围的单元数上限
+const TARGET_CELL_COUNT: f64 = 1024.0;
+
+// 一次最多返回的单元数
+const MAX_HEATMAP_CELLS: i64 = 2000;
+
+// 刷新预聚合数据使用的事务级咨询锁，多个节点同时只有一个执行统计
+const ROLLUP_ADVISORY_LOCK_KEY: i64 = 0x6765_6f74_686f_7401;
+
+// 按范围大小选择geohash精度：覆盖范围的单元数不超过上限的最大精度
+pub fn precision_for_bounds(south: f64, west: f64, north: f64, east: f64) -> u32 {
+    (1..=ROLLUP_PRECISION)
+        .rev()
+        .find(|&precision| {
+            let (height, width) = geohash::cell_size(precision);
+            let rows = ((north - south) / height).ceil() + 1.0;
+            let columns = ((east - west) / width).ceil() + 1.0;
+            rows * columns <= TARGET_CELL_COUNT
+        })
+        .unwrap_or(1)
+}
+
+// 热力图单元的统计结果
+#[derive(Debug, Clone)]
+pub struct HeatmapCellRow {
+    pub geohash: String,
+    pub checkin_count: i64,
+    pub message_count: i64,
+    pub active_user_count: i64,
+}
+
+// 热力图查询条件，precision为合并后的geohash精度
+#[derive(Debug, Clone, Copy)]
+pub struct HeatmapQuery {
+    pub south: f64,
+    pub west: f64,
+    pub north: f64,
+    pub east: f64,
+    pub start_time: DateTime<Utc>,
+    pub end_time: DateTime<Utc>,
+    pub precision: u32,
+}
+
+// 热力图：定时把签到、群组消息和活跃用户按小时和geohash单元预聚合，查询时只读取预聚合数据
+#[derive(Debug)]
+pub struct HotspotService {
+    db: Database,
+    config: HotspotConfig,
+}
+
+impl HotspotService {
+    pub fn new(db: Database, config: HotspotConfig) -> Self {
+        Self { db, config }
+    }
+
+    pub fn config(&self) -> HotspotConfig {
+        self.config
+    }
+
+    // 重新统计最近一个已聚合的小时及之后的数据，首次运行时统计整个保留期，返回写入的单元数。
+    // 群组消息和活跃用户按发送者的位置隐私级别模糊后统计，hidden级别和隐身用户不计入；
+    // 没有位置的消息（坐标为0）不计入。活跃用户按位置轨迹统计，每人每小时计一次，
+    // 默认位置不是用户上报的真实位置，不计入。其他节点正在统计时跳过本次运行，返回None
+    pub async fn refresh_rollups(&self) -> Result<Option<u64>, AppError> {
+        let mut tx = self
+            .db
+            .pg_pool
+            .begin()
+            .await
+            .map_err(|e| AppError::InternalServerError(format!("开始统计事务失败: {:?}", e)))?;
+
+        let locked = sqlx::query_scalar!(
+            r#"SELECT pg_try_advisory_xact_lock($1) as "locked!""#,
+            ROLLUP_ADVISORY_LOCK_KEY
+        )
+        .fetch_one(&mut *tx)
+        .await
+        .map_err(|e| AppError::InternalServerError(format!("获取热力图统计锁失败: {:?}", e)))?;
+        if !locked {
+            return Ok(None);
+        }
+
+        let from = sqlx::query_scalar!(
+            r#"
+            SELECT COALESCE(
+                MAX(bucket_start),
+                date_trunc('hour', NOW() - make_interval(days => $1))
+            ) as "from!"
+            FROM hotspot_rollups
+            "#,
+            self.config.retention_days as i32
+        )
+        .fetch_one(&mut *tx)
+        .await
+        .map_err(|e| AppError::InternalServerError(format!("查询热力图统计进度失败: {:?}", e)))?;
+
+        sqlx::query!(
+            r#"DELETE FROM hotspot_rollups WHERE bucket_start >= $1"#,
+            from
+        )
+        .execute(&mut *tx)
+        .await
+        .map_err(|e| {
+            AppError::InternalServerError(format!("清除待刷新的热力图统计失败: {:?}", e))
+        })?;
+
+        let result = sqlx::query!(
+            r#"
+            INSERT INTO hotspot_rollups
+                (bucket_start, geohash, latitude, longitude, checkin_count, message_count, active_user_count)
+            SELECT
+                bucket_start,
+                geohash,
+                AVG(latitude),
+                AVG(longitude),
+                SUM(checkins)::int,
+                SUM(messages)::int,
+                SUM(users)::int
+            FROM (
+                SELECT
+                    date_trunc('hour', c.created_at) as bucket_start,
+                    ST_GeoHash(c.geom::geometry, $2) as geohash,
+                    c.latitude,
+                    c.longitude,
+                    1 as checkins, 0 as messages, 0 as users
+                FROM checkins c
+                WHERE c.created_at >= $1 AND c.geom IS NOT NULL
+                UNION ALL
+                SELECT
+                    date_trunc('hour', gm.created_at),
+                    ST_GeoHash(p.point, $2),
+                    fuzz_coordinate(gm.latitude, u.location_privacy),
+                    fuzz_coordinate(gm.longitude, u.location_privacy),
+                    0, 1, 0
+                FROM group_messages gm
+                JOIN users u ON u.user_id = gm.sender_id
+                CROSS JOIN LATERAL (
+                    SELECT CASE
+                        WHEN u.location_privacy = 'exact' THEN gm.geom::geometry
+                        ELSE ST_SetSRID(ST_MakePoint(
+                            fuzz_coordinate(gm.longitude, u.location_privacy),
+                            fuzz_coordinate(gm.latitude, u.location_privacy)
+                        ), 4326)
+                    END as point
+                ) p
+                WHERE gm.created_at >= $1
+                AND gm.geom IS NOT NULL
+                AND NOT (gm.latitude = 0 AND gm.longitude = 0)
+                AND u.location_privacy <> 'hidden'
+                UNION ALL
+                SELECT
+                    h.bucket_start,
+                    ST_GeoHash(p.point, $2),
+                    fuzz_coordinate(h.latitude, u.location_privacy),
+                    fuzz_coordinate(h.longitude, u.location_privacy),
+                    0, 0, 1
+                FROM (
+                    -- 每个用户每小时取最后一个轨迹点
+                    SELECT DISTINCT ON (user_id, date_trunc('hour', recorded_at))
+                        user_id,
+                        date_trunc('hour', recorded_at) as bucket_start,
+                        latitude,
+                        longitude,
+                        geom
+                    FROM user_location_history
+                    WHERE recorded_at >= $1
+                    AND NOT (latitude = $3 AND longitude = $4)
+                    ORDER BY user_id, date_trunc('hour', recorded_at), recorded_at DESC
+                ) h
+                JOIN users u ON u.user_id = h.user_id
+                CROSS JOIN LATERAL (
+                    SELECT CASE
+                        WHEN u.location_privacy = 'exact' THEN h.geom::geometry
+                        ELSE ST_SetSRID(ST_MakePoint(
+                            fuzz_coordinate(h.longitude, u.location_privacy),
+                            fuzz_coordinate(h.latitude, u.location_privacy)
+                        ), 4326)
+                    END as point
+                ) p
+                WHERE h.geom IS NOT NULL
+                AND u.location_privacy <> 'hidden'
+                AND (u.location_visibility = 'everyone' OR u.location_visibility_expires_at <= NOW())
+            ) events
+            GROUP BY bucket_start, geohash
+            "#,
+            from,
+            ROLLUP_PRECISION as i32,
+            DEFAULT_LATITUDE,
+            DEFAULT_LONGITUDE
+        )
+        .execute(&mut *tx)
+        .await
+        .map_err(|e| AppError::InternalServerError(format!("统计热力图失败: {:?}", e)))?;
+
+        tx.commit()
+            .await
+            .map_err(|e| AppError::InternalServerError(format!("提交统计事务失败: {:?}", e)))?;
+
+        Ok(Some(result.rows_affected()))
+    }
+
+    // 清理超过保留期的预聚合数据
+    pub async fn purge_expired(&self) -> Result<u64, AppError> {
+        let result = sqlx::query!(
+            r#"
+            DELETE FROM hotspot_rollups
+            WHERE bucket_start < date_trunc('hour', NOW() - make_interval(days => $1))
+            "#,
+            self.config.retention_days as i32
+        )
+        .execute(&self.db.pg_pool)
+        .await
+        .map_err(|e| AppError::InternalServerError(format!("清理过期热力图统计失败: {:?}", e)))?;
+
+        Ok(result.rows_affected())
+    }
+
+    // 获取范围和时间段内的热力图，按小时粒度统计，单元过多时只返回总数最高的部分
+    pub async fn get_heatmap(&self, query: HeatmapQuery) -> Result<Vec<HeatmapCellRow>, AppError> {
+        info!(
+            "【Service】开始获取热力图: 范围=({}, {})-({}, {}), 时间={}~{}, 精度={}",
+            query.south,
+            query.west,
+            query.north,
+            query.east,
+            query.start_time,
+            query.end_time,
+            query.precision
+        );
+
+        let cells = sqlx::query_as!(
+            HeatmapCellRow,
+            r#"
+            SELECT
+                left(geohash, $7) as "geohash!",
+                SUM(checkin_count)::bigint as "checkin_count!",
+                SUM(message_count)::bigint as "message_count!",
+                SUM(active_user_count)::bigint as "active_user_count!"
+            FROM hotspot_rollups
+            WHERE bucket_start >= date_trunc('hour', $5::timestamptz)
+            AND bucket_start < $6
+            AND latitude BETWEEN $1 AND $3
+            AND longitude BETWEEN $2 AND $4
+            GROUP BY 1
+            ORDER BY SUM(checkin_count + message_count + active_user_count) DESC
+            LIMIT $8
+            "#,
+            query.south,
+            query.west,
+            query.north,
+            query.east,
+            query.start_time,
+            query.end_time,
+            query.precision as i32,
+            MAX_HEATMAP_CELLS
+        )
+        .fetch_all(&self.db.pg_pool)
+        .await
+        .map_err(|e| {
+            error!("获取热力图失败: {:?}", e);
+            AppError::InternalServerError("获取热力图失败".to_string())
+        })?;
+
+        info!("【Service】获取热力图成功: 返回{}个单元", cells.len());
+        Ok(cells)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn precision_for_small_bounds_uses_rollup_precision() {
+        assert_eq!(
+            precision_for_bounds(39.90, 116.40, 39.95, 116.48),
+            ROLLUP_PRECISION
+        );
+    }
+
+    #[test]
+    fn precision_for_whole_world_is_coarsest() {
+        assert_eq!(precision_for_bounds(-90.0, -180.0, 90.0, 180.0), 1);
+    }
+
+    #[test]
+    fn precision_for_zero_size_bounds_uses_rollup_precision() {
+        assert_eq!(
+            precision_for_bounds(35.16, 112.68, 35.16, 112.68),
+            ROLLUP_PRECISION
+        );
+    }
+
+    #[test]
+    fn precision_keeps_cell_count_within_target() {
+        let precision = precision_for_bounds(20.0, 100.0, 45.0, 130.0);
+        let (height, width) = geohash::cell_size(precision);
+        let rows = (25.0 / height).ceil() + 1.0;
+        let columns = (30.0 / width).ceil() + 1.0;
+        assert!(rows * columns <= TARGET_CELL_COUNT);
+        assert!(precision < ROLLUP_PRECISION);
+    }
+}
@@ -0,0 +1,180 @@
+use crate::{
+    config::{app_config::LocationHistoryConfig, db::Database},
+    utils::response::{AppError, paginate},
+};
+use chrono::{DateTime, Utc};
+use tracing::{debug, error, info};
+
+// 轨迹点
+#[derive(Debug, Clone)]
+pub struct LocationHistoryRow {
+    pub id: i64,
+    pub latitude: f64,
+    pub longitude: f64,
+    pub accuracy: Option<f64>,
+    pub speed: Option<f64>,
+    pub heading: Option<f64>,
+    pub location_name: Option<String>,
+    pub recorded_at: DateTime<Utc>,
+}
+
+// 客户端上报的位置，accuracy/speed/heading由设备提供，可能缺失
+#[derive(Debug, Clone, Copy)]
+pub struct LocationPoint<'a> {
+    pub latitude: f64,
+    pub longitude: f64,
+    pub accuracy: Option<f64>,
+    pub speed: Option<f64>,
+    pub heading: Option<f64>,
+    pub location_name: &'a str,
+}
+
+// 位置轨迹：每次更新位置时追加一个点，与上一个点几乎相同时不重复记录。
+// 轨迹只对用户本人可见，超过保留期的记录由定时任务清理
+#[derive(Debug)]
+pub struct LocationHistoryService {
+    db: Database,
+    config: LocationHistoryConfig,
+}
+
+impl LocationHistoryService {
+    pub fn new(db: Database, config: LocationHistoryConfig) -> Self {
+        Self { db, config }
+    }
+
+    // 追加轨迹点，与上一个点距离和时间都很接近时跳过，返回是否记录
+    pub async fn record_point(
+        &self,
+        user_id: i64,
+        point: LocationPoint<'_>,
+    ) -> Result<bool, AppError> {
+        let result = sqlx::query!(
+            r#"
+            INSERT INTO user_location_history
+                (user_id, latitude, longitude, accuracy, speed, heading, location_name, recorded_at)
+            SELECT $1, $2, $3, $4, $5, $6, $7, NOW()
+            WHERE NOT EXISTS (
+                SELECT 1 FROM (
+                    SELECT geom, recorded_at FROM user_location_history
+                    WHERE user_id = $1
+                    ORDER BY recorded_at DESC
+                    LIMIT 1
+                ) last
+                WHERE last.recorded_at > NOW() - make_interval(secs => $9)
+                AND ST_DWithin(last.geom, ST_SetSRID(ST_MakePoint($3, $2), 4326)::geography, $8)
+            )
+            "#,
+            user_id,
+            point.latitude,
+            point.longitude,
+            point.accuracy,
+            point.speed,
+            point.heading,
+            point.location_name,
+            self.config.dedup_distance_meters,
+            self.config.dedup_interval_secs as f64
+        )
+        .execute(&self.db.pg_pool)
+        .await
+        .map_err(|e| {
+            error!("记录位置轨迹失败: {:?}", e);
+            AppError::InternalServerError("记录位置轨迹失败".to_string())
+        })?;
+
+        let recorded = result.rows_affected() > 0;
+        if !recorded {
+            debug!("与上一个轨迹点相同，跳过记录: user_id={}", user_id);
+        }
+        Ok(recorded)
+    }
+
+    // 获取时间范围内的轨迹，按时间倒序分页
+    pub async fn list_history(
+        &self,
+        user_id: i64,
+        start_time: Option<DateTime<Utc>>,
+        end_time: Option<DateTime<Utc>>,
+        cursor: i64,
+        limit: i64,
+    ) -> Result<(Vec<LocationHistoryRow>, bool, Option<i64>), AppError> {
+        let rows = sqlx::query_as!(
+            LocationHistoryRow,
+            r#"
+            SELECT id, latitude, longitude, accuracy, speed, heading, location_name, recorded_at
+            FROM user_location_history
+            WHERE user_id = $1
+            AND ($2::timestamptz IS NULL OR recorded_at >= $2)
+            AND ($3::timestamptz IS NULL OR recorded_at <= $3)
+            AND ($4::bigint = 0 OR id < $4::bigint)
+            ORDER BY id DESC
+            LIMIT $5
+            "#,
+            user_id,
+            start_time,
+            end_time,
+            cursor,
+            limit + 1
+        )
+        .fetch_all(&self.db.pg_pool)
+        .await
+        .map_err(|e| {
+            error!("获取位置轨迹失败: {:?}", e);
+            AppError::InternalServerError("获取位置轨迹失败".to_string())
+        })?;
+
+        Ok(paginate(rows, limit, |row| row.id))
+    }
+
+    // 删除时间范围内的轨迹，未指定范围时删除全部，返回删除的条数
+    pub async fn delete_history(
+        &self,
+        user_id: i64,
+        start_time: Option<DateTime<Utc>>,
+        end_time: Option<DateTime<Utc>>,
+    ) -> Result<u64, AppError> {
+        let result = sqlx::query!(
+            r#"
+            DELETE FROM user_location_history
+            WHERE user_id = $1
+            AND ($2::timestamptz IS NULL OR recorded_at >= $2)
+            AND ($3::timestamptz IS NULL OR recorded_at <= $3)
+            "#,
+            user_id,
+            start_time,
+            end_time
+        )
+        .execute(&self.db.pg_pool)
+        .await
+        .map_err(|e| {
+            error!("删除位置轨迹失败: {:?}", e);
+            AppError::InternalServerError("删除位置轨迹失败".to_string())
+        })?;
+
+        info!(
+            "【Service】已删除位置轨迹: user_id={}, 条数={}",
+            user_id,
+            result.rows_affected()
+        );
+        Ok(result.rows_affected())
+    }
+
+    // 清理超过保留期的轨迹
+    pub async fn purge_expired(&self) -> Result<u64, AppError> {
+        if self.config.retention_days == 0 {
+            return Ok(0);
+        }
+
+        let result = sqlx::query!(
+            r#"
+            DELETE FROM user_location_history
+            WHERE recorded_at < NOW() - make_interval(days => $1)
+            "#,
+            self.config.retention_days as i32
+        )
+        .execute(&self.db.pg_pool)
+        .await
+        .map_err(|e| AppError::InternalServerError(format!("清理过期位置轨迹失败: {:?}", e)))?;
+
+        Ok(result.rows_affected())
+    }
+}
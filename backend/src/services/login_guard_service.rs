@@ -0,0 +1,424 @@
+use crate::{
+    config::{app_config::LoginGuardConfig, db::Database},
+    utils::{redis::RedisExecutor, response::AppError},
+};
+use redis::{AsyncCommands, Script};
+use std::collections::HashMap;
+use std::net::IpAddr;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+use tracing::{error, warn};
+
+// Redis中计数键的前缀
+const GUARD_KEY_PREFIX: &str = "geotalk:login_guard:";
+// 锁定次数的记忆时长（秒），期间再次触发锁定时锁定时长翻倍
+const LOCKOUT_MEMORY_SECS: u64 = 24 * 60 * 60;
+// 登录失败后的首次延迟，之后每次失败翻倍
+const BASE_DELAY: Duration = Duration::from_millis(250);
+// 登录延迟上限
+const MAX_DELAY: Duration = Duration::from_secs(4);
+
+// 计数脚本：窗口内首次失败时以0初始化计数并设置过期时间，计数加一后达到上限时锁定并清零计数，
+// 锁定时长的计算与lockout_duration一致，返回锁定秒数（未锁定时为0）
+const RECORD_SCRIPT: &str = r#"
+local limit = tonumber(ARGV[1])
+local base = tonumber(ARGV[3])
+local max_secs = math.max(tonumber(ARGV[4]), base)
+redis.call('SET', KEYS[1], 0, 'NX', 'EX', ARGV[2])
+local count = redis.call('INCR', KEYS[1])
+if count < limit then
+    return 0
+end
+local lockouts = redis.call('INCR', KEYS[2])
+redis.call('EXPIRE', KEYS[2], ARGV[5])
+local seconds = math.min(base * 2 ^ math.min(lockouts - 1, 20), max_secs)
+redis.call('SET', KEYS[3], lockouts, 'EX', seconds)
+redis.call('DEL', KEYS[1])
+return seconds
+"#;
+
+// 某个维度（登录ID/IP）当前的尝试状态
+#[derive(Debug, Clone, Copy, Default)]
+struct AttemptState {
+    count: u32,
+    // 剩余锁定秒数，未锁定时为None
+    locked_for: Option<u64>,
+}
+
+// 内存中的尝试记录，未配置Redis时使用
+#[derive(Debug)]
+struct AttemptRecord {
+    count: u32,
+    window_expires_at: Instant,
+    lockouts: u32,
+    lockouts_expire_at: Instant,
+    locked_until: Option<Instant>,
+}
+
+// 登录和账号创建的防暴力破解：按登录ID和IP统计失败次数，逐步延迟并临时锁定。
+// 配置了Redis时计数存入Redis在多节点间共享，否则保存在本节点内存中
+#[derive(Debug)]
+pub struct LoginGuardService {
+    config: LoginGuardConfig,
+    redis: Option<RedisExecutor>,
+    record_script: Script,
+    memory: Mutex<HashMap<String, AttemptRecord>>,
+}
+
+impl LoginGuardService {
+    pub fn new(db: &Database, config: LoginGuardConfig) -> Self {
+        Self {
+            config,
+            redis: db.redis_client.clone().map(RedisExecutor::new),
+            record_script: Script::new(RECORD_SCRIPT),
+            memory: Mutex::new(HashMap::new()),
+        }
+    }
+
+    fn login_scope(login_id: &str) -> String {
+        format!("login:{}", login_id.trim().to_lowercase())
+    }
+
+    // IPv6按/64网段统计，避免攻击者在同一网段内轮换地址
+    fn ip_scope(kind: &str, ip: IpAddr) -> String {
+        match ip {
+            IpAddr::V4(ip) => format!("{}:{}", kind, ip),
+            IpAddr::V6(ip) => {
+                let segments = ip.segments();
+                format!(
+                    "{}:{:x}:{:x}:{:x}:{:x}::/64",
+                    kind, segments[0], segments[1], segments[2], segments[3]
+                )
+            }
+        }
+    }
+
+    // 第N次锁定的时长：首次锁定时长逐次翻倍，不超过上限（首次锁定时长超过上限时以其为上限）
+    fn lockout_duration(config: &LoginGuardConfig, base_secs: u64, lockouts: u32) -> u64 {
+        let factor = 1u64 << lockouts.saturating_sub(1).min(20);
+        base_secs
+            .saturating_mul(factor)
+            .min(config.max_lockout_secs.max(base_secs))
+    }
+
+    // 已失败N次时处理下一次登录前的延迟
+    fn failure_delay(failures: u32) -> Duration {
+        if failures == 0 {
+            return Duration::ZERO;
+        }
+        BASE_DELAY
+            .saturating_mul(1 << failures.saturating_sub(1).min(16))
+            .min(MAX_DELAY)
+    }
+
+    // =============== 对外接口 ===============
+
+    // 登录前检查：登录ID或IP处于锁定状态时拒绝，否则按已失败次数延迟处理
+    pub async fn check_login(&self, login_id: &str, ip: IpAddr) -> Result<(), AppError> {
+        let login_state = self.state(&Self::login_scope(login_id)).await;
+        let ip_state = self.state(&Self::ip_scope("ip", ip)).await;
+
+        if let Some(seconds) = login_state.locked_for.max(ip_state.locked_for) {
+            warn!("登录已被锁定: login_id={}, ip={}", login_id, ip);
+            return Err(AppError::TooManyRequests(format!(
+                "登录失败次数过多，请在{}秒后重试",
+                seconds
+            )));
+        }
+
+        let delay = Self::failure_delay(login_state.count.max(ip_state.count));
+        if !delay.is_zero() {
+            tokio::time::sleep(delay).await;
+        }
+        Ok(())
+    }
+
+    // 记录一次登录失败，达到阈值时锁定对应的登录ID或IP
+    pub async fn record_login_failure(&self, login_id: &str, ip: IpAddr) {
+        let window = self.config.failure_window_secs;
+        let lockout = self.config.lockout_secs;
+
+        let login_scope = Self::login_scope(login_id);
+        let limit = self.config.max_failures_per_login_id;
+        if let Some(seconds) = self.record(&login_scope, limit, window, lockout).await {
+            warn!("登录ID已锁定{}秒: login_id={}", seconds, login_id);
+        }
+
+        let ip_scope = Self::ip_scope("ip", ip);
+        let limit = self.config.max_failures_per_ip;
+        if let Some(seconds) = self.record(&ip_scope, limit, window, lockout).await {
+            warn!("IP已锁定{}秒: ip={}", seconds, ip);
+        }
+    }
+
+    // 登录成功后清除该登录ID的失败记录（IP的失败记录保留至窗口结束）
+    pub async fn record_login_success(&self, login_id: &str) {
+        self.clear(&Self::login_scope(login_id)).await;
+    }
+
+    // 创建账号前检查并计数，同一IP在窗口内创建过多账号时锁定，首次锁定持续一个完整窗口
+    pub async fn check_account_creation(&self, ip: IpAddr) -> Result<(), AppError> {
+        let scope = Self::ip_scope("signup", ip);
+
+        if let Some(seconds) = self.state(&scope).await.locked_for {
+            warn!("账号创建已被限制: ip={}", ip);
+            return Err(AppError::TooManyRequests(format!(
+                "创建账号过于频繁，请在{}秒后重试",
+                seconds
+            )));
+        }
+
+        let limit = self.config.max_signups_per_ip;
+        let window = self.config.signup_window_secs;
+        if let Some(seconds) = self.record(&scope, limit, window, window).await {
+            warn!("IP创建账号已达上限，限制{}秒: ip={}", seconds, ip);
+        }
+        Ok(())
+    }
+
+    // 清理内存中已过期的记录（Redis中的记录会自动过期）
+    pub fn purge_expired(&self) -> usize {
+        let now = Instant::now();
+        let mut memory = self.memory.lock().unwrap_or_else(|e| e.into_inner());
+        let before = memory.len();
+        memory.retain(|_, record| {
+            record.window_expires_at > now
+                || record.lockouts_expire_at > now
+                || record.locked_until.is_some_and(|until| until > now)
+        });
+        before - memory.len()
+    }
+
+    // =============== 计数存储 ===============
+
+    fn redis_keys(scope: &str) -> (String, String, String) {
+        (
+            format!("{}count:{}", GUARD_KEY_PREFIX, scope),
+            format!("{}lockouts:{}", GUARD_KEY_PREFIX, scope),
+            format!("{}lock:{}", GUARD_KEY_PREFIX, scope),
+        )
+    }
+
+    // 读取当前状态；存储出错时记录日志并放行，避免Redis故障导致无法登录
+    async fn state(&self, scope: &str) -> AttemptState {
+        let Some(redis) = &self.redis else {
+            return self.memory_state(scope);
+        };
+
+        let (count_key, _, lock_key) = Self::redis_keys(scope);
+        let result = redis
+            .run(|mut conn| async move {
+                let count: Option<u32> = conn.get(&count_key).await?;
+                let ttl: i64 = conn.ttl(&lock_key).await?;
+                Ok(AttemptState {
+                    count: count.unwrap_or(0),
+                    locked_for: (ttl > 0).then_some(ttl as u64),
+                })
+            })
+            .await;
+
+        result.unwrap_or_else(|e| {
+            error!("读取登录尝试记录失败: scope={}, error={:?}", scope, e);
+            AttemptState::default()
+        })
+    }
+
+    // 计数加一，达到上限时锁定并清零计数，返回锁定秒数
+    async fn record(
+        &self,
+        scope: &str,
+        limit: u32,
+        window_secs: u64,
+        lockout_secs: u64,
+    ) -> Option<u64> {
+        let Some(redis) = &self.redis else {
+            return self.memory_record(scope, limit, window_secs, lockout_secs);
+        };
+
+        let script = self.record_script.clone();
+        let max_lockout_secs = self.config.max_lockout_secs;
+        let (count_key, lockouts_key, lock_key) = Self::redis_keys(scope);
+        let result = redis
+            .run(|mut conn| async move {
+                let seconds: u64 = script
+                    .key(count_key)
+                    .key(lockouts_key)
+                    .key(lock_key)
+                    .arg(limit)
+                    .arg(window_secs)
+                    .arg(lockout_secs)
+                    .arg(max_lockout_secs)
+                    .arg(LOCKOUT_MEMORY_SECS)
+                    .invoke_async(&mut conn)
+                    .await?;
+                Ok((seconds > 0).then_some(seconds))
+            })
+            .await;
+
+        result.unwrap_or_else(|e| {
+            error!("写入登录尝试记录失败: scope={}, error={:?}", scope, e);
+            None
+        })
+    }
+
+    // 清除失败计数和锁定次数（不解除已生效的锁定）
+    async fn clear(&self, scope: &str) {
+        let Some(redis) = &self.redis else {
+            let mut memory = self.memory.lock().unwrap_or_else(|e| e.into_inner());
+            if let Some(record) = memory.get(scope)
+                && record
+                    .locked_until
+                    .is_none_or(|until| until <= Instant::now())
+            {
+                memory.remove(scope);
+            }
+            return;
+        };
+
+        let (count_key, lockouts_key, _) = Self::redis_keys(scope);
+        if let Err(e) = redis
+            .run(|mut conn| async move { conn.del::<_, ()>(&[count_key, lockouts_key]).await })
+            .await
+        {
+            error!("清除登录尝试记录失败: scope={}, error={:?}", scope, e);
+        }
+    }
+
+    fn memory_state(&self, scope: &str) -> AttemptState {
+        let now = Instant::now();
+        let memory = self.memory.lock().unwrap_or_else(|e| e.into_inner());
+        let Some(record) = memory.get(scope) else {
+            return AttemptState::default();
+        };
+
+        AttemptState {
+            count: if record.window_expires_at > now {
+                record.count
+            } else {
+                0
+            },
+            locked_for: record
+                .locked_until
+                .filter(|until| *until > now)
+                .map(|until| (until - now).as_secs().max(1)),
+        }
+    }
+
+    fn memory_record(
+        &self,
+        scope: &str,
+        limit: u32,
+        window_secs: u64,
+        lockout_secs: u64,
+    ) -> Option<u64> {
+        let now = Instant::now();
+        let mut memory = self.memory.lock().unwrap_or_else(|e| e.into_inner());
+        let record = memory
+            .entry(scope.to_string())
+            .or_insert_with(|| AttemptRecord {
+                count: 0,
+                window_expires_at: now,
+                lockouts: 0,
+                lockouts_expire_at: now,
+                locked_until: None,
+            });
+
+        if record.window_expires_at <= now {
+            record.count = 0;
+            record.window_expires_at = now + Duration::from_secs(window_secs);
+        }
+        if record.lockouts_expire_at <= now {
+            record.lockouts = 0;
+        }
+
+        record.count += 1;
+        if record.count < limit {
+            return None;
+        }
+
+        record.lockouts += 1;
+        record.lockouts_expire_at = now + Duration::from_secs(LOCKOUT_MEMORY_SECS);
+        let seconds = Self::lockout_duration(&self.config, lockout_secs, record.lockouts);
+        record.locked_until = Some(now + Duration::from_secs(seconds));
+        record.count = 0;
+        Some(seconds)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn lockout_duration_doubles_up_to_max() {
+        let config = LoginGuardConfig::default();
+        assert_eq!(LoginGuardService::lockout_duration(&config, 60, 1), 60);
+        assert_eq!(LoginGuardService::lockout_duration(&config, 60, 2), 120);
+        assert_eq!(LoginGuardService::lockout_duration(&config, 60, 6), 1920);
+        assert_eq!(LoginGuardService::lockout_duration(&config, 60, 7), 3600);
+    }
+
+    #[test]
+    fn lockout_duration_saturates_on_large_counts() {
+        let config = LoginGuardConfig::default();
+        assert_eq!(LoginGuardService::lockout_duration(&config, 60, 0), 60);
+        assert_eq!(
+            LoginGuardService::lockout_duration(&config, 60, u32::MAX),
+            3600
+        );
+
+        let config = LoginGuardConfig {
+            max_lockout_secs: u64::MAX,
+            ..LoginGuardConfig::default()
+        };
+        assert_eq!(
+            LoginGuardService::lockout_duration(&config, u64::MAX / 2, u32::MAX),
+            u64::MAX
+        );
+    }
+
+    #[test]
+    fn lockout_duration_keeps_base_above_max() {
+        // 首次锁定时长超过上限时以首次锁定时长为上限，例如账号创建锁定一个完整窗口
+        let config = LoginGuardConfig {
+            max_lockout_secs: 60,
+            ..LoginGuardConfig::default()
+        };
+        assert_eq!(LoginGuardService::lockout_duration(&config, 3600, 1), 3600);
+        assert_eq!(LoginGuardService::lockout_duration(&config, 3600, 3), 3600);
+    }
+
+    #[test]
+    fn failure_delay_doubles_up_to_max() {
+        assert_eq!(LoginGuardService::failure_delay(0), Duration::ZERO);
+        assert_eq!(LoginGuardService::failure_delay(1), BASE_DELAY);
+        assert_eq!(
+            LoginGuardService::failure_delay(2),
+            Duration::from_millis(500)
+        );
+        assert_eq!(LoginGuardService::failure_delay(5), MAX_DELAY);
+        assert_eq!(LoginGuardService::failure_delay(u32::MAX), MAX_DELAY);
+    }
+
+    #[test]
+    fn memory_record_locks_at_limit_and_resets_count() {
+        let guard = LoginGuardService {
+            config: LoginGuardConfig::default(),
+            redis: None,
+            record_script: Script::new(RECORD_SCRIPT),
+            memory: Mutex::new(HashMap::new()),
+        };
+
+        assert_eq!(guard.memory_record("login:a", 3, 60, 30), None);
+        assert_eq!(guard.memory_record("login:a", 3, 60, 30), None);
+        assert_eq!(guard.memory_record("login:a", 3, 60, 30), Some(30));
+        assert_eq!(guard.memory_state("login:a").count, 0);
+        assert!(guard.memory_state("login:a").locked_for.is_some());
+
+        // 记忆期内再次触发锁定时锁定时长翻倍
+        for _ in 0..2 {
+            guard.memory_record("login:a", 3, 60, 30);
+        }
+        assert_eq!(guard.memory_record("login:a", 3, 60, 30), Some(60));
+    }
+}
@@ -176,10 +176,36 @@ impl MessageService {
         Ok(other_user_id)
     }
 
+    // 检查两个用户之间是否存在屏蔽关系（任一方向）
+    pub async fn check_users_blocked(&self, user_1: i64, user_2: i64) -> Result<bool, AppError> {
+        let blocked = sqlx::query_scalar!(
+            r#"
+            SELECT EXISTS(
+                SELECT 1 FROM user_blocks
+                WHERE (blocker_id = $1 AND blocked_id = $2)
+                   OR (blocker_id = $2 AND blocked_id = $1)
+            ) as "blocked!"
+            "#,
+            user_1,
+            user_2
+        )
+        .fetch_one(&self.pool)
+        .await
+        .map_err(|e| {
+            tracing::error!("查询屏蔽关系失败: {:?}", e);
+            AppError::InternalServerError("查询屏蔽关系失败".to_string())
+        })?;
+        Ok(blocked)
+    }
+
     // =============== 操作函数 ===============
 
     // 创建私信会话
     pub async fn create_dm_session(&self, user_1: i64, user_2: i64) -> Result<Uuid, AppError> {
+        if self.check_users_blocked(user_1, user_2).await? {
+            return Err(AppError::Forbidden("无法与该用户私信".to_string()));
+        }
+
         // 确保 user_1 总是较小的用户ID，保证一致性
         let (smaller_id, larger_id) = if user_1 < user_2 {
             (user_1, user_2)
@@ -328,23 +354,24 @@ impl MessageService {
             )
             .await?;
 
-        // 获取消息详情
+        // 获取消息详情，位置按发送者的隐私级别模糊后推送给接收者
         let message = sqlx::query_as!(
             MessageRow,
             r#"
             SELECT 
-                message_id as "message_id!",
-                conversation_id as "conversation_id!",
-                sender_id as "sender_id!",
-                recipient_id as "recipient_id!",
-                content as "content!",
-                message_type as "message_type!",
-                created_at as "created_at!",
-                read_at,
-                latitude as "latitude!: f64",
-                longitude as "longitude!: f64"
-            FROM direct_messages
-            WHERE message_id = $1
+                dm.message_id as "message_id!",
+                dm.conversation_id as "conversation_id!",
+                dm.sender_id as "sender_id!",
+                dm.recipient_id as "recipient_id!",
+                dm.content as "content!",
+                dm.message_type as "message_type!",
+                dm.created_at as "created_at!",
+                dm.read_at,
+                COALESCE(fuzz_coordinate(dm.latitude, u.location_privacy), 0) as "latitude!: f64",
+                COALESCE(fuzz_coordinate(dm.longitude, u.location_privacy), 0) as "longitude!: f64"
+            FROM direct_messages dm
+            JOIN users u ON u.user_id = dm.sender_id
+            WHERE dm.message_id = $1
             "#,
             message_id
         )
@@ -385,21 +412,22 @@ impl MessageService {
             )
             .await?;
 
-        // 获取消息详情
+        // 获取消息详情，位置按发送者的隐私级别模糊
         let message = sqlx::query_as!(
             GroupMessageRow,
             r#"
             SELECT 
-                message_id as "message_id!",
-                group_id as "group_id!",
-                sender_id as "sender_id!",
-                content as "content!",
-                message_type as "message_type!",
-                created_at as "created_at!",
-                latitude as "latitude!: f64",
-                longitude as "longitude!: f64"
-            FROM group_messages
-            WHERE message_id = $1
+                gm.message_id as "message_id!",
+                gm.group_id as "group_id!",
+                gm.sender_id as "sender_id!",
+                gm.content as "content!",
+                gm.message_type as "message_type!",
+                gm.created_at as "created_at!",
+                COALESCE(fuzz_coordinate(gm.latitude, u.location_privacy), 0) as "latitude!: f64",
+                COALESCE(fuzz_coordinate(gm.longitude, u.location_privacy), 0) as "longitude!: f64"
+            FROM group_messages gm
+            JOIN users u ON u.user_id = gm.sender_id
+            WHERE gm.message_id = $1
             "#,
             message_id
         )
@@ -430,6 +458,11 @@ impl MessageService {
         user1_id: i64,
         user2_id: i64,
     ) -> Result<Uuid, AppError> {
+        // 存在屏蔽关系时不能发起或继续私信
+        if self.check_users_blocked(user1_id, user2_id).await? {
+            return Err(AppError::Forbidden("无法与该用户私信".to_string()));
+        }
+
         let (smaller_id, larger_id) = if user1_id < user2_id {
             (user1_id, user2_id)
         } else {
@@ -520,29 +553,36 @@ impl MessageService {
             return Err(AppError::Forbidden("User not in conversation".into()));
         }
 
+        // 对方发送的消息按其隐私级别模糊位置，0表示未提供位置
         let rows = sqlx::query_as!(
             MessageRow,
             r#"
             SELECT 
-                message_id as "message_id!",
-                conversation_id as "conversation_id!",
-                sender_id as "sender_id!",
-                recipient_id as "recipient_id!",
-                content as "content!",
-                message_type as "message_type!",
-                created_at as "created_at!",
-                read_at,
-                latitude as "latitude!: f64",
-                longitude as "longitude!: f64"
-            FROM direct_messages
-            WHERE conversation_id = $1
-            AND ($2::bigint = 0 OR id < $2::bigint)
-            ORDER BY id DESC
+                dm.message_id as "message_id!",
+                dm.conversation_id as "conversation_id!",
+                dm.sender_id as "sender_id!",
+                dm.recipient_id as "recipient_id!",
+                dm.content as "content!",
+                dm.message_type as "message_type!",
+                dm.created_at as "created_at!",
+                dm.read_at,
+                CASE WHEN dm.sender_id = $4 THEN dm.latitude
+                     ELSE COALESCE(fuzz_coordinate(dm.latitude, u.location_privacy), 0)
+                END as "latitude!: f64",
+                CASE WHEN dm.sender_id = $4 THEN dm.longitude
+                     ELSE COALESCE(fuzz_coordinate(dm.longitude, u.location_privacy), 0)
+                END as "longitude!: f64"
+            FROM direct_messages dm
+            JOIN users u ON u.user_id = dm.sender_id
+            WHERE dm.conversation_id = $1
+            AND ($2::bigint = 0 OR dm.id < $2::bigint)
+            ORDER BY dm.id DESC
             LIMIT $3
             "#,
             conversation_id,
             cursor,
-            limit + 1
+            limit + 1,
+            user_id
         )
         .fetch_all(&self.pool)
         .await
@@ -591,32 +631,38 @@ impl MessageService {
     pub async fn get_group_messages(
         &self,
         group_id: Uuid,
-        _user_id: i64,
+        user_id: i64,
         cursor: i64,
         limit: i64,
     ) -> Result<(Vec<GroupMessageRow>, bool, Option<i64>), AppError> {
-        // 使用单一SQL语句，cursor参数始终传入一个值
+        // 使用单一SQL语句，cursor参数始终传入一个值；其他成员的消息按发送者的隐私级别模糊位置
         let messages = sqlx::query_as!(
             GroupMessageRow,
             r#"
             SELECT 
-                message_id as "message_id!",
-                group_id as "group_id!",
-                sender_id as "sender_id!",
-                content as "content!",
-                message_type as "message_type!",
-                created_at as "created_at!",
-                latitude as "latitude!: f64",
-                longitude as "longitude!: f64"
-            FROM group_messages
-            WHERE group_id = $1 
-            AND id < $2
-            ORDER BY created_at DESC
+                gm.message_id as "message_id!",
+                gm.group_id as "group_id!",
+                gm.sender_id as "sender_id!",
+                gm.content as "content!",
+                gm.message_type as "message_type!",
+                gm.created_at as "created_at!",
+                CASE WHEN gm.sender_id = $4 THEN gm.latitude
+                     ELSE COALESCE(fuzz_coordinate(gm.latitude, u.location_privacy), 0)
+                END as "latitude!: f64",
+                CASE WHEN gm.sender_id = $4 THEN gm.longitude
+                     ELSE COALESCE(fuzz_coordinate(gm.longitude, u.location_privacy), 0)
+                END as "longitude!: f64"
+            FROM group_messages gm
+            JOIN users u ON u.user_id = gm.sender_id
+            WHERE gm.group_id = $1 
+            AND gm.id < $2
+            ORDER BY gm.created_at DESC
             LIMIT $3
             "#,
             group_id,
             cursor,
-            limit + 1
+            limit + 1,
+            user_id
         )
         .fetch_all(&self.pool)
         .await
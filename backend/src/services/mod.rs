@@ -1,14 +1,38 @@
+pub mod account_service;
+pub mod admin_service;
+pub mod block_service;
 pub mod checkin_service;
+pub mod cluster_service;
 pub mod config_service;
+pub mod event_service;
+pub mod friend_service;
+pub mod geocode_service;
 pub mod group_service;
+pub mod hotspot_service;
+pub mod location_history_service;
+pub mod login_guard_service;
 pub mod message_service;
+pub mod notifier;
+pub mod rate_limit_service;
+pub mod token_denylist_service;
 pub mod user_service;
 pub mod ws_service;
 
+pub use account_service::AccountService;
+pub use admin_service::AdminService;
+pub use block_service::BlockService;
 pub use checkin_service::CheckinService;
-// pub use event_service::EventService;
+pub use cluster_service::ClusterService;
+pub use event_service::EventService;
+pub use friend_service::FriendService;
 pub use config_service::ConfigService;
+pub use geocode_service::GeocodeService;
 pub use group_service::GroupService;
+pub use hotspot_service::HotspotService;
+pub use location_history_service::LocationHistoryService;
+pub use login_guard_service::LoginGuardService;
 pub use message_service::MessageService;
+pub use rate_limit_service::RateLimitService;
+pub use token_denylist_service::TokenDenylistService;
 pub use user_service::UserService;
 pub use ws_service::WsService;
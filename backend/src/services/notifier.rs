@@ -0,0 +1,91 @@
+// 用户通知发送：验证码等消息通过可替换的通知渠道投递
+use crate::{config::app_config::NotifierConfig, utils::response::AppError};
+use chrono::Utc;
+use futures::future::BoxFuture;
+use serde::Serialize;
+use std::fmt::Debug;
+use std::path::PathBuf;
+use std::sync::Arc;
+use tokio::io::AsyncWriteExt;
+use tracing::info;
+
+// 发送给用户的一条通知
+#[derive(Debug, Clone, Serialize)]
+pub struct Notification {
+    // 接收方，即用户的登录ID（邮箱/手机号）
+    pub recipient: String,
+    pub subject: String,
+    pub body: String,
+}
+
+pub trait Notifier: Send + Sync + Debug {
+    // 发送通知
+    fn send(&self, notification: Notification) -> BoxFuture<'_, Result<(), AppError>>;
+}
+
+// 根据配置创建通知渠道
+pub fn create_notifier(config: &NotifierConfig) -> Arc<dyn Notifier> {
+    match config {
+        NotifierConfig::Log => Arc::new(LogNotifier),
+        NotifierConfig::File { path } => Arc::new(FileNotifier::new(path.clone())),
+    }
+}
+
+// 将通知写入日志，适用于本地开发
+#[derive(Debug)]
+pub struct LogNotifier;
+
+impl Notifier for LogNotifier {
+    fn send(&self, notification: Notification) -> BoxFuture<'_, Result<(), AppError>> {
+        Box::pin(async move {
+            info!(
+                "【通知】recipient={}, subject={}, body={}",
+                notification.recipient, notification.subject, notification.body
+            );
+            Ok(())
+        })
+    }
+}
+
+// 将通知按行追加到JSON文件，适用于本地开发和测试
+#[derive(Debug)]
+pub struct FileNotifier {
+    path: PathBuf,
+}
+
+impl FileNotifier {
+    pub fn new(path: PathBuf) -> Self {
+        Self { path }
+    }
+}
+
+#[derive(Serialize)]
+struct NotificationRecord<'a> {
+    sent_at: chrono::DateTime<Utc>,
+    #[serde(flatten)]
+    notification: &'a Notification,
+}
+
+impl Notifier for FileNotifier {
+    fn send(&self, notification: Notification) -> BoxFuture<'_, Result<(), AppError>> {
+        Box::pin(async move {
+            let record = NotificationRecord {
+                sent_at: Utc::now(),
+                notification: &notification,
+            };
+            let mut line = serde_json::to_string(&record)
+                .map_err(|e| AppError::InternalServerError(format!("序列化通知失败: {:?}", e)))?;
+            line.push('\n');
+
+            let mut file = tokio::fs::OpenOptions::new()
+                .create(true)
+                .append(true)
+                .open(&self.path)
+                .await
+                .map_err(|e| AppError::InternalServerError(format!("打开通知文件失败: {:?}", e)))?;
+            file.write_all(line.as_bytes())
+                .await
+                .map_err(|e| AppError::InternalServerError(format!("写入通知文件失败: {:?}", e)))
+        })
+    }
+}
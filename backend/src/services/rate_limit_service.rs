@@ -0,0 +1,147 @@
+use crate::{
+    config::{db::Database, routes::RateLimitPolicy},
+    utils::{redis::RedisExecutor, response::AppError},
+};
+use redis::Script;
+use std::collections::HashMap;
+use std::sync::Mutex;
+use std::time::Instant;
+use tracing::{error, warn};
+
+// Redis中令牌桶键的前缀
+const RATE_LIMIT_KEY_PREFIX: &str = "geotalk:ratelimit:";
+
+// 令牌桶脚本：按Redis服务器时间补充令牌后尝试取出一个，返回 {是否放行, 需等待的毫秒数}
+const TOKEN_BUCKET_SCRIPT: &str = r#"
+local burst = tonumber(ARGV[1])
+local per_ms = tonumber(ARGV[2]) / 60000
+local time = redis.call('TIME')
+local now = tonumber(time[1]) * 1000 + math.floor(tonumber(time[2]) / 1000)
+local state = redis.call('HMGET', KEYS[1], 'tokens', 'ts')
+local tokens = tonumber(state[1]) or burst
+local ts = tonumber(state[2]) or now
+tokens = math.min(burst, tokens + math.max(0, now - ts) * per_ms)
+local allowed = 0
+local wait_ms = 0
+if tokens >= 1 then
+    tokens = tokens - 1
+    allowed = 1
+else
+    wait_ms = math.ceil((1 - tokens) / per_ms)
+end
+redis.call('HSET', KEYS[1], 'tokens', tostring(tokens), 'ts', now)
+redis.call('PEXPIRE', KEYS[1], math.ceil(burst / per_ms) + 1000)
+return {allowed, wait_ms}
+"#;
+
+// 内存中的令牌桶，未配置Redis时使用
+#[derive(Debug)]
+struct Bucket {
+    tokens: f64,
+    updated_at: Instant,
+    // 令牌补满的时间，之后该桶可以丢弃
+    full_at: Instant,
+}
+
+// 按路由分组限流：每个请求方在每个策略下拥有一个令牌桶。
+// 配置了Redis时令牌桶存入Redis在多节点间共享，否则保存在本节点内存中
+#[derive(Debug)]
+pub struct RateLimitService {
+    redis: Option<RedisExecutor>,
+    script: Script,
+    buckets: Mutex<HashMap<String, Bucket>>,
+}
+
+impl RateLimitService {
+    pub fn new(db: &Database) -> Self {
+        Self {
+            redis: db.redis_client.clone().map(RedisExecutor::new),
+            script: Script::new(TOKEN_BUCKET_SCRIPT),
+            buckets: Mutex::new(HashMap::new()),
+        }
+    }
+
+    // 从请求方的令牌桶中取出一个令牌，桶为空时返回请求过于频繁
+    pub async fn acquire(&self, policy: &RateLimitPolicy, subject: &str) -> Result<(), AppError> {
+        if policy.per_minute == 0 || policy.burst == 0 {
+            return Ok(());
+        }
+
+        let key = format!("{}{}:{}", RATE_LIMIT_KEY_PREFIX, policy.name, subject);
+        let wait_ms = match &self.redis {
+            Some(redis) => {
+                let script = self.script.clone();
+                let (burst, per_minute) = (policy.burst, policy.per_minute);
+                let script_key = key.clone();
+                let result = redis
+                    .run(|mut conn| async move {
+                        let (allowed, wait_ms): (i64, i64) = script
+                            .key(script_key)
+                            .arg(burst)
+                            .arg(per_minute)
+                            .invoke_async(&mut conn)
+                            .await?;
+                        Ok((allowed == 0).then_some(wait_ms.max(0) as u64))
+                    })
+                    .await;
+
+                // 限流存储出错时放行，避免Redis故障导致所有接口不可用
+                result.unwrap_or_else(|e| {
+                    error!("执行限流脚本失败: key={}, error={:?}", key, e);
+                    None
+                })
+            }
+            None => self.acquire_local(&key, policy),
+        };
+
+        match wait_ms {
+            None => Ok(()),
+            Some(wait_ms) => {
+                warn!("请求被限流: key={}, wait_ms={}", key, wait_ms);
+                let seconds = wait_ms.div_ceil(1000).max(1);
+                Err(AppError::TooManyRequests(format!(
+                    "请求过于频繁，请在{}秒后重试",
+                    seconds
+                )))
+            }
+        }
+    }
+
+    // 清理内存中已补满的令牌桶（Redis中的令牌桶会自动过期）
+    pub fn purge_expired(&self) -> usize {
+        let now = Instant::now();
+        let mut buckets = self.buckets.lock().unwrap_or_else(|e| e.into_inner());
+        let before = buckets.len();
+        buckets.retain(|_, bucket| bucket.full_at > now);
+        before - buckets.len()
+    }
+
+    // 内存令牌桶，返回被限流时需等待的毫秒数
+    fn acquire_local(&self, key: &str, policy: &RateLimitPolicy) -> Option<u64> {
+        let now = Instant::now();
+        let burst = f64::from(policy.burst);
+        let per_ms = f64::from(policy.per_minute) / 60_000.0;
+
+        let mut buckets = self.buckets.lock().unwrap_or_else(|e| e.into_inner());
+        let bucket = buckets.entry(key.to_string()).or_insert_with(|| Bucket {
+            tokens: burst,
+            updated_at: now,
+            full_at: now,
+        });
+
+        let elapsed_ms = now.duration_since(bucket.updated_at).as_secs_f64() * 1000.0;
+        bucket.tokens = (bucket.tokens + elapsed_ms * per_ms).min(burst);
+        bucket.updated_at = now;
+
+        let wait_ms = if bucket.tokens >= 1.0 {
+            bucket.tokens -= 1.0;
+            None
+        } else {
+            Some(((1.0 - bucket.tokens) / per_ms).ceil() as u64)
+        };
+
+        let refill_ms = ((burst - bucket.tokens) / per_ms).ceil() as u64;
+        bucket.full_at = now + std::time::Duration::from_millis(refill_ms);
+        wait_ms
+    }
+}
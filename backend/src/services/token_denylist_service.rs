@@ -0,0 +1,136 @@
+use crate::{
+    config::db::Database,
+    utils::{redis::RedisExecutor, response::AppError},
+};
+use chrono::{DateTime, Utc};
+use redis::AsyncCommands;
+use tracing::{debug, error, warn};
+
+// Redis中黑名单键的前缀
+const DENYLIST_KEY_PREFIX: &str = "geotalk:denylist:";
+
+// 访问令牌黑名单：以PostgreSQL为准，配置了Redis时同时写入Redis并自动过期。
+// Redis只作为命中缓存：命中即视为已注销，未命中或出错时查询PostgreSQL，
+// 数据库中已注销的令牌回填到Redis
+#[derive(Debug)]
+pub struct TokenDenylistService {
+    db: Database,
+    redis: Option<RedisExecutor>,
+}
+
+impl TokenDenylistService {
+    pub fn new(db: Database) -> Self {
+        let redis = db.redis_client.clone().map(RedisExecutor::new);
+        Self { db, redis }
+    }
+
+    fn denylist_key(jti: &str) -> String {
+        format!("{}{}", DENYLIST_KEY_PREFIX, jti)
+    }
+
+    // 将令牌加入黑名单，直到令牌过期
+    pub async fn revoke(
+        &self,
+        jti: &str,
+        user_id: i64,
+        expires_at: DateTime<Utc>,
+    ) -> Result<(), AppError> {
+        let ttl_seconds = (expires_at - Utc::now()).num_seconds();
+        if ttl_seconds <= 0 {
+            // 令牌已过期，无需记录
+            return Ok(());
+        }
+
+        sqlx::query!(
+            r#"
+            INSERT INTO revoked_tokens (jti, user_id, expires_at, revoked_at)
+            VALUES ($1, $2, $3, $4)
+            ON CONFLICT (jti) DO NOTHING
+            "#,
+            jti,
+            user_id,
+            expires_at,
+            Utc::now()
+        )
+        .execute(&self.db.pg_pool)
+        .await
+        .map_err(|e| {
+            error!("写入令牌黑名单失败: {:?}", e);
+            AppError::InternalServerError("注销令牌失败".to_string())
+        })?;
+
+        // 数据库写入成功后注销即已生效，Redis写入失败只影响缓存
+        self.cache_revoked(jti, user_id, ttl_seconds as u64).await;
+
+        debug!("令牌已加入黑名单: user_id={}, jti={}", user_id, jti);
+        Ok(())
+    }
+
+    // 检查令牌是否已被注销
+    pub async fn is_revoked(&self, jti: &str) -> Result<bool, AppError> {
+        if let Some(redis) = &self.redis {
+            let key = Self::denylist_key(jti);
+            match redis
+                .run(|mut conn| async move { conn.exists(key).await })
+                .await
+            {
+                Ok(true) => return Ok(true),
+                Ok(false) => {}
+                Err(_) => warn!("Redis不可用，改为从数据库查询令牌黑名单: jti={}", jti),
+            }
+        }
+
+        let revoked = sqlx::query!(
+            r#"
+            SELECT user_id, expires_at
+            FROM revoked_tokens
+            WHERE jti = $1 AND expires_at > NOW()
+            "#,
+            jti
+        )
+        .fetch_optional(&self.db.pg_pool)
+        .await
+        .map_err(|e| {
+            error!("查询令牌黑名单失败: {:?}", e);
+            AppError::InternalServerError("服务器错误".to_string())
+        })?;
+
+        let Some(revoked) = revoked else {
+            return Ok(false);
+        };
+
+        // 此前写入Redis失败的记录回填到Redis
+        let ttl_seconds = (revoked.expires_at - Utc::now()).num_seconds();
+        if ttl_seconds > 0 {
+            self.cache_revoked(jti, revoked.user_id, ttl_seconds as u64)
+                .await;
+        }
+        Ok(true)
+    }
+
+    // 将已注销的令牌写入Redis，失败时只记录日志
+    async fn cache_revoked(&self, jti: &str, user_id: i64, ttl_seconds: u64) {
+        let Some(redis) = &self.redis else {
+            return;
+        };
+        let key = Self::denylist_key(jti);
+        if let Err(e) = redis
+            .run(|mut conn| async move { conn.set_ex::<_, _, ()>(key, user_id, ttl_seconds).await })
+            .await
+        {
+            error!("写入Redis令牌黑名单失败: jti={}, error={:?}", jti, e);
+        }
+    }
+
+    // 清理数据库中已过期的黑名单记录（Redis中的记录会自动过期）
+    pub async fn purge_expired(&self) -> Result<u64, AppError> {
+        let result = sqlx::query!(r#"DELETE FROM revoked_tokens WHERE expires_at <= NOW()"#)
+            .execute(&self.db.pg_pool)
+            .await
+            .map_err(|e| {
+                AppError::InternalServerError(format!("清理过期令牌黑名单失败: {:?}", e))
+            })?;
+
+        Ok(result.rows_affected())
+    }
+}
@@ -1,24 +1,38 @@
-use argon2::password_hash::{SaltString, rand_core::OsRng};
-use argon2::{Argon2, PasswordHasher};
-use chrono::Utc;
+use argon2::password_hash::{PasswordHash, SaltString, rand_core::OsRng};
+use argon2::{Algorithm, Argon2, Params, PasswordHasher, PasswordVerifier, Version};
+use chrono::{DateTime, Duration, NaiveDate, Utc};
 use serde::Serialize;
 use tracing::{debug, error, info, warn};
-use uuid;
+use uuid::Uuid;
 use sqlx::FromRow;
+use std::net::IpAddr;
 use std::sync::Arc;
+use tokio::sync::OnceCell;
 
 use crate::{
     config::{
+        app_config::parse_duration_secs,
         db::Database,
         location::{DEFAULT_LATITUDE, DEFAULT_LOCATION_NAME, DEFAULT_LONGITUDE},
     },
-    services::ConfigService,
+    services::{
+        ConfigService, LoginGuardService,
+        notifier::{Notification, Notifier},
+    },
     utils::{
-        jwt::{generate_jwt_token, verify_jwt_token},
+        geo::Viewport,
+        jwt::{generate_jwt_token, generate_refresh_token, verify_refresh_token},
         response::AppError,
     },
 };
 
+// 密码重置验证码的有效期（分钟）
+const RESET_CODE_TTL_MINUTES: i64 = 15;
+// 每个验证码允许的最大错误尝试次数
+const RESET_CODE_MAX_ATTEMPTS: i32 = 5;
+// 两次发送验证码的最小间隔（秒）
+const RESET_CODE_RESEND_INTERVAL_SECS: i64 = 60;
+
 // =============== 返回结构体 ===============
 
 #[derive(Debug, Clone, Serialize, FromRow)]
@@ -26,11 +40,53 @@ pub struct UserRow {
     pub user_id: i64,
     pub nickname: String,
     pub last_active: Option<chrono::DateTime<chrono::Utc>>,
-    pub latitude: f64,
-    pub longitude: f64,
-    pub distance: f64,
-    pub location_name: String,
+    pub latitude: Option<f64>,
+    pub longitude: Option<f64>,
+    pub distance: Option<f64>,
+    pub location_name: Option<String>,
     pub online_status: String,
+    pub avatar_url: Option<String>,
+    pub bio: Option<String>,
+    pub gender: Option<String>,
+}
+
+// 用户本人的完整资料
+#[derive(Debug, Clone, Serialize)]
+pub struct UserProfileRow {
+    pub user_id: i64,
+    pub login_id: String,
+    pub nickname: String,
+    pub avatar_url: Option<String>,
+    pub bio: Option<String>,
+    pub gender: Option<String>,
+    pub birthday: Option<NaiveDate>,
+    pub is_temp: bool,
+    pub location_privacy: String,
+    // 当前生效的可见范围，已到期的设置视为everyone
+    pub location_visibility: String,
+    pub location_visibility_expires_at: Option<chrono::DateTime<chrono::Utc>>,
+    pub created_at: chrono::DateTime<chrono::Utc>,
+    pub deletion_scheduled_at: Option<chrono::DateTime<chrono::Utc>>,
+}
+
+// 资料更新内容，None表示不修改；字符串字段为空时清除，birthday为Some(None)时清除
+#[derive(Debug, Default)]
+pub struct ProfileUpdate {
+    pub nickname: Option<String>,
+    pub avatar_url: Option<String>,
+    pub bio: Option<String>,
+    pub gender: Option<String>,
+    pub birthday: Option<Option<NaiveDate>>,
+    pub location_privacy: Option<String>,
+}
+
+// 附近用户的搜索条件，friends_only为true时只返回好友
+#[derive(Debug, Clone, Copy)]
+pub struct NearbyUserSearch {
+    pub longitude: f64,
+    pub latitude: f64,
+    pub radius: f64,
+    pub friends_only: bool,
 }
 
 #[derive(Debug, Serialize)]
@@ -46,23 +102,62 @@ pub struct UserLoginResult {
 pub struct AuthUserRow {
     pub user_id: i64,
     pub nickname: String,
+    // 最近一次修改密码或退出所有设备的时间
+    pub tokens_valid_after: Option<DateTime<Utc>>,
+    pub platform_role: String,
+    pub banned_at: Option<DateTime<Utc>>,
+}
+
+impl AuthUserRow {
+    // 令牌是否在最近一次修改密码或退出所有设备之前签发，此类令牌不再有效
+    pub fn is_token_outdated(&self, issued_at: usize) -> bool {
+        self.tokens_valid_after
+            .is_some_and(|changed_at| (issued_at as i64) < changed_at.timestamp())
+    }
 }
 
 #[derive(Debug)]
 pub struct UserLoginRow {
     pub user_id: i64,
     pub nickname: String,
+    pub banned_at: Option<DateTime<Utc>>,
+}
+
+// 密码校验结果
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PasswordCheck {
+    // 密码错误
+    Invalid,
+    // 密码正确
+    Valid,
+    // 密码正确，但存储格式或工作因子已过时，需要重新哈希
+    ValidNeedsRehash,
 }
 
 #[derive(Debug, Clone)]
 pub struct UserService {
     db: Database,
     config_service: Arc<ConfigService>,
+    login_guard: Arc<LoginGuardService>,
+    notifier: Arc<dyn Notifier>,
+    // 登录ID不存在时用于校验的虚拟密码哈希，首次使用时按当前工作因子生成
+    dummy_password_hash: Arc<OnceCell<String>>,
 }
 
 impl UserService {
-    pub fn new(db: Database, config_service: Arc<ConfigService>) -> Self {
-        Self { db, config_service }
+    pub fn new(
+        db: Database,
+        config_service: Arc<ConfigService>,
+        login_guard: Arc<LoginGuardService>,
+        notifier: Arc<dyn Notifier>,
+    ) -> Self {
+        Self {
+            db,
+            config_service,
+            login_guard,
+            notifier,
+            dummy_password_hash: Arc::new(OnceCell::new()),
+        }
     }
 
     // =============== 验证函数 ===============
@@ -85,51 +180,101 @@ impl UserService {
 
     // =============== 操作函数 ===============
 
-    // 哈希密码
+    // 按配置的工作因子创建Argon2id实例
+    fn argon2(&self) -> Result<Argon2<'static>, AppError> {
+        let config = self.config_service.config.password_hash;
+        let params = Params::new(
+            config.memory_kib,
+            config.iterations,
+            config.parallelism,
+            None,
+        )
+        .map_err(|e| {
+            error!("Argon2参数配置无效: {:?}", e);
+            AppError::InternalServerError("密码处理失败".to_string())
+        })?;
+        Ok(Argon2::new(Algorithm::Argon2id, Version::V0x13, params))
+    }
+
+    // 哈希密码，返回Argon2id PHC字符串；哈希计算耗时较长，在阻塞线程中执行
     pub async fn hash_password(&self, password: &str) -> Result<String, AppError> {
-        let salt = SaltString::generate(&mut OsRng);
-        let password_hash = Argon2::default()
-            .hash_password(password.as_bytes(), &salt)
+        let argon2 = self.argon2()?;
+        let password = password.to_string();
+        tokio::task::spawn_blocking(move || {
+            let salt = SaltString::generate(&mut OsRng);
+            argon2
+                .hash_password(password.as_bytes(), &salt)
+                .map(|hash| hash.to_string())
+        })
+        .await
+        .map_err(|e| {
+            error!("密码加密任务失败: {:?}", e);
+            AppError::InternalServerError("密码处理失败".to_string())
+        })?
+        .map_err(|e| {
+            error!("密码加密失败: {:?}", e);
+            AppError::InternalServerError("密码处理失败".to_string())
+        })
+    }
+
+    // 校验密码，兼容旧版直接存储前端哈希值的记录；在阻塞线程中执行
+    pub async fn verify_password(
+        &self,
+        password: &str,
+        stored_hash: &str,
+    ) -> Result<PasswordCheck, AppError> {
+        let argon2 = self.argon2()?;
+        let password = password.to_string();
+        let stored_hash = stored_hash.to_string();
+        tokio::task::spawn_blocking(move || check_password_hash(&argon2, &password, &stored_hash))
+            .await
             .map_err(|e| {
-                error!("密码加密失败: {:?}", e);
+                error!("密码校验任务失败: {:?}", e);
                 AppError::InternalServerError("密码处理失败".to_string())
-            })?
-            .to_string();
+            })
+    }
 
-        Ok(password_hash)
+    // 虚拟密码哈希，使登录ID不存在时的响应时间与密码错误时一致
+    async fn dummy_password_hash(&self) -> Result<&str, AppError> {
+        self.dummy_password_hash
+            .get_or_try_init(|| async { self.hash_password(&Uuid::new_v4().to_string()).await })
+            .await
+            .map(String::as_str)
     }
 
-    // 更新用户状态（事务操作）
-    pub async fn update_user_presence(
-        &self,
-        user_id: i64,
-        latitude: f64,
-        longitude: f64,
-        location_name: &str,
-    ) -> Result<(), AppError> {
-        // 开启事务
-        let mut tx = self.db.pg_pool.begin().await.map_err(|e| {
-            error!("开启事务失败: {:?}", e);
-            AppError::InternalServerError("数据库操作失败".to_string())
-        })?;
+    // 使用当前配置重新哈希并保存用户密码
+    async fn upgrade_password_hash(&self, user_id: i64, password: &str) -> Result<(), AppError> {
+        let password_hash = self.hash_password(password).await?;
 
-        // 更新用户最后活跃时间
         sqlx::query!(
             r#"
-            UPDATE users 
-            SET last_active_at = $1
-            WHERE user_id = $2
+            UPDATE users
+            SET password_hash = $1, updated_at = $2
+            WHERE user_id = $3
             "#,
+            password_hash,
             Utc::now(),
             user_id
         )
-        .execute(&mut *tx)
+        .execute(&self.db.pg_pool)
         .await
         .map_err(|e| {
-            error!("更新用户状态失败: {:?}", e);
-            AppError::InternalServerError("更新用户状态失败".to_string())
+            error!("升级用户密码哈希失败: {:?}", e);
+            AppError::InternalServerError("更新用户密码失败".to_string())
         })?;
 
+        info!("【Service】已升级用户密码哈希: user_id={}", user_id);
+        Ok(())
+    }
+
+    // 仅更新用户位置
+    pub async fn update_user_location(
+        &self,
+        user_id: i64,
+        latitude: f64,
+        longitude: f64,
+        location_name: &str,
+    ) -> Result<(), AppError> {
         // 更新用户位置
         sqlx::query!(
             r#"
@@ -147,111 +292,340 @@ impl UserService {
             location_name,
             Utc::now()
         )
-        .execute(&mut *tx)
+        .execute(&self.db.pg_pool)
         .await
         .map_err(|e| {
             error!("更新用户位置失败: {:?}", e);
             AppError::InternalServerError("更新用户位置失败".to_string())
         })?;
 
-        // 提交事务
+        Ok(())
+    }
+
+    // 校验用户当前密码，用于注销账号等敏感操作的二次确认
+    pub async fn verify_user_password(&self, user_id: i64, password: &str) -> Result<(), AppError> {
+        let stored_hash = sqlx::query_scalar!(
+            r#"SELECT password_hash FROM users WHERE user_id = $1"#,
+            user_id
+        )
+        .fetch_optional(&self.db.pg_pool)
+        .await
+        .map_err(|e| {
+            error!("获取密码哈希失败: {:?}", e);
+            AppError::InternalServerError("获取用户信息失败".to_string())
+        })?
+        .ok_or_else(|| AppError::NotFound("用户不存在".to_string()))?
+        .ok_or_else(|| AppError::BadRequest("该账号未设置密码".to_string()))?;
+
+        match self.verify_password(password, &stored_hash).await? {
+            PasswordCheck::Valid | PasswordCheck::ValidNeedsRehash => Ok(()),
+            PasswordCheck::Invalid => Err(AppError::Unauthorized("密码错误".to_string())),
+        }
+    }
+
+    // =============== 密码修改与重置 ===============
+
+    // 修改密码：校验当前密码，吊销所有设备的令牌后为当前设备签发新令牌
+    pub async fn change_password(
+        &self,
+        user_id: i64,
+        current_password: &str,
+        new_password: &str,
+    ) -> Result<UserLoginResult, AppError> {
+        info!("【Service】修改密码: user_id={}", user_id);
+
+        self.verify_user_password(user_id, current_password).await?;
+        let new_hash = self.hash_password(new_password).await?;
+
+        let mut tx = self.db.pg_pool.begin().await.map_err(|e| {
+            error!("开启事务失败: {:?}", e);
+            AppError::InternalServerError("数据库操作失败".to_string())
+        })?;
+        let nickname = self
+            .set_password(&mut tx, user_id, &new_hash, "password_change")
+            .await?;
         tx.commit().await.map_err(|e| {
             error!("提交事务失败: {:?}", e);
             AppError::InternalServerError("数据库操作失败".to_string())
         })?;
 
-        Ok(())
+        let result = self.issue_tokens(user_id, nickname, false).await?;
+
+        info!("【Service】修改密码成功: user_id={}", user_id);
+
+        Ok(result)
     }
 
-    // 仅更新用户位置
-    pub async fn update_user_location(
-        &self,
-        user_id: i64,
-        latitude: f64,
-        longitude: f64,
-        location_name: &str,
-    ) -> Result<(), AppError> {
-        // 更新用户位置
+    // 申请密码重置验证码。为避免泄露账号是否存在，账号不存在或无法重置时同样返回成功
+    pub async fn request_password_reset(&self, login_id: &str) -> Result<(), AppError> {
+        info!("【Service】申请密码重置: login_id={}", login_id);
+
+        let user = sqlx::query!(
+            r#"
+            SELECT
+                u.user_id,
+                (
+                    SELECT MAX(c.created_at) FROM password_reset_codes c
+                    WHERE c.user_id = u.user_id
+                ) as last_sent_at
+            FROM users u
+            WHERE u.login_id = $1
+              AND u.is_temp = false
+              AND u.password_hash IS NOT NULL
+            "#,
+            login_id
+        )
+        .fetch_optional(&self.db.pg_pool)
+        .await
+        .map_err(|e| {
+            error!("查询重置密码用户失败: {:?}", e);
+            AppError::InternalServerError("申请密码重置失败".to_string())
+        })?;
+
+        let Some(user) = user else {
+            debug!("【Service】忽略无法重置密码的账号: login_id={}", login_id);
+            return Ok(());
+        };
+
+        let now = Utc::now();
+        if user.last_sent_at.is_some_and(|sent_at| {
+            now - sent_at < Duration::seconds(RESET_CODE_RESEND_INTERVAL_SECS)
+        }) {
+            debug!("【Service】验证码发送过于频繁: user_id={}", user.user_id);
+            return Ok(());
+        }
+
+        let code = format!("{:08}", rand::random_range(0..100_000_000u32));
+        let mut tx = self.db.pg_pool.begin().await.map_err(|e| {
+            error!("开启事务失败: {:?}", e);
+            AppError::InternalServerError("数据库操作失败".to_string())
+        })?;
+
+        // 新验证码生成后，之前未使用的验证码全部作废
+        sqlx::query!(
+            r#"DELETE FROM password_reset_codes WHERE user_id = $1 AND used_at IS NULL"#,
+            user.user_id
+        )
+        .execute(&mut *tx)
+        .await
+        .map_err(|e| {
+            error!("清除旧验证码失败: {:?}", e);
+            AppError::InternalServerError("申请密码重置失败".to_string())
+        })?;
+
         sqlx::query!(
             r#"
-            INSERT INTO user_locations (user_id, latitude, longitude, location_name, updated_at)
+            INSERT INTO password_reset_codes (id, user_id, code_hash, created_at, expires_at)
             VALUES ($1, $2, $3, $4, $5)
-            ON CONFLICT (user_id) DO UPDATE
-            SET latitude = EXCLUDED.latitude,
-                longitude = EXCLUDED.longitude,
-                location_name = EXCLUDED.location_name,
-                updated_at = EXCLUDED.updated_at
             "#,
-            user_id,
-            latitude,
-            longitude,
-            location_name,
-            Utc::now()
+            Uuid::new_v4(),
+            user.user_id,
+            hash_reset_code(&code),
+            now,
+            now + Duration::minutes(RESET_CODE_TTL_MINUTES)
         )
-        .execute(&self.db.pg_pool)
+        .execute(&mut *tx)
         .await
         .map_err(|e| {
-            error!("更新用户位置失败: {:?}", e);
-            AppError::InternalServerError("更新用户位置失败".to_string())
+            error!("保存验证码失败: {:?}", e);
+            AppError::InternalServerError("申请密码重置失败".to_string())
+        })?;
+
+        tx.commit().await.map_err(|e| {
+            error!("提交事务失败: {:?}", e);
+            AppError::InternalServerError("数据库操作失败".to_string())
         })?;
 
+        self.notifier
+            .send(Notification {
+                recipient: login_id.to_string(),
+                subject: "GeoTalk密码重置验证码".to_string(),
+                body: format!(
+                    "您的密码重置验证码为{}，{}分钟内有效。如非本人操作，请忽略。",
+                    code, RESET_CODE_TTL_MINUTES
+                ),
+            })
+            .await?;
+
+        info!("【Service】密码重置验证码已发送: user_id={}", user.user_id);
+
         Ok(())
     }
 
-    // 用户登录
-    pub async fn login(
+    // 使用验证码重置密码，成功后吊销所有设备的令牌，返回用户ID
+    pub async fn reset_password(
         &self,
         login_id: &str,
-        password_hash: &str,
-    ) -> Result<UserLoginResult, AppError> {
-        info!("【Service】用户登录: login_id={}", login_id);
+        code: &str,
+        new_password: &str,
+    ) -> Result<i64, AppError> {
+        info!("【Service】重置密码: login_id={}", login_id);
 
-        // 检查用户是否存在
-        let user = sqlx::query_as!(
-            UserLoginRow,
+        let invalid_code = || AppError::BadRequest("验证码无效或已过期".to_string());
+        let new_hash = self.hash_password(new_password).await?;
+
+        let mut tx = self.db.pg_pool.begin().await.map_err(|e| {
+            error!("开启事务失败: {:?}", e);
+            AppError::InternalServerError("数据库操作失败".to_string())
+        })?;
+
+        // 锁定最新的有效验证码，避免并发尝试绕过次数限制
+        let reset_code = sqlx::query!(
             r#"
-            SELECT user_id as "user_id!", nickname as "nickname!"
-            FROM users
-            WHERE login_id = $1
+            SELECT c.id, c.user_id, c.code_hash, c.attempts
+            FROM password_reset_codes c
+            JOIN users u ON u.user_id = c.user_id
+            WHERE u.login_id = $1
+              AND c.used_at IS NULL
+              AND c.expires_at > $2
+            ORDER BY c.created_at DESC
+            LIMIT 1
+            FOR UPDATE OF c
             "#,
-            login_id
+            login_id,
+            Utc::now()
         )
-        .fetch_one(&self.db.pg_pool)
+        .fetch_optional(&mut *tx)
         .await
         .map_err(|e| {
-            error!("用户登录查询失败: {:?}", e);
-            AppError::NotFound("用户不存在或密码错误".to_string())
+            error!("查询验证码失败: {:?}", e);
+            AppError::InternalServerError("重置密码失败".to_string())
+        })?
+        .ok_or_else(invalid_code)?;
+
+        if reset_code.attempts >= RESET_CODE_MAX_ATTEMPTS {
+            warn!("验证码错误次数过多: user_id={}", reset_code.user_id);
+            return Err(invalid_code());
+        }
+
+        if !constant_time_eq(
+            hash_reset_code(code).as_bytes(),
+            reset_code.code_hash.as_bytes(),
+        ) {
+            sqlx::query!(
+                r#"UPDATE password_reset_codes SET attempts = attempts + 1 WHERE id = $1"#,
+                reset_code.id
+            )
+            .execute(&mut *tx)
+            .await
+            .map_err(|e| {
+                error!("更新验证码尝试次数失败: {:?}", e);
+                AppError::InternalServerError("重置密码失败".to_string())
+            })?;
+            tx.commit().await.map_err(|e| {
+                error!("提交事务失败: {:?}", e);
+                AppError::InternalServerError("数据库操作失败".to_string())
+            })?;
+
+            warn!("验证码错误: user_id={}", reset_code.user_id);
+            return Err(invalid_code());
+        }
+
+        sqlx::query!(
+            r#"UPDATE password_reset_codes SET used_at = $1 WHERE id = $2"#,
+            Utc::now(),
+            reset_code.id
+        )
+        .execute(&mut *tx)
+        .await
+        .map_err(|e| {
+            error!("作废验证码失败: {:?}", e);
+            AppError::InternalServerError("重置密码失败".to_string())
         })?;
 
-        // 验证密码
-        let password_hash_from_db = sqlx::query_scalar!(
+        self.set_password(&mut tx, reset_code.user_id, &new_hash, "password_reset")
+            .await?;
+
+        tx.commit().await.map_err(|e| {
+            error!("提交事务失败: {:?}", e);
+            AppError::InternalServerError("数据库操作失败".to_string())
+        })?;
+
+        // 密码已重置，清除之前的登录失败记录
+        self.login_guard.record_login_success(login_id).await;
+
+        info!("【Service】重置密码成功: user_id={}", reset_code.user_id);
+
+        Ok(reset_code.user_id)
+    }
+
+    // 保存新密码并吊销用户所有的刷新令牌家族，返回用户昵称
+    async fn set_password(
+        &self,
+        conn: &mut sqlx::PgConnection,
+        user_id: i64,
+        password_hash: &str,
+        revoked_reason: &str,
+    ) -> Result<String, AppError> {
+        let now = Utc::now();
+        let nickname = sqlx::query_scalar!(
             r#"
-            SELECT password_hash
-            FROM users
-            WHERE login_id = $1
+            UPDATE users
+            SET password_hash = $1, password_changed_at = $2, updated_at = $2
+            WHERE user_id = $3
+            RETURNING nickname as "nickname!"
             "#,
-            login_id
+            password_hash,
+            now,
+            user_id
         )
-        .fetch_one(&self.db.pg_pool)
+        .fetch_optional(&mut *conn)
         .await
         .map_err(|e| {
-            error!("获取密码哈希失败: {:?}", e);
-            AppError::NotFound("用户不存在或密码错误".to_string())
+            error!("更新密码失败: {:?}", e);
+            AppError::InternalServerError("更新密码失败".to_string())
+        })?
+        .ok_or_else(|| AppError::NotFound("用户不存在".to_string()))?;
+
+        sqlx::query!(
+            r#"
+            UPDATE refresh_token_families
+            SET revoked_at = $1, revoked_reason = $2
+            WHERE user_id = $3 AND revoked_at IS NULL
+            "#,
+            now,
+            revoked_reason,
+            user_id
+        )
+        .execute(&mut *conn)
+        .await
+        .map_err(|e| {
+            error!("吊销刷新令牌家族失败: {:?}", e);
+            AppError::InternalServerError("更新密码失败".to_string())
         })?;
 
-        // 确保密码存在
-        let password_hash_from_db = match password_hash_from_db {
-            Some(hash) => hash,
-            None => {
-                error!("用户密码未设置: login_id={}", login_id);
-                return Err(AppError::NotFound("用户不存在或密码错误".to_string()));
+        Ok(nickname)
+    }
+
+    // 用户登录
+    pub async fn login(
+        &self,
+        login_id: &str,
+        password_hash: &str,
+        client_ip: IpAddr,
+    ) -> Result<UserLoginResult, AppError> {
+        info!("【Service】用户登录: login_id={}", login_id);
+
+        // 检查登录ID和IP是否因多次失败被锁定
+        self.login_guard.check_login(login_id, client_ip).await?;
+
+        let user = match self.verify_login_credentials(login_id, password_hash).await {
+            Ok(user) => user,
+            Err(e) => {
+                if matches!(e, AppError::NotFound(_)) {
+                    self.login_guard
+                        .record_login_failure(login_id, client_ip)
+                        .await;
+                }
+                return Err(e);
             }
         };
+        self.login_guard.record_login_success(login_id).await;
 
-        // 验证密码
-        if password_hash != password_hash_from_db {
-            warn!("密码验证失败: login_id={}", login_id);
-            return Err(AppError::NotFound("用户不存在或密码错误".to_string()));
+        // 密码正确后再提示封禁，避免泄露账号状态
+        if user.banned_at.is_some() {
+            warn!("已封禁的用户尝试登录: user_id={}", user.user_id);
+            return Err(AppError::Forbidden("账号已被封禁".to_string()));
         }
 
         // 更新用户最后登录时间
@@ -271,50 +645,74 @@ impl UserService {
             AppError::InternalServerError("更新用户状态失败".to_string())
         })?;
 
-        // 生成JWT令牌
-        let config = self.config_service.get_config();
-        let jwt_secret = &config.jwt_secret;
-        let access_expires_in = config.jwt_expires_in.parse::<i64>().unwrap_or(3600);
-        let refresh_expires_in = std::env::var("JWT_REFRESH_EXPIRES_IN")
-            .unwrap_or_else(|_| "604800".to_string())
-            .parse::<i64>()
-            .unwrap_or(604800);
-
-        // 生成访问令牌
-        let (access_token, expires_at) =
-            generate_jwt_token(user.user_id, jwt_secret, access_expires_in, false).map_err(|e| {
-                error!("生成访问令牌失败: {:?}", e);
-                AppError::InternalServerError("生成访问令牌失败".to_string())
-            })?;
-
-        let (refresh_token, _) = generate_jwt_token(user.user_id, jwt_secret, refresh_expires_in, false)
-            .map_err(|e| {
-                error!("生成刷新令牌失败: {:?}", e);
-                AppError::InternalServerError("生成刷新令牌失败".to_string())
-            })?;
+        // 生成JWT令牌，每次登录开启一个新的刷新令牌家族
+        let result = self
+            .issue_tokens(user.user_id, user.nickname, false)
+            .await?;
 
         info!("【Service】用户登录成功: user_id={}", user.user_id);
 
-        Ok(UserLoginResult {
-            user_id: user.user_id,
-            nickname: user.nickname,
-            access_token,
-            refresh_token,
-            expires_at,
-        })
+        Ok(result)
     }
 
-    pub async fn logout(&self, user_id: i64) -> Result<(), AppError> {
-        // 更新用户状态
-        self.update_user_presence(
-            user_id,
-            DEFAULT_LATITUDE,
-            DEFAULT_LONGITUDE,
-            DEFAULT_LOCATION_NAME,
-        )
-        .await?;
-
-        Ok(())
+    // 校验登录ID和密码，成功时返回用户信息
+    async fn verify_login_credentials(
+        &self,
+        login_id: &str,
+        password_hash: &str,
+    ) -> Result<UserLoginRow, AppError> {
+        let row = sqlx::query!(
+            r#"
+            SELECT user_id as "user_id!", nickname as "nickname!", banned_at, password_hash
+            FROM users
+            WHERE login_id = $1
+            "#,
+            login_id
+        )
+        .fetch_optional(&self.db.pg_pool)
+        .await
+        .map_err(|e| {
+            error!("用户登录查询失败: {:?}", e);
+            AppError::InternalServerError("登录失败".to_string())
+        })?;
+
+        let Some((user, Some(password_hash_from_db))) = row.map(|row| {
+            let user = UserLoginRow {
+                user_id: row.user_id,
+                nickname: row.nickname,
+                banned_at: row.banned_at,
+            };
+            (user, row.password_hash)
+        }) else {
+            // 用户不存在或未设置密码时同样校验一次虚拟哈希，避免通过响应时间判断登录ID是否存在
+            let dummy_hash = self.dummy_password_hash().await?;
+            self.verify_password(password_hash, dummy_hash).await?;
+            warn!("用户不存在或未设置密码: login_id={}", login_id);
+            return Err(AppError::NotFound("用户不存在或密码错误".to_string()));
+        };
+
+        // 验证密码
+        match self
+            .verify_password(password_hash, &password_hash_from_db)
+            .await?
+        {
+            PasswordCheck::Valid => {}
+            PasswordCheck::ValidNeedsRehash => {
+                // 旧格式或旧工作因子的密码在登录成功后透明升级，失败不影响本次登录
+                if let Err(e) = self
+                    .upgrade_password_hash(user.user_id, password_hash)
+                    .await
+                {
+                    warn!("升级密码哈希失败: user_id={}, error={:?}", user.user_id, e);
+                }
+            }
+            PasswordCheck::Invalid => {
+                warn!("密码验证失败: login_id={}", login_id);
+                return Err(AppError::NotFound("用户不存在或密码错误".to_string()));
+            }
+        }
+
+        Ok(user)
     }
 
     // 根据ID搜索用户
@@ -330,23 +728,42 @@ impl UserService {
                 u.user_id,
                 u.nickname as "nickname!",
                 u.last_active_at as last_active,
-                COALESCE(ul.latitude, $3) as "latitude!: f64",
-                COALESCE(ul.longitude, $4) as "longitude!: f64",
-                COALESCE(ul.location_name, $5) as "location_name!: String",
-                COALESCE(
+                fl.latitude,
+                fl.longitude,
+                CASE WHEN p.privacy = 'exact' THEN COALESCE(ul.location_name, $5) END as location_name,
+                CASE WHEN p.privacy <> 'hidden' THEN COALESCE(
                     ST_Distance(
-                        ST_MakePoint(ul.longitude, ul.latitude)::geography,
+                        ST_MakePoint(fuzz_coordinate(ul.longitude, p.privacy), fuzz_coordinate(ul.latitude, p.privacy))::geography,
                         ST_MakePoint(COALESCE(ul2.longitude, $4), COALESCE(ul2.latitude, $3))::geography
                     ),
                     0.0
-                ) as "distance!: f64",
+                ) END as distance,
                 CASE 
-                    WHEN u.last_active_at > NOW() - INTERVAL '5 minutes' THEN 'online'
+                    WHEN v.visible AND u.last_active_at > NOW() - INTERVAL '5 minutes' THEN 'online'
                     ELSE 'offline'
-                END as "online_status!: String"
+                END as "online_status!: String",
+                u.avatar_url,
+                u.bio,
+                u.gender
             FROM users u
             LEFT JOIN user_locations ul ON u.user_id = ul.user_id
             LEFT JOIN user_locations ul2 ON ul2.user_id = $1
+            -- 查看自己时返回精确位置；对方对当前用户隐身时隐藏位置，否则按对方的隐私级别模糊
+            CROSS JOIN LATERAL (
+                SELECT location_visible_to(u.user_id, $1) as visible
+            ) v
+            CROSS JOIN LATERAL (
+                SELECT CASE
+                    WHEN u.user_id = $1 THEN 'exact'
+                    WHEN NOT v.visible THEN 'hidden'
+                    ELSE u.location_privacy
+                END as privacy
+            ) p
+            CROSS JOIN LATERAL (
+                SELECT
+                    fuzz_coordinate(COALESCE(ul.latitude, $3), p.privacy) as latitude,
+                    fuzz_coordinate(COALESCE(ul.longitude, $4), p.privacy) as longitude
+            ) fl
             WHERE u.user_id = $2
             "#,
             current_user_id,
@@ -368,16 +785,20 @@ impl UserService {
     // 查找附近的用户
     pub async fn find_nearby_users(
         &self,
-        longitude: f64,
-        latitude: f64,
-        radius: f64,
+        search: NearbyUserSearch,
         cursor: i64,
         limit: i64,
         current_user_id: i64,
     ) -> Result<(Vec<UserRow>, bool, Option<i64>), AppError> {
+        let NearbyUserSearch {
+            longitude,
+            latitude,
+            radius,
+            friends_only,
+        } = search;
         info!(
-            "【Service】开始查找附近用户: 位置=({}, {}), 半径={}, 游标={}, 限制={}",
-            longitude, latitude, radius, cursor, limit
+            "【Service】开始查找附近用户: 位置=({}, {}), 半径={}, 仅好友={}, 游标={}, 限制={}",
+            longitude, latitude, radius, friends_only, cursor, limit
         );
 
         // 直接使用游标参数，不做额外判断，同时使用类型提示
@@ -389,28 +810,54 @@ impl UserService {
                     u.user_id,
                     u.nickname,
                     u.last_active_at as last_active,
-                    coalesce(ul.latitude, $1) as latitude,
-                    coalesce(ul.longitude, $2) as longitude,
-                    coalesce(ul.location_name, $3) as location_name,
+                    fl.latitude,
+                    fl.longitude,
+                    CASE WHEN p.privacy = 'exact' THEN coalesce(ul.location_name, $3) END as location_name,
                     COALESCE(
                         ST_Distance(
-                            ST_SetSRID(ST_MakePoint(coalesce(ul.longitude, $2), coalesce(ul.latitude, $1)), 4326)::geography,
+                            ST_SetSRID(ST_MakePoint(fl.longitude, fl.latitude), 4326)::geography,
                             ST_SetSRID(ST_MakePoint($2, $1), 4326)::geography
                         ),
                         0
                     ) as distance,
                     CASE 
-                        WHEN u.last_active_at > NOW() - INTERVAL '5 minutes' THEN 'online'
+                        WHEN v.visible AND u.last_active_at > NOW() - INTERVAL '5 minutes' THEN 'online'
                         ELSE 'offline'
-                    END as online_status
+                    END as online_status,
+                    u.avatar_url,
+                    u.bio,
+                    u.gender
                 FROM users u
                 LEFT JOIN user_locations ul ON u.user_id = ul.user_id
+                -- 对方设置了隐身或仅好友和同群成员可见时，按hidden处理
+                CROSS JOIN LATERAL (
+                    SELECT location_visible_to(u.user_id, $4) as visible
+                ) v
+                CROSS JOIN LATERAL (
+                    SELECT CASE WHEN v.visible THEN u.location_privacy ELSE 'hidden' END as privacy
+                ) p
+                -- 距离和半径筛选都基于模糊后的坐标，避免通过调整搜索中心反推精确位置
+                CROSS JOIN LATERAL (
+                    SELECT
+                        fuzz_coordinate(coalesce(ul.latitude, $1), p.privacy) as latitude,
+                        fuzz_coordinate(coalesce(ul.longitude, $2), p.privacy) as longitude
+                ) fl
                 WHERE 
                     u.user_id != $4 AND
                     u.id < $5 AND
+                    p.privacy <> 'hidden' AND
+                    NOT EXISTS (
+                        SELECT 1 FROM user_blocks b
+                        WHERE (b.blocker_id = $4 AND b.blocked_id = u.user_id)
+                           OR (b.blocker_id = u.user_id AND b.blocked_id = $4)
+                    ) AND
+                    (NOT $8 OR EXISTS (
+                        SELECT 1 FROM friendships f
+                        WHERE f.user_id = $4 AND f.friend_id = u.user_id
+                    )) AND
                     COALESCE(
                         ST_Distance(
-                            ST_SetSRID(ST_MakePoint(coalesce(ul.longitude, $2), coalesce(ul.latitude, $1)), 4326)::geography,
+                            ST_SetSRID(ST_MakePoint(fl.longitude, fl.latitude), 4326)::geography,
                             ST_SetSRID(ST_MakePoint($2, $1), 4326)::geography
                         ),
                         0
@@ -422,11 +869,14 @@ impl UserService {
                 user_id as "user_id!: i64",
                 nickname as "nickname!: String",
                 last_active,
-                latitude as "latitude!: f64",
-                longitude as "longitude!: f64",
-                location_name as "location_name!: String",
-                distance as "distance!: f64",
-                online_status as "online_status!: String"
+                latitude,
+                longitude,
+                location_name,
+                distance,
+                online_status as "online_status!: String",
+                avatar_url,
+                bio,
+                gender
             FROM user_distance
             ORDER BY distance ASC
             "#,
@@ -436,7 +886,8 @@ impl UserService {
             current_user_id,
             cursor as i32,
             radius,
-            limit as i32
+            limit as i32,
+            friends_only
         )
         .fetch_all(&self.db.pg_pool)
         .await
@@ -464,6 +915,106 @@ impl UserService {
         Ok((user_rows, has_more, next_cursor_id))
     }
 
+    // 查找地图可视范围内的用户，按user_id倒序分页，距离相对于范围中心
+    pub async fn find_users_in_viewport(
+        &self,
+        viewport: Viewport,
+        cursor: i64,
+        limit: i64,
+        current_user_id: i64,
+    ) -> Result<(Vec<UserRow>, bool, Option<i64>), AppError> {
+        info!(
+            "【Service】开始查找可视范围内用户: 范围={:?}, 游标={}, 限制={}",
+            viewport, cursor, limit
+        );
+        let (center_latitude, center_longitude) = viewport.center();
+
+        // 先用精确坐标外扩0.1度（最大模糊偏移）走空间索引粗筛，再按模糊后的坐标精确筛选
+        let mut user_rows = sqlx::query_as!(
+            UserRow,
+            r#"
+            SELECT
+                u.user_id as "user_id!: i64",
+                u.nickname as "nickname!: String",
+                u.last_active_at as last_active,
+                fl.latitude,
+                fl.longitude,
+                CASE WHEN p.privacy = 'exact' THEN coalesce(ul.location_name, $7) END as location_name,
+                ST_Distance(
+                    ST_SetSRID(ST_MakePoint(fl.longitude, fl.latitude), 4326)::geography,
+                    ST_SetSRID(ST_MakePoint($9, $8), 4326)::geography
+                ) as distance,
+                CASE
+                    WHEN v.visible AND u.last_active_at > NOW() - INTERVAL '5 minutes' THEN 'online'
+                    ELSE 'offline'
+                END as "online_status!: String",
+                u.avatar_url,
+                u.bio,
+                u.gender
+            FROM users u
+            JOIN user_locations ul ON u.user_id = ul.user_id
+            CROSS JOIN LATERAL (
+                SELECT location_visible_to(u.user_id, $5) as visible
+            ) v
+            CROSS JOIN LATERAL (
+                SELECT CASE WHEN v.visible THEN u.location_privacy ELSE 'hidden' END as privacy
+            ) p
+            CROSS JOIN LATERAL (
+                SELECT
+                    fuzz_coordinate(ul.latitude, p.privacy) as latitude,
+                    fuzz_coordinate(ul.longitude, p.privacy) as longitude
+            ) fl
+            WHERE
+                u.user_id != $5 AND
+                ($6::bigint = 0 OR u.user_id < $6) AND
+                ST_Intersects(
+                    ul.geom::geometry,
+                    ST_MakeEnvelope($2::float8 - 0.1, $1::float8 - 0.1, $4::float8 + 0.1, $3::float8 + 0.1, 4326)
+                ) AND
+                p.privacy <> 'hidden' AND
+                fl.latitude BETWEEN $1 AND $3 AND
+                fl.longitude BETWEEN $2 AND $4 AND
+                NOT EXISTS (
+                    SELECT 1 FROM user_blocks b
+                    WHERE (b.blocker_id = $5 AND b.blocked_id = u.user_id)
+                       OR (b.blocker_id = u.user_id AND b.blocked_id = $5)
+                )
+            ORDER BY u.user_id DESC
+            LIMIT $10
+            "#,
+            viewport.south,
+            viewport.west,
+            viewport.north,
+            viewport.east,
+            current_user_id,
+            cursor,
+            DEFAULT_LOCATION_NAME,
+            center_latitude,
+            center_longitude,
+            limit + 1
+        )
+        .fetch_all(&self.db.pg_pool)
+        .await
+        .map_err(|e| {
+            error!("查找可视范围内用户失败: {:?}", e);
+            AppError::InternalServerError("查找可视范围内用户失败".to_string())
+        })?;
+
+        let has_more = user_rows.len() as i64 > limit;
+        user_rows.truncate(limit as usize);
+        let next_cursor = if has_more {
+            user_rows.last().map(|user| user.user_id)
+        } else {
+            None
+        };
+        info!(
+            "【Service】查找可视范围内用户成功: 返回{}个用户，是否有更多={}",
+            user_rows.len(),
+            has_more
+        );
+        Ok((user_rows, has_more, next_cursor))
+    }
+
     // 根据名称搜索用户
     pub async fn find_users_by_name(
         &self,
@@ -490,25 +1041,45 @@ impl UserService {
                     u.user_id,
                     u.nickname,
                     u.last_active_at as last_active,
-                    coalesce(ul.latitude, $1) as latitude,
-                    coalesce(ul.longitude, $2) as longitude,
-                    coalesce(ul.location_name, $3) as location_name,
-                    COALESCE(
+                    fl.latitude,
+                    fl.longitude,
+                    CASE WHEN p.privacy = 'exact' THEN coalesce(ul.location_name, $3) END as location_name,
+                    CASE WHEN p.privacy <> 'hidden' THEN COALESCE(
                         ST_Distance(
-                            ST_SetSRID(ST_MakePoint(coalesce(ul.longitude, $2), coalesce(ul.latitude, $1)), 4326)::geography,
+                            ST_SetSRID(ST_MakePoint(fl.longitude, fl.latitude), 4326)::geography,
                             ST_SetSRID(ST_MakePoint($2, $1), 4326)::geography
                         ),
                         0
-                    ) as distance,
+                    ) END as distance,
                     CASE 
-                        WHEN u.last_active_at > NOW() - INTERVAL '5 minutes' THEN 'online'
+                        WHEN v.visible AND u.last_active_at > NOW() - INTERVAL '5 minutes' THEN 'online'
                         ELSE 'offline'
-                    END as online_status
+                    END as online_status,
+                    u.avatar_url,
+                    u.bio,
+                    u.gender
                 FROM users u
                 LEFT JOIN user_locations ul ON u.user_id = ul.user_id
+                -- 对方设置了隐身或仅好友和同群成员可见时，按hidden处理
+                CROSS JOIN LATERAL (
+                    SELECT location_visible_to(u.user_id, $4) as visible
+                ) v
+                CROSS JOIN LATERAL (
+                    SELECT CASE WHEN v.visible THEN u.location_privacy ELSE 'hidden' END as privacy
+                ) p
+                CROSS JOIN LATERAL (
+                    SELECT
+                        fuzz_coordinate(coalesce(ul.latitude, $1), p.privacy) as latitude,
+                        fuzz_coordinate(coalesce(ul.longitude, $2), p.privacy) as longitude
+                ) fl
                 WHERE 
                     u.user_id != $4 AND
                     u.id < $5 AND
+                    NOT EXISTS (
+                        SELECT 1 FROM user_blocks b
+                        WHERE (b.blocker_id = $4 AND b.blocked_id = u.user_id)
+                           OR (b.blocker_id = u.user_id AND b.blocked_id = $4)
+                    ) AND
                     lower(u.nickname) LIKE lower($6)
                 ORDER BY u.id DESC
                 LIMIT $7 + 1
@@ -517,11 +1088,14 @@ impl UserService {
                 user_id as "user_id!: i64",
                 nickname as "nickname!: String",
                 last_active,
-                latitude as "latitude!: f64",
-                longitude as "longitude!: f64",
-                location_name as "location_name!: String",
-                distance as "distance!: f64",
-                online_status as "online_status!: String"
+                latitude,
+                longitude,
+                location_name,
+                distance,
+                online_status as "online_status!: String",
+                avatar_url,
+                bio,
+                gender
             FROM user_search
             ORDER BY distance ASC
             "#,
@@ -566,7 +1140,10 @@ impl UserService {
             r#"
             SELECT 
                 user_id as "user_id!",
-                nickname as "nickname!"
+                nickname as "nickname!",
+                GREATEST(password_changed_at, tokens_revoked_at) as tokens_valid_after,
+                platform_role,
+                banned_at
             FROM users
             WHERE user_id = $1
             "#,
@@ -583,18 +1160,160 @@ impl UserService {
         Ok(user)
     }
 
+    // 获取用户本人的完整资料
+    pub async fn get_profile(&self, user_id: i64) -> Result<UserProfileRow, AppError> {
+        sqlx::query_as!(
+            UserProfileRow,
+            r#"
+            SELECT user_id, login_id, nickname, avatar_url, bio, gender, birthday, is_temp,
+                   location_privacy,
+                   CASE WHEN location_visibility_expires_at <= NOW() THEN 'everyone'
+                        ELSE location_visibility END as "location_visibility!",
+                   CASE WHEN location_visibility_expires_at > NOW()
+                        THEN location_visibility_expires_at END as location_visibility_expires_at,
+                   created_at, deletion_scheduled_at
+            FROM users
+            WHERE user_id = $1
+            "#,
+            user_id
+        )
+        .fetch_optional(&self.db.pg_pool)
+        .await
+        .map_err(|e| {
+            error!("获取用户资料失败: {:?}", e);
+            AppError::InternalServerError("获取用户资料失败".to_string())
+        })?
+        .ok_or_else(|| AppError::NotFound("用户不存在".to_string()))
+    }
+
+    // 更新用户资料，返回更新后的完整资料
+    pub async fn update_profile(
+        &self,
+        user_id: i64,
+        update: ProfileUpdate,
+    ) -> Result<UserProfileRow, AppError> {
+        info!("【Service】开始更新用户资料: user_id={}", user_id);
+
+        let profile = sqlx::query_as!(
+            UserProfileRow,
+            r#"
+            UPDATE users
+            SET nickname = COALESCE($2, nickname),
+                avatar_url = CASE WHEN $3::text IS NULL THEN avatar_url ELSE NULLIF($3, '') END,
+                bio = CASE WHEN $4::text IS NULL THEN bio ELSE NULLIF($4, '') END,
+                gender = CASE WHEN $5::text IS NULL THEN gender ELSE NULLIF($5, '') END,
+                birthday = CASE WHEN $6 THEN $7 ELSE birthday END,
+                location_privacy = COALESCE($9, location_privacy),
+                updated_at = $8
+            WHERE user_id = $1
+            RETURNING user_id, login_id, nickname, avatar_url, bio, gender, birthday, is_temp,
+                      location_privacy,
+                      CASE WHEN location_visibility_expires_at <= NOW() THEN 'everyone'
+                           ELSE location_visibility END as "location_visibility!",
+                      CASE WHEN location_visibility_expires_at > NOW()
+                           THEN location_visibility_expires_at END as location_visibility_expires_at,
+                      created_at, deletion_scheduled_at
+            "#,
+            user_id,
+            update.nickname,
+            update.avatar_url,
+            update.bio,
+            update.gender,
+            update.birthday.is_some(),
+            update.birthday.flatten(),
+            Utc::now(),
+            update.location_privacy
+        )
+        .fetch_optional(&self.db.pg_pool)
+        .await
+        .map_err(|e| {
+            error!("更新用户资料失败: {:?}", e);
+            AppError::InternalServerError("更新用户资料失败".to_string())
+        })?
+        .ok_or_else(|| AppError::NotFound("用户不存在".to_string()))?;
+
+        info!("【Service】用户资料更新成功: user_id={}", user_id);
+
+        Ok(profile)
+    }
+
+    // 设置位置可见范围，expires_at为空表示一直有效
+    pub async fn update_location_visibility(
+        &self,
+        user_id: i64,
+        visibility: &str,
+        expires_at: Option<chrono::DateTime<chrono::Utc>>,
+    ) -> Result<(), AppError> {
+        info!(
+            "【Service】开始设置位置可见范围: user_id={}, 范围={}, 到期时间={:?}",
+            user_id, visibility, expires_at
+        );
+
+        let result = sqlx::query!(
+            r#"
+            UPDATE users
+            SET location_visibility = $2,
+                location_visibility_expires_at = $3,
+                updated_at = $4
+            WHERE user_id = $1
+            "#,
+            user_id,
+            visibility,
+            expires_at,
+            Utc::now()
+        )
+        .execute(&self.db.pg_pool)
+        .await
+        .map_err(|e| {
+            error!("设置位置可见范围失败: {:?}", e);
+            AppError::InternalServerError("设置位置可见范围失败".to_string())
+        })?;
+
+        if result.rows_affected() == 0 {
+            return Err(AppError::NotFound("用户不存在".to_string()));
+        }
+
+        info!("【Service】位置可见范围设置成功: user_id={}", user_id);
+        Ok(())
+    }
+
+    // 将已到期的位置可见范围恢复为所有人可见，返回受影响的用户ID
+    pub async fn reset_expired_location_visibility(&self) -> Result<Vec<i64>, AppError> {
+        sqlx::query_scalar!(
+            r#"
+            UPDATE users
+            SET location_visibility = 'everyone',
+                location_visibility_expires_at = NULL,
+                updated_at = $1
+            WHERE location_visibility_expires_at <= $1
+            RETURNING user_id as "user_id!"
+            "#,
+            Utc::now()
+        )
+        .fetch_all(&self.db.pg_pool)
+        .await
+        .map_err(|e| {
+            error!("恢复到期的位置可见范围失败: {:?}", e);
+            AppError::InternalServerError("恢复位置可见范围失败".to_string())
+        })
+    }
+
     // 注册并生成令牌
     pub async fn signup_with_token(
         &self,
         login_id: &str,
         password_hash: &str, // 从前端收到的无盐哈希值
         nickname: &str,
+        client_ip: IpAddr,
     ) -> Result<UserLoginResult, AppError> {
         info!(
             "【Service】开始用户注册: login_id={}, nickname={}",
             login_id, nickname
         );
 
+        // 限制同一IP创建账号的频率
+        self.login_guard.check_account_creation(client_ip).await?;
+
         // 检查用户名是否已存在
         if self.is_login_id_taken(login_id).await? {
             warn!("【Service】注册失败: 登录ID已存在 - {}", login_id);
@@ -660,42 +1379,29 @@ impl UserService {
         })?;
 
         // 生成JWT令牌
-        let config = self.config_service.get_config();
-        let jwt_secret = &config.jwt_secret;
-        let access_expires_in = config.jwt_expires_in.parse::<i64>().unwrap_or(3600);
-        // 临时用户使用更长的过期时间（30天）
-        let refresh_expires_in = 30 * 24 * 60 * 60; // 30天
-
-        let (access_token, expires_at) =
-            generate_jwt_token(user_id, jwt_secret, access_expires_in, true).map_err(|e| {
-                error!("生成访问令牌失败: {:?}", e);
-                AppError::InternalServerError("生成访问令牌失败".to_string())
-            })?;
-
-        let (refresh_token, _) = generate_jwt_token(user_id, jwt_secret, refresh_expires_in, true)
-            .map_err(|e| {
-                error!("生成刷新令牌失败: {:?}", e);
-                AppError::InternalServerError("生成刷新令牌失败".to_string())
-            })?;
+        let result = self
+            .issue_tokens(user_id, nickname.to_string(), false)
+            .await?;
 
         info!(
             "【Service】用户注册成功: user_id={}, nickname={}",
             user_id, nickname
         );
 
-        Ok(UserLoginResult {
-            user_id,
-            nickname: nickname.to_string(),
-            access_token,
-            refresh_token,
-            expires_at,
-        })
+        Ok(result)
     }
 
     // 创建临时用户
-    pub async fn create_temp_user(&self, nickname: &str) -> Result<UserLoginResult, AppError> {
+    pub async fn create_temp_user(
+        &self,
+        nickname: &str,
+        client_ip: IpAddr,
+    ) -> Result<UserLoginResult, AppError> {
         info!("【Service】开始创建临时用户: nickname={}", nickname);
 
+        // 限制同一IP创建账号的频率
+        self.login_guard.check_account_creation(client_ip).await?;
+
         // 开启事务
         let mut tx = self.db.pg_pool.begin().await.map_err(|e| {
             error!("开启事务失败: {:?}", e);
@@ -703,7 +1409,7 @@ impl UserService {
         })?;
 
         // 生成临时登录ID
-        let temp_login_id = format!("temp_{}", uuid::Uuid::new_v4().to_string().replace("-", ""));
+        let temp_login_id = format!("temp_{}", Uuid::new_v4().to_string().replace("-", ""));
         debug!("【Service】生成临时登录ID: {}", temp_login_id);
 
         // 生成临时用户ID - 从99999999999开始
@@ -712,8 +1418,8 @@ impl UserService {
         // 创建临时用户
         let user_id = sqlx::query_scalar!(
             r#"
-            INSERT INTO users (user_id, login_id, nickname, created_at, updated_at, last_active_at)
-            VALUES ($1, $2, $3, $4, $4, $4)
+            INSERT INTO users (user_id, login_id, nickname, is_temp, created_at, updated_at, last_active_at)
+            VALUES ($1, $2, $3, TRUE, $4, $4, $4)
             RETURNING user_id
             "#,
             temp_user_id,
@@ -755,100 +1461,599 @@ impl UserService {
             AppError::InternalServerError("数据库操作失败".to_string())
         })?;
 
-        // 生成JWT令牌
+        // 为临时用户生成Token
+        let result = self
+            .issue_tokens(user_id, nickname.to_string(), true)
+            .await?;
+
+        info!(
+            "【Service】临时用户创建成功: user_id={}, nickname={}",
+            user_id, nickname
+        );
+
+        Ok(result)
+    }
+
+    // 临时用户认领为正式账号：设置登录ID和密码，保留用户ID及其全部内容
+    pub async fn claim_account(
+        &self,
+        user_id: i64,
+        login_id: &str,
+        password_hash: &str, // 从前端收到的无盐哈希值
+        nickname: Option<&str>,
+    ) -> Result<UserLoginResult, AppError> {
+        info!(
+            "【Service】开始认领临时账号: user_id={}, login_id={}",
+            user_id, login_id
+        );
+
+        let argon2_hash = self.hash_password(password_hash).await?;
+
+        let mut tx = self.db.pg_pool.begin().await.map_err(|e| {
+            error!("开启事务失败: {:?}", e);
+            AppError::InternalServerError("数据库操作失败".to_string())
+        })?;
+
+        // 锁定用户，避免重复认领
+        let is_temp = sqlx::query_scalar!(
+            r#"SELECT is_temp FROM users WHERE user_id = $1 FOR UPDATE"#,
+            user_id
+        )
+        .fetch_optional(&mut *tx)
+        .await
+        .map_err(|e| {
+            error!("获取用户信息失败: {:?}", e);
+            AppError::InternalServerError("获取用户信息失败".to_string())
+        })?
+        .ok_or_else(|| AppError::NotFound("用户不存在".to_string()))?;
+
+        if !is_temp {
+            warn!("【Service】认领失败: 用户不是临时用户 - {}", user_id);
+            return Err(AppError::BadRequest("只有临时用户可以认领账号".to_string()));
+        }
+
+        if self.is_login_id_taken(login_id).await? {
+            warn!("【Service】认领失败: 登录ID已存在 - {}", login_id);
+            return Err(AppError::Conflict("该账号已被注册".to_string()));
+        }
+
+        // 认领前签发的临时令牌全部失效，之后只能使用认领后签发的令牌
+        let now = Utc::now();
+        let nickname = sqlx::query_scalar!(
+            r#"
+            UPDATE users
+            SET login_id = $1,
+                password_hash = $2,
+                nickname = COALESCE($3, nickname),
+                is_temp = FALSE,
+                expires_at = NULL,
+                tokens_revoked_at = $4,
+                updated_at = $4
+            WHERE user_id = $5
+            RETURNING nickname
+            "#,
+            login_id,
+            argon2_hash,
+            nickname,
+            now,
+            user_id
+        )
+        .fetch_one(&mut *tx)
+        .await
+        .map_err(|e| match e {
+            // 并发注册同一登录ID时由唯一约束兜底
+            sqlx::Error::Database(db_err) if db_err.is_unique_violation() => {
+                AppError::Conflict("该账号已被注册".to_string())
+            }
+            e => {
+                error!("认领账号失败: {:?}", e);
+                AppError::InternalServerError("认领账号失败".to_string())
+            }
+        })?;
+
+        sqlx::query!(
+            r#"
+            UPDATE refresh_token_families
+            SET revoked_at = $1, revoked_reason = 'claim'
+            WHERE user_id = $2 AND revoked_at IS NULL
+            "#,
+            now,
+            user_id
+        )
+        .execute(&mut *tx)
+        .await
+        .map_err(|e| {
+            error!("吊销临时用户的刷新令牌失败: {:?}", e);
+            AppError::InternalServerError("认领账号失败".to_string())
+        })?;
+
+        tx.commit().await.map_err(|e| {
+            error!("提交事务失败: {:?}", e);
+            AppError::InternalServerError("数据库操作失败".to_string())
+        })?;
+
+        // 重新签发不带临时标志的令牌
+        let result = self.issue_tokens(user_id, nickname, false).await?;
+
+        info!(
+            "【Service】临时账号认领成功: user_id={}, login_id={}",
+            user_id, login_id
+        );
+
+        Ok(result)
+    }
+
+    // 获取访问令牌和刷新令牌的有效期（秒）
+    fn token_expires_in(&self, is_temp: bool) -> (i64, i64) {
         let config = self.config_service.get_config();
-        let jwt_secret = &config.jwt_secret;
-        let access_expires_in = config.jwt_expires_in.parse::<i64>().unwrap_or(3600);
-        // 临时用户使用更长的过期时间（30天）
-        let refresh_expires_in = 30 * 24 * 60 * 60; // 30天
+        let access_expires_in = config.jwt_expires_in;
+        // 根据用户类型设置刷新令牌的过期时间
+        let refresh_expires_in = if is_temp {
+            30 * 24 * 60 * 60 // 临时用户30天
+        } else {
+            std::env::var("JWT_REFRESH_EXPIRES_IN")
+                .ok()
+                .and_then(|v| parse_duration_secs(&v))
+                .unwrap_or(604800) // 正式用户默认7天
+        };
+        (access_expires_in, refresh_expires_in)
+    }
+
+    // 开启新的刷新令牌家族并签发令牌
+    async fn issue_tokens(
+        &self,
+        user_id: i64,
+        nickname: String,
+        is_temp: bool,
+    ) -> Result<UserLoginResult, AppError> {
+        let mut tx = self.db.pg_pool.begin().await.map_err(|e| {
+            error!("开启事务失败: {:?}", e);
+            AppError::InternalServerError("数据库操作失败".to_string())
+        })?;
+
+        let family_id = Uuid::new_v4();
+        sqlx::query!(
+            r#"
+            INSERT INTO refresh_token_families (family_id, user_id, is_temp, created_at)
+            VALUES ($1, $2, $3, $4)
+            "#,
+            family_id,
+            user_id,
+            is_temp,
+            Utc::now()
+        )
+        .execute(&mut *tx)
+        .await
+        .map_err(|e| {
+            error!("创建刷新令牌家族失败: {:?}", e);
+            AppError::InternalServerError("生成刷新令牌失败".to_string())
+        })?;
+
+        let result = self
+            .issue_token_pair(
+                &mut tx,
+                family_id,
+                Uuid::new_v4(),
+                user_id,
+                nickname,
+                is_temp,
+            )
+            .await?;
+
+        tx.commit().await.map_err(|e| {
+            error!("提交事务失败: {:?}", e);
+            AppError::InternalServerError("数据库操作失败".to_string())
+        })?;
+
+        Ok(result)
+    }
+
+    // 在指定家族中签发一对访问令牌和刷新令牌
+    async fn issue_token_pair(
+        &self,
+        conn: &mut sqlx::PgConnection,
+        family_id: Uuid,
+        token_id: Uuid,
+        user_id: i64,
+        nickname: String,
+        is_temp: bool,
+    ) -> Result<UserLoginResult, AppError> {
+        let config = self.config_service.get_config();
+        let jwt_keys = &config.jwt_keys;
+        let (access_expires_in, refresh_expires_in) = self.token_expires_in(is_temp);
 
-        // 为临时用户生成Token
         let (access_token, expires_at) =
-            generate_jwt_token(user_id, jwt_secret, access_expires_in, true).map_err(|e| {
+            generate_jwt_token(user_id, jwt_keys, access_expires_in, is_temp).map_err(|e| {
                 error!("生成访问令牌失败: {:?}", e);
                 AppError::InternalServerError("生成访问令牌失败".to_string())
             })?;
 
-        let (refresh_token, _) = generate_jwt_token(user_id, jwt_secret, refresh_expires_in, true)
-            .map_err(|e| {
-                error!("生成刷新令牌失败: {:?}", e);
-                AppError::InternalServerError("生成刷新令牌失败".to_string())
-            })?;
+        let (refresh_token, refresh_expires_at) =
+            generate_refresh_token(user_id, jwt_keys, refresh_expires_in, is_temp, token_id)
+                .map_err(|e| {
+                    error!("生成刷新令牌失败: {:?}", e);
+                    AppError::InternalServerError("生成刷新令牌失败".to_string())
+                })?;
 
-        info!(
-            "【Service】临时用户创建成功: user_id={}, nickname={}",
-            user_id, nickname
-        );
+        sqlx::query!(
+            r#"
+            INSERT INTO refresh_tokens (token_id, family_id, user_id, issued_at, expires_at)
+            VALUES ($1, $2, $3, $4, $5)
+            "#,
+            token_id,
+            family_id,
+            user_id,
+            Utc::now(),
+            refresh_expires_at
+        )
+        .execute(&mut *conn)
+        .await
+        .map_err(|e| {
+            error!("保存刷新令牌失败: {:?}", e);
+            AppError::InternalServerError("生成刷新令牌失败".to_string())
+        })?;
 
         Ok(UserLoginResult {
             user_id,
-            nickname: nickname.to_string(),
+            nickname,
             access_token,
             refresh_token,
             expires_at,
         })
     }
 
-    // 判断用户ID是否为临时用户
-    pub fn is_temp_user(&self, user_id: i64) -> bool {
-        // 临时用户ID从99999999999开始
-        user_id >= 99999999999
-    }
-
-    // 刷新令牌
+    // 刷新令牌：旧令牌作废并在同一家族内签发新令牌，重复使用旧令牌会吊销整个家族
     pub async fn refresh_token(&self, refresh_token: &str) -> Result<UserLoginResult, AppError> {
-        // 验证刷新令牌
+        // 验证刷新令牌，访问令牌不能用于刷新
         let config = self.config_service.get_config();
-        let jwt_secret = &config.jwt_secret;
-        let claims = verify_jwt_token(refresh_token, jwt_secret)
+        let claims = verify_refresh_token(refresh_token, &config.jwt_keys)
             .map_err(|_| AppError::Unauthorized("刷新令牌无效或已过期".to_string()))?;
-
-        // 获取用户信息
         let user_id = claims.sub;
-        // 使用claims中的临时用户标志
-        let is_temp = claims.temp;
-        
-        // 从users表中获取用户信息
-        let nickname = sqlx::query_scalar!(
+        let token_id = Uuid::parse_str(&claims.jti)
+            .map_err(|_| AppError::Unauthorized("刷新令牌无效或已过期".to_string()))?;
+
+        let mut tx = self.db.pg_pool.begin().await.map_err(|e| {
+            error!("开启事务失败: {:?}", e);
+            AppError::InternalServerError("数据库操作失败".to_string())
+        })?;
+
+        // 锁定令牌及其家族，避免并发刷新同一令牌
+        let token = sqlx::query!(
             r#"
-            SELECT nickname
-            FROM users
-            WHERE user_id = $1
+            SELECT t.family_id, t.used_at, f.revoked_at, u.is_temp, u.nickname
+            FROM refresh_tokens t
+            JOIN refresh_token_families f ON f.family_id = t.family_id
+            JOIN users u ON u.user_id = t.user_id
+            WHERE t.token_id = $1 AND t.user_id = $2
+            FOR UPDATE OF t, f
             "#,
+            token_id,
             user_id
         )
-        .fetch_optional(&self.db.pg_pool)
+        .fetch_optional(&mut *tx)
         .await
         .map_err(|e| {
-            error!("获取用户信息失败: {:?}", e);
-            AppError::NotFound("用户不存在".to_string())
+            error!("获取刷新令牌失败: {:?}", e);
+            AppError::InternalServerError("刷新令牌失败".to_string())
         })?
-        .ok_or_else(|| AppError::NotFound("用户不存在".to_string()))?;
+        .ok_or_else(|| AppError::Unauthorized("刷新令牌无效或已过期".to_string()))?;
 
-        // 生成新的访问令牌
-        let access_expires_in = config.jwt_expires_in.parse::<i64>().unwrap_or(3600);
-        // 根据用户类型设置刷新令牌的过期时间
-        let refresh_expires_in = if is_temp {
-            30 * 24 * 60 * 60 // 临时用户30天
-        } else {
-            std::env::var("JWT_REFRESH_EXPIRES_IN")
-                .unwrap_or_else(|_| "604800".to_string()) // 正式用户默认7天
-                .parse::<i64>()
-                .unwrap_or(604800)
+        if token.revoked_at.is_some() {
+            return Err(AppError::Unauthorized("刷新令牌已失效".to_string()));
+        }
+
+        // 已轮换过的令牌被再次使用，说明令牌可能已泄露，吊销整个家族
+        if token.used_at.is_some() {
+            sqlx::query!(
+                r#"
+                UPDATE refresh_token_families
+                SET revoked_at = $1, revoked_reason = 'reuse'
+                WHERE family_id = $2 AND revoked_at IS NULL
+                "#,
+                Utc::now(),
+                token.family_id
+            )
+            .execute(&mut *tx)
+            .await
+            .map_err(|e| {
+                error!("吊销刷新令牌家族失败: {:?}", e);
+                AppError::InternalServerError("刷新令牌失败".to_string())
+            })?;
+
+            tx.commit().await.map_err(|e| {
+                error!("提交事务失败: {:?}", e);
+                AppError::InternalServerError("数据库操作失败".to_string())
+            })?;
+
+            warn!(
+                "【Service】检测到刷新令牌重复使用，已吊销令牌家族: user_id={}, family_id={}",
+                user_id, token.family_id
+            );
+            return Err(AppError::Unauthorized("刷新令牌已失效".to_string()));
+        }
+
+        let new_token_id = Uuid::new_v4();
+        sqlx::query!(
+            r#"
+            UPDATE refresh_tokens
+            SET used_at = $1, replaced_by = $2
+            WHERE token_id = $3
+            "#,
+            Utc::now(),
+            new_token_id,
+            token_id
+        )
+        .execute(&mut *tx)
+        .await
+        .map_err(|e| {
+            error!("更新刷新令牌失败: {:?}", e);
+            AppError::InternalServerError("刷新令牌失败".to_string())
+        })?;
+
+        // 以用户当前的临时标志为准，已认领的账号刷新后不再带临时标志
+        let result = self
+            .issue_token_pair(
+                &mut tx,
+                token.family_id,
+                new_token_id,
+                user_id,
+                token.nickname,
+                token.is_temp,
+            )
+            .await?;
+
+        tx.commit().await.map_err(|e| {
+            error!("提交事务失败: {:?}", e);
+            AppError::InternalServerError("数据库操作失败".to_string())
+        })?;
+
+        debug!(
+            "【Service】刷新令牌已轮换: user_id={}, family_id={}",
+            user_id, token.family_id
+        );
+
+        Ok(result)
+    }
+
+    // 吊销刷新令牌所在的家族，无效的刷新令牌直接忽略
+    pub async fn revoke_token_family(
+        &self,
+        user_id: i64,
+        refresh_token: &str,
+    ) -> Result<(), AppError> {
+        let config = self.config_service.get_config();
+        let Some(token_id) = verify_refresh_token(refresh_token, &config.jwt_keys)
+            .ok()
+            .filter(|claims| claims.sub == user_id)
+            .and_then(|claims| Uuid::parse_str(&claims.jti).ok())
+        else {
+            debug!("【Service】忽略无效的刷新令牌: user_id={}", user_id);
+            return Ok(());
         };
 
-        let (new_access_token, expires_at) =
-            generate_jwt_token(user_id, &jwt_secret, access_expires_in, false).map_err(|e| AppError::InternalServerError(format!("生成访问令牌失败: {:?}", e)))?;
+        sqlx::query!(
+            r#"
+            UPDATE refresh_token_families
+            SET revoked_at = $1, revoked_reason = 'logout'
+            WHERE family_id = (
+                SELECT family_id FROM refresh_tokens WHERE token_id = $2 AND user_id = $3
+            )
+            AND revoked_at IS NULL
+            "#,
+            Utc::now(),
+            token_id,
+            user_id
+        )
+        .execute(&self.db.pg_pool)
+        .await
+        .map_err(|e| {
+            error!("吊销刷新令牌家族失败: {:?}", e);
+            AppError::InternalServerError("退出登录失败".to_string())
+        })?;
 
-        let (new_refresh_token, _) =
-            generate_jwt_token(user_id, &jwt_secret, refresh_expires_in, false).map_err(|e| AppError::InternalServerError(format!("生成刷新令牌失败: {:?}", e)))?;
+        Ok(())
+    }
 
-        Ok(UserLoginResult {
+    // 使用户此前签发的所有访问令牌失效，用于无法单独注销的令牌
+    pub async fn revoke_issued_tokens(&self, user_id: i64) -> Result<(), AppError> {
+        sqlx::query!(
+            r#"
+            UPDATE users
+            SET tokens_revoked_at = $1
+            WHERE user_id = $2
+            "#,
+            Utc::now(),
+            user_id
+        )
+        .execute(&self.db.pg_pool)
+        .await
+        .map_err(|e| {
+            error!("更新令牌失效时间失败: {:?}", e);
+            AppError::InternalServerError("退出登录失败".to_string())
+        })?;
+
+        info!(
+            "【Service】已使用户此前签发的访问令牌失效: user_id={}",
+            user_id
+        );
+        Ok(())
+    }
+
+    // 退出所有设备：吊销用户的所有刷新令牌家族，并使此前签发的访问令牌失效，返回吊销的家族数量
+    pub async fn revoke_all_token_families(&self, user_id: i64) -> Result<u64, AppError> {
+        let now = Utc::now();
+        let mut tx = self.db.pg_pool.begin().await.map_err(|e| {
+            error!("开启事务失败: {:?}", e);
+            AppError::InternalServerError("数据库操作失败".to_string())
+        })?;
+
+        sqlx::query!(
+            r#"
+            UPDATE users
+            SET tokens_revoked_at = $1
+            WHERE user_id = $2
+            "#,
+            now,
+            user_id
+        )
+        .execute(&mut *tx)
+        .await
+        .map_err(|e| {
+            error!("更新令牌失效时间失败: {:?}", e);
+            AppError::InternalServerError("退出登录失败".to_string())
+        })?;
+
+        let result = sqlx::query!(
+            r#"
+            UPDATE refresh_token_families
+            SET revoked_at = $1, revoked_reason = 'logout_all'
+            WHERE user_id = $2 AND revoked_at IS NULL
+            "#,
+            now,
+            user_id
+        )
+        .execute(&mut *tx)
+        .await
+        .map_err(|e| {
+            error!("吊销刷新令牌家族失败: {:?}", e);
+            AppError::InternalServerError("退出登录失败".to_string())
+        })?;
+
+        tx.commit().await.map_err(|e| {
+            error!("提交事务失败: {:?}", e);
+            AppError::InternalServerError("数据库操作失败".to_string())
+        })?;
+
+        info!(
+            "【Service】已吊销用户所有刷新令牌: user_id={}, 家族数={}",
             user_id,
-            nickname,
-            access_token: new_access_token,
-            refresh_token: new_refresh_token,
-            expires_at,
-        })
+            result.rows_affected()
+        );
+
+        Ok(result.rows_affected())
+    }
+}
+
+// 密码重置验证码的SHA-256哈希（十六进制），数据库中只保存哈希
+fn hash_reset_code(code: &str) -> String {
+    ring::digest::digest(&ring::digest::SHA256, code.as_bytes())
+        .as_ref()
+        .iter()
+        .map(|b| format!("{:02x}", b))
+        .collect()
+}
+
+// 按存储格式校验密码，并判断存储的哈希是否需要按当前配置升级
+fn check_password_hash(argon2: &Argon2<'_>, password: &str, stored_hash: &str) -> PasswordCheck {
+    let parsed_hash = match PasswordHash::new(stored_hash) {
+        Ok(hash) => hash,
+        Err(_) => {
+            // 旧格式：存储的是前端哈希值本身，使用常量时间比较
+            return if constant_time_eq(password.as_bytes(), stored_hash.as_bytes()) {
+                PasswordCheck::ValidNeedsRehash
+            } else {
+                PasswordCheck::Invalid
+            };
+        }
+    };
+
+    if argon2
+        .verify_password(password.as_bytes(), &parsed_hash)
+        .is_err()
+    {
+        return PasswordCheck::Invalid;
+    }
+
+    // 算法或工作因子与当前配置不一致时需要升级
+    let is_current = parsed_hash.algorithm == Algorithm::Argon2id.ident()
+        && Params::try_from(&parsed_hash).is_ok_and(|params| {
+            params.m_cost() == argon2.params().m_cost()
+                && params.t_cost() == argon2.params().t_cost()
+                && params.p_cost() == argon2.params().p_cost()
+        });
+
+    if is_current {
+        PasswordCheck::Valid
+    } else {
+        PasswordCheck::ValidNeedsRehash
+    }
+}
+
+// 常量时间比较，避免通过响应时间推断密码内容
+fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+    if a.len() != b.len() {
+        return false;
+    }
+    a.iter().zip(b).fold(0u8, |acc, (x, y)| acc | (x ^ y)) == 0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn argon2(memory_kib: u32, iterations: u32) -> Argon2<'static> {
+        let params = Params::new(memory_kib, iterations, 1, None).unwrap();
+        Argon2::new(Algorithm::Argon2id, Version::V0x13, params)
+    }
+
+    fn hash(argon2: &Argon2<'_>, password: &str) -> String {
+        let salt = SaltString::generate(&mut OsRng);
+        argon2
+            .hash_password(password.as_bytes(), &salt)
+            .unwrap()
+            .to_string()
+    }
+
+    #[test]
+    fn current_hash_is_valid() {
+        let argon2 = argon2(64, 1);
+        let stored = hash(&argon2, "secret");
+        assert_eq!(
+            check_password_hash(&argon2, "secret", &stored),
+            PasswordCheck::Valid
+        );
+        assert_eq!(
+            check_password_hash(&argon2, "wrong", &stored),
+            PasswordCheck::Invalid
+        );
+    }
+
+    #[test]
+    fn legacy_plain_hash_needs_rehash() {
+        let argon2 = argon2(64, 1);
+        let legacy = "5e884898da28047151d0e56f8dc6292773603d0d6aabbdd62a11ef721d1542d8";
+        assert_eq!(
+            check_password_hash(&argon2, legacy, legacy),
+            PasswordCheck::ValidNeedsRehash
+        );
+        assert_eq!(
+            check_password_hash(&argon2, "other", legacy),
+            PasswordCheck::Invalid
+        );
+    }
+
+    #[test]
+    fn outdated_work_factor_needs_rehash() {
+        let stored = hash(&argon2(64, 1), "secret");
+        let current = argon2(128, 2);
+        assert_eq!(
+            check_password_hash(&current, "secret", &stored),
+            PasswordCheck::ValidNeedsRehash
+        );
+        assert_eq!(
+            check_password_hash(&current, "wrong", &stored),
+            PasswordCheck::Invalid
+        );
+    }
+
+    #[test]
+    fn other_argon2_variant_needs_rehash() {
+        let params = Params::new(64, 1, 1, None).unwrap();
+        let argon2i = Argon2::new(Algorithm::Argon2i, Version::V0x13, params);
+        let stored = hash(&argon2i, "secret");
+        assert_eq!(
+            check_password_hash(&argon2(64, 1), "secret", &stored),
+            PasswordCheck::ValidNeedsRehash
+        );
     }
 }
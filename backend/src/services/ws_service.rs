@@ -1,54 +1,350 @@
 use crate::{
-    models::websocket::{
-        DirectMessageEvent, GroupMessageEvent, NewCheckinEvent, UserStatusEvent, WebSocketMessage,
-        WebSocketMessageType,
+    config::{db::Database, location::CHECKIN_NOTIFICATION_RADIUS},
+    middleware::auth::AppState,
+    models::{
+        api::user::AuthUser,
+        websocket::{
+            DirectMessageEvent, FriendEvent, GroupDissolvedEvent, GroupMessageEvent,
+            NewCheckinEvent, ReadReceiptEvent, UserProfileEvent, UserStatusEvent, UserTypingEvent,
+            WebSocketCommand, WebSocketCommandType, WebSocketConnectMessage, WebSocketMessage,
+            WebSocketMessageType,
+        },
+    },
+    services::{ConfigService, EventService, TokenDenylistService},
+    utils::{
+        jwt::{TokenClaims, verify_access_token},
+        response::AppError,
+    },
+    ws::{
+        command,
+        event_bus::{BusEnvelope, BusTarget, DeviceSession, EventBus},
+        router::MessageRouter,
+        session::SessionManager,
     },
-    services::ConfigService,
-    utils::{jwt::verify_jwt_token, response::AppError},
-    ws::session::SessionManager,
 };
 use axum::extract::ws::{Message, WebSocket};
 use chrono::Utc;
-use futures::{sink::SinkExt, stream::StreamExt};
+use futures::{
+    sink::SinkExt,
+    stream::{SplitSink, SplitStream, StreamExt},
+};
 use serde_json::json;
 use std::sync::Arc;
-use tokio::sync::broadcast::{self, Receiver, Sender};
-use tracing::{error, info};
+use tokio::sync::broadcast::error::RecvError;
+use tracing::{debug, error, info, warn};
 use uuid::Uuid;
 
 #[derive(Debug, Clone)]
 pub struct WsService {
     pub session_manager: SessionManager,
-    pub tx: Sender<WebSocketMessage>,
+    pub router: MessageRouter,
     pub config_service: Arc<ConfigService>,
+    event_service: Arc<EventService>,
+    token_denylist_service: Arc<TokenDenylistService>,
+    event_bus: Arc<dyn EventBus>,
+    db: Database,
 }
 
-impl WsService {
-    pub fn new(session_manager: SessionManager, config_service: Arc<ConfigService>) -> Self {
-        // 创建一个广播通道，容量100
-        let (tx, _) = broadcast::channel(100);
+// 断线重连时每批补发的事件数
+const REPLAY_BATCH_SIZE: i64 = 100;
+// 断线重连时最多补发的事件数，超过后提示客户端通过REST接口同步
+const REPLAY_MAX_EVENTS: usize = 1000;
 
+impl WsService {
+    pub fn new(
+        session_manager: SessionManager,
+        config_service: Arc<ConfigService>,
+        event_service: Arc<EventService>,
+        token_denylist_service: Arc<TokenDenylistService>,
+        event_bus: Arc<dyn EventBus>,
+        db: Database,
+    ) -> Self {
         Self {
             session_manager,
-            tx,
+            router: MessageRouter::new(),
             config_service,
+            event_service,
+            token_denylist_service,
+            event_bus,
+            db,
+        }
+    }
+
+    // 启动事件分发任务，把事件总线上的事件投递给本节点的连接
+    pub fn spawn_event_dispatcher(&self) {
+        let mut rx = self.event_bus.subscribe();
+        let router = self.router.clone();
+
+        tokio::spawn(async move {
+            loop {
+                match rx.recv().await {
+                    Ok(envelope) => {
+                        let delivered = Self::dispatch(&router, envelope).await;
+                        debug!("总线事件已投递给本节点{}个连接", delivered);
+                    }
+                    Err(RecvError::Lagged(skipped)) => {
+                        warn!("事件分发滞后，丢弃了{}条事件", skipped);
+                    }
+                    Err(RecvError::Closed) => break,
+                }
+            }
+        });
+    }
+
+    async fn dispatch(router: &MessageRouter, envelope: BusEnvelope) -> usize {
+        let BusEnvelope { target, message } = envelope;
+        match target {
+            BusTarget::Broadcast => router.broadcast(message).await,
+            BusTarget::Users { user_ids } => router.send_to_users(&user_ids, message).await,
+            BusTarget::Sequenced { seqs } => {
+                let mut delivered = 0;
+                for (user_id, seq) in seqs {
+                    let mut message = message.clone();
+                    message.seq = Some(seq);
+                    delivered += router.send_to_users(&[user_id], message).await;
+                }
+                delivered
+            }
+            BusTarget::Token { user_id, token_id } => {
+                router.send_to_token(user_id, &token_id, message).await
+            }
+        }
+    }
+
+    // 发布事件到总线，由各节点投递给本地连接
+    async fn publish(&self, target: BusTarget, message: WebSocketMessage) {
+        if let Err(e) = self
+            .event_bus
+            .publish(BusEnvelope { target, message })
+            .await
+        {
+            error!("发布总线事件失败: {:?}", e);
         }
     }
 
     // 广播消息给所有连接的客户端
-    pub fn broadcast_message(&self, message: WebSocketMessage) -> Result<(), String> {
-        match self.tx.send(message.clone()) {
-            Ok(_) => Ok(()),
+    pub async fn broadcast_message(&self, message: WebSocketMessage) {
+        self.publish(BusTarget::Broadcast, message).await;
+    }
+
+    // 发送消息给指定用户
+    async fn send_to_users(&self, user_ids: &[i64], message: WebSocketMessage) {
+        if user_ids.is_empty() {
+            return;
+        }
+        let target = BusTarget::Users {
+            user_ids: user_ids.to_vec(),
+        };
+        self.publish(target, message).await;
+    }
+
+    // 持久化到接收者的事件日志后投递，每个接收者得到各自的序列号
+    async fn send_persistent(&self, user_ids: &[i64], message: WebSocketMessage) {
+        match self.event_service.append_event(user_ids, &message).await {
+            Ok(seqs) => {
+                self.publish(BusTarget::Sequenced { seqs }, message).await;
+            }
             Err(e) => {
-                error!("广播消息失败: {:?}", e);
-                Err(format!("广播消息失败: {:?}", e))
+                // 事件日志写入失败时仍然实时投递，只是无法补发
+                error!("写入用户事件日志失败: {:?}", e);
+                self.send_to_users(user_ids, message).await;
             }
         }
     }
 
-    // 创建新的订阅者
-    pub fn subscribe(&self) -> Receiver<WebSocketMessage> {
-        self.tx.subscribe()
+    // 通知并关闭使用指定访问令牌建立的所有连接（包括其他节点上的连接）
+    pub async fn close_token_sessions(&self, user_id: i64, token_id: &str, reason: &str) {
+        let message = WebSocketMessage {
+            message_type: WebSocketMessageType::SessionRevoked,
+            data: Some(json!({ "reason": reason })),
+            timestamp: Utc::now(),
+            seq: None,
+        };
+        let target = BusTarget::Token {
+            user_id,
+            token_id: token_id.to_string(),
+        };
+        self.publish(target, message).await;
+    }
+
+    // 通知并关闭用户的所有连接（包括其他节点上的连接）
+    pub async fn close_user_sessions(&self, user_id: i64, reason: &str) {
+        let message = WebSocketMessage {
+            message_type: WebSocketMessageType::SessionRevoked,
+            data: Some(json!({ "reason": reason })),
+            timestamp: Utc::now(),
+            seq: None,
+        };
+        self.send_to_users(&[user_id], message).await;
+    }
+
+    // =============== 接收者查询 ===============
+
+    // 获取群组当前成员ID
+    pub async fn get_group_member_ids(&self, group_id: Uuid) -> Result<Vec<i64>, AppError> {
+        sqlx::query_scalar!(
+            r#"SELECT user_id FROM group_members WHERE group_id = $1"#,
+            group_id
+        )
+        .fetch_all(&self.db.pg_pool)
+        .await
+        .map_err(|e| {
+            error!("查询群组成员失败: {:?}", e);
+            AppError::InternalServerError("查询群组成员失败".to_string())
+        })
+    }
+
+    // 获取指定范围内的在线用户ID
+    async fn get_online_user_ids_within(
+        &self,
+        latitude: f64,
+        longitude: f64,
+        radius: f64,
+    ) -> Result<Vec<i64>, AppError> {
+        // 集群内所有节点的在线用户
+        let online_user_ids = self.event_bus.online_user_ids().await?;
+        if online_user_ids.is_empty() {
+            return Ok(Vec::new());
+        }
+
+        sqlx::query_scalar!(
+            r#"
+            SELECT user_id
+            FROM user_locations
+            WHERE user_id = ANY($1)
+            AND ST_DWithin(
+                geom,
+                ST_SetSRID(ST_MakePoint($2, $3), 4326)::geography,
+                $4
+            )
+            "#,
+            &online_user_ids,
+            longitude,
+            latitude,
+            radius
+        )
+        .fetch_all(&self.db.pg_pool)
+        .await
+        .map_err(|e| {
+            error!("查询附近在线用户失败: {:?}", e);
+            AppError::InternalServerError("查询附近用户失败".to_string())
+        })
+    }
+
+    // 获取与用户存在屏蔽关系（任一方向）的用户ID，这些用户不接收该用户的在线状态等通知
+    async fn get_blocked_user_ids(&self, user_id: i64) -> Result<Vec<i64>, AppError> {
+        sqlx::query_scalar!(
+            r#"
+            SELECT blocked_id as "user_id!" FROM user_blocks WHERE blocker_id = $1
+            UNION
+            SELECT blocker_id FROM user_blocks WHERE blocked_id = $1
+            "#,
+            user_id
+        )
+        .fetch_all(&self.db.pg_pool)
+        .await
+        .map_err(|e| {
+            error!("查询屏蔽关系失败: {:?}", e);
+            AppError::InternalServerError("查询屏蔽关系失败".to_string())
+        })
+    }
+
+    // 用户的在线状态是否对所有人可见：可见范围为everyone（或设置已到期）且不存在屏蔽关系
+    async fn is_visible_to_all(&self, user_id: i64) -> Result<bool, AppError> {
+        sqlx::query_scalar!(
+            r#"
+            SELECT (
+                (u.location_visibility = 'everyone' OR COALESCE(u.location_visibility_expires_at <= NOW(), FALSE))
+                AND NOT EXISTS (
+                    SELECT 1 FROM user_blocks b
+                    WHERE b.blocker_id = $1 OR b.blocked_id = $1
+                )
+            ) as "visible!"
+            FROM users u
+            WHERE u.user_id = $1
+            "#,
+            user_id
+        )
+        .fetch_optional(&self.db.pg_pool)
+        .await
+        .map(|visible| visible.unwrap_or(false))
+        .map_err(|e| {
+            error!("查询用户可见范围失败: {:?}", e);
+            AppError::InternalServerError("查询用户可见范围失败".to_string())
+        })
+    }
+
+    // 获取在线用户中能（visible为true）或不能（visible为false）看到该用户在线状态的用户ID，
+    // 与该用户存在屏蔽关系的用户不在结果中
+    async fn get_online_viewer_ids(
+        &self,
+        user_id: i64,
+        visible: bool,
+    ) -> Result<Vec<i64>, AppError> {
+        let online_user_ids = self.event_bus.online_user_ids().await?;
+        if online_user_ids.is_empty() {
+            return Ok(Vec::new());
+        }
+
+        sqlx::query_scalar!(
+            r#"
+            SELECT viewer_id as "viewer_id!"
+            FROM unnest($2::bigint[]) as viewer_id
+            WHERE location_visible_to($1, viewer_id) = $3
+            AND NOT EXISTS (
+                SELECT 1 FROM user_blocks b
+                WHERE (b.blocker_id = $1 AND b.blocked_id = viewer_id)
+                   OR (b.blocker_id = viewer_id AND b.blocked_id = $1)
+            )
+            "#,
+            user_id,
+            &online_user_ids,
+            visible
+        )
+        .fetch_all(&self.db.pg_pool)
+        .await
+        .map_err(|e| {
+            error!("查询在线状态接收者失败: {:?}", e);
+            AppError::InternalServerError("查询在线用户失败".to_string())
+        })
+    }
+
+    // 获取与用户有会话或共同群组的在线用户ID
+    async fn get_online_peer_ids(&self, user_id: i64) -> Result<Vec<i64>, AppError> {
+        let online_user_ids = self.event_bus.online_user_ids().await?;
+        if online_user_ids.is_empty() {
+            return Ok(Vec::new());
+        }
+
+        sqlx::query_scalar!(
+            r#"
+            SELECT peer_id as "peer_id!"
+            FROM (
+                SELECT CASE WHEN user1_id = $1 THEN user2_id ELSE user1_id END as peer_id
+                FROM conversations
+                WHERE user1_id = $1 OR user2_id = $1
+                UNION
+                SELECT gm2.user_id
+                FROM group_members gm1
+                JOIN group_members gm2 ON gm1.group_id = gm2.group_id
+                WHERE gm1.user_id = $1
+            ) peers
+            WHERE peer_id != $1 AND peer_id = ANY($2)
+            AND NOT EXISTS (
+                SELECT 1 FROM user_blocks b
+                WHERE (b.blocker_id = $1 AND b.blocked_id = peer_id)
+                   OR (b.blocker_id = peer_id AND b.blocked_id = $1)
+            )
+            "#,
+            user_id,
+            &online_user_ids
+        )
+        .fetch_all(&self.db.pg_pool)
+        .await
+        .map_err(|e| {
+            error!("查询在线联系人失败: {:?}", e);
+            AppError::InternalServerError("查询在线联系人失败".to_string())
+        })
     }
 
     // =============== 具体消息类型发送方法 ===============
@@ -83,12 +379,12 @@ impl WsService {
             message_type: WebSocketMessageType::DirectMessage,
             data: Some(serde_json::to_value(event).unwrap_or_default()),
             timestamp: Utc::now(),
+            seq: None,
         };
 
-        self.tx.send(ws_message).map_err(|e| {
-            error!("发送私信消息失败: {:?}", e);
-            AppError::InternalServerError("发送消息失败".to_string())
-        })?;
+        // 只投递给发送者和接收者的连接
+        self.send_persistent(&[sender_id, recipient_id], ws_message)
+            .await;
 
         Ok(())
     }
@@ -97,7 +393,7 @@ impl WsService {
     pub async fn send_group_message(
         &self,
         message_id: &str,
-        group_id: &str,
+        group_id: Uuid,
         group_name: &str,
         sender_id: i64,
         sender_nickname: &str,
@@ -121,12 +417,13 @@ impl WsService {
             message_type: WebSocketMessageType::GroupMessage,
             data: Some(serde_json::to_value(event).unwrap_or_default()),
             timestamp: Utc::now(),
+            seq: None,
         };
 
-        self.tx.send(ws_message).map_err(|e| {
-            error!("发送群组消息失败: {:?}", e);
-            AppError::InternalServerError("发送消息失败".to_string())
-        })?;
+        // 只投递给群组当前成员
+        let member_ids = self.get_group_member_ids(group_id).await?;
+        self.send_persistent(&member_ids, ws_message).await;
+        debug!("群组消息已发布给{}个成员", member_ids.len());
 
         Ok(())
     }
@@ -157,12 +454,19 @@ impl WsService {
             message_type: WebSocketMessageType::NewCheckin,
             data: Some(serde_json::to_value(event).unwrap_or_default()),
             timestamp: Utc::now(),
+            seq: None,
         };
 
-        self.tx.send(ws_message).map_err(|e| {
-            error!("发送签到通知失败: {:?}", e);
-            AppError::InternalServerError("发送通知失败".to_string())
-        })?;
+        // 只投递给签到位置附近的在线用户，排除与签到用户存在屏蔽关系的用户
+        let blocked_user_ids = self.get_blocked_user_ids(user_id).await?;
+        let nearby_user_ids: Vec<i64> = self
+            .get_online_user_ids_within(latitude, longitude, CHECKIN_NOTIFICATION_RADIUS)
+            .await?
+            .into_iter()
+            .filter(|id| !blocked_user_ids.contains(id))
+            .collect();
+        debug!("签到通知将发送给{}个附近在线用户", nearby_user_ids.len());
+        self.send_to_users(&nearby_user_ids, ws_message).await;
 
         Ok(())
     }
@@ -175,55 +479,333 @@ impl WsService {
             last_active: Utc::now(),
         };
 
+        let message_type = if status == "offline" {
+            WebSocketMessageType::UserOffline
+        } else {
+            WebSocketMessageType::UserOnline
+        };
+
+        let ws_message = WebSocketMessage {
+            message_type,
+            data: Some(serde_json::to_value(event).unwrap_or_default()),
+            timestamp: Utc::now(),
+            seq: None,
+        };
+
+        // 对所有人可见且没有屏蔽关系时直接广播，否则只投递给能看到该用户的在线用户，
+        // 隐身时只有用户自己的其他设备收到
+        if self.is_visible_to_all(user_id).await? {
+            self.broadcast_message(ws_message).await;
+        } else {
+            let recipient_ids = self.get_online_viewer_ids(user_id, true).await?;
+            self.send_to_users(&recipient_ids, ws_message).await;
+        }
+
+        Ok(())
+    }
+
+    // 可见范围变更后同步在线状态：能看到的在线用户收到上线，不再能看到的收到下线
+    pub async fn refresh_user_status(&self, user_id: i64) -> Result<(), AppError> {
+        let online_user_ids = self.event_bus.online_user_ids().await?;
+        if !online_user_ids.contains(&user_id) {
+            return Ok(());
+        }
+
+        self.send_user_status(user_id, "online").await?;
+
+        let event = UserStatusEvent {
+            user_id: user_id.to_string(),
+            status: "offline".to_string(),
+            last_active: Utc::now(),
+        };
+        let ws_message = WebSocketMessage {
+            message_type: WebSocketMessageType::UserOffline,
+            data: Some(serde_json::to_value(event).unwrap_or_default()),
+            timestamp: Utc::now(),
+            seq: None,
+        };
+        let hidden_ids = self.get_online_viewer_ids(user_id, false).await?;
+        self.send_to_users(&hidden_ids, ws_message).await;
+
+        Ok(())
+    }
+
+    // 发送资料变更给在线联系人和用户自己的其他设备
+    pub async fn send_profile_update(
+        &self,
+        user_id: i64,
+        nickname: &str,
+        avatar_url: Option<&str>,
+    ) -> Result<(), AppError> {
+        let event = UserProfileEvent {
+            user_id,
+            nickname: nickname.to_string(),
+            avatar_url: avatar_url.map(|s| s.to_string()),
+        };
+
+        let ws_message = WebSocketMessage {
+            message_type: WebSocketMessageType::ProfileUpdated,
+            data: Some(serde_json::to_value(event).unwrap_or_default()),
+            timestamp: Utc::now(),
+            seq: None,
+        };
+
+        let mut recipient_ids = self.get_online_peer_ids(user_id).await?;
+        recipient_ids.push(user_id);
+        debug!("资料变更将发送给{}个在线用户", recipient_ids.len());
+        self.send_to_users(&recipient_ids, ws_message).await;
+
+        Ok(())
+    }
+
+    // 发送输入状态给指定用户
+    pub async fn send_user_typing(
+        &self,
+        user_id: i64,
+        target_type: &str,
+        target_id: &str,
+        is_typing: bool,
+        recipient_ids: &[i64],
+    ) -> Result<(), AppError> {
+        let event = UserTypingEvent {
+            user_id,
+            target_id: target_id.to_string(),
+            target_type: target_type.to_string(),
+            is_typing,
+        };
+
         let ws_message = WebSocketMessage {
-            message_type: WebSocketMessageType::UserOnline,
+            message_type: WebSocketMessageType::UserTyping,
             data: Some(serde_json::to_value(event).unwrap_or_default()),
             timestamp: Utc::now(),
+            seq: None,
         };
 
-        self.tx.send(ws_message).map_err(|e| {
-            error!("发送用户状态更新失败: {:?}", e);
-            AppError::InternalServerError("发送状态更新失败".to_string())
-        })?;
+        self.send_to_users(recipient_ids, ws_message).await;
+
+        Ok(())
+    }
+
+    // 发送已读回执给会话另一方
+    pub async fn send_read_receipt(
+        &self,
+        user_id: i64,
+        peer_id: i64,
+        conversation_id: Uuid,
+        last_read_message_id: Option<Uuid>,
+    ) -> Result<(), AppError> {
+        let event = ReadReceiptEvent {
+            user_id,
+            conversation_id: conversation_id.to_string(),
+            last_read_message_id: last_read_message_id
+                .map(|id| id.to_string())
+                .unwrap_or_default(),
+            timestamp: Utc::now(),
+        };
+
+        let ws_message = WebSocketMessage {
+            message_type: WebSocketMessageType::ReadReceipt,
+            data: Some(serde_json::to_value(event).unwrap_or_default()),
+            timestamp: Utc::now(),
+            seq: None,
+        };
+
+        self.send_persistent(&[peer_id], ws_message).await;
+
+        Ok(())
+    }
+
+    // 发送好友申请、成为好友、解除好友等事件，离线用户上线后补发
+    pub async fn send_friend_event(
+        &self,
+        message_type: WebSocketMessageType,
+        recipient_ids: &[i64],
+        event: FriendEvent,
+    ) -> Result<(), AppError> {
+        let ws_message = WebSocketMessage {
+            message_type,
+            data: Some(serde_json::to_value(event).unwrap_or_default()),
+            timestamp: Utc::now(),
+            seq: None,
+        };
+
+        self.send_persistent(recipient_ids, ws_message).await;
+
+        Ok(())
+    }
+
+    // 通知原群组成员群组已被解散
+    pub async fn send_group_dissolved(
+        &self,
+        member_ids: &[i64],
+        group_id: Uuid,
+        group_name: &str,
+    ) -> Result<(), AppError> {
+        let event = GroupDissolvedEvent {
+            group_id: group_id.to_string(),
+            group_name: group_name.to_string(),
+            timestamp: Utc::now(),
+        };
+
+        let ws_message = WebSocketMessage {
+            message_type: WebSocketMessageType::GroupDissolved,
+            data: Some(serde_json::to_value(event).unwrap_or_default()),
+            timestamp: Utc::now(),
+            seq: None,
+        };
+
+        self.send_persistent(member_ids, ws_message).await;
 
         Ok(())
     }
 
     // =============== WebSocket连接处理 ===============
 
+    // 发送一帧WebSocket消息
+    async fn send_frame(
+        sender: &mut SplitSink<WebSocket, Message>,
+        message: &WebSocketMessage,
+    ) -> Result<(), axum::Error> {
+        let message_str = serde_json::to_string(message).unwrap_or_default();
+        sender.send(Message::Text(message_str.into())).await
+    }
+
+    // 补发序列号大于last_seq的事件，返回最后补发的序列号
+    async fn replay_missed_events(
+        &self,
+        sender: &mut SplitSink<WebSocket, Message>,
+        user_id: i64,
+        last_seq: i64,
+    ) -> Result<i64, axum::Error> {
+        let mut since_seq = last_seq;
+        let mut replayed = 0;
+
+        loop {
+            let (events, has_more) = match self
+                .event_service
+                .get_events_since(user_id, since_seq, REPLAY_BATCH_SIZE)
+                .await
+            {
+                Ok(result) => result,
+                Err(e) => {
+                    error!("获取用户 {} 的离线事件失败: {:?}", user_id, e);
+                    break;
+                }
+            };
+
+            for event in events {
+                since_seq = event.seq;
+                if let Some(message) = event.into_message() {
+                    Self::send_frame(sender, &message).await?;
+                    replayed += 1;
+                }
+            }
+
+            if !has_more {
+                break;
+            }
+
+            // 积压过多时停止补发，提示客户端通过REST接口同步剩余事件
+            if replayed >= REPLAY_MAX_EVENTS {
+                let frame = WebSocketMessage {
+                    message_type: WebSocketMessageType::SyncRequired,
+                    data: Some(json!({ "since_seq": since_seq })),
+                    timestamp: Utc::now(),
+                    seq: None,
+                };
+                Self::send_frame(sender, &frame).await?;
+                break;
+            }
+        }
+
+        info!(
+            "已为用户 {} 补发{}条离线事件: last_seq={}, 补发至={}",
+            user_id, replayed, last_seq, since_seq
+        );
+
+        Ok(since_seq)
+    }
+
     // 处理已经分离的WebSocket流
     pub async fn handle_ws_stream(
         &self,
-        mut sender: futures::stream::SplitSink<WebSocket, Message>,
-        mut receiver: futures::stream::SplitStream<WebSocket>,
-        user_id: i64,
-        nickname: String,
+        mut sender: SplitSink<WebSocket, Message>,
+        mut receiver: SplitStream<WebSocket>,
+        state: Arc<AppState>,
+        mut current_user: AuthUser,
+        token_id: String,
+        connect_msg: WebSocketConnectMessage,
     ) {
-        // 添加会话
-        self.session_manager
-            .add_session(user_id, nickname.clone())
-            .await;
-
-        // 订阅广播消息
-        let mut rx = self.tx.subscribe();
+        let user_id = current_user.user_id;
 
-        // 发送认证成功消息
+        // 添加会话，同一用户的每个连接都有独立的会话ID
+        let (session, is_first_session) = self
+            .session_manager
+            .add_session(user_id, current_user.nickname.clone(), connect_msg.device)
+            .await;
+        let session_id = session.session_id;
+
+        // 注册连接，接收路由给该用户的消息
+        let mut rx = self.router.register(user_id, session_id, token_id).await;
+
+        // 发送认证成功消息，附带当前最新的事件序列号
+        let latest_seq = self
+            .event_service
+            .get_latest_seq(user_id)
+            .await
+            .unwrap_or_else(|e| {
+                error!("获取用户 {} 的事件序列号失败: {:?}", user_id, e);
+                0
+            });
         let success_msg = json!({
             "type": "connected",
             "user_id": user_id,
+            "session_id": session_id,
+            "last_seq": latest_seq,
             "timestamp": Utc::now().to_rfc3339()
         })
         .to_string();
 
         if let Err(e) = sender.send(Message::Text(success_msg.into())).await {
             error!("发送认证成功消息失败: {:?}", e);
-            self.session_manager.remove_session(user_id).await;
+            drop(rx);
+            self.router.unregister(user_id, session_id).await;
+            self.session_manager.remove_session(user_id, session_id).await;
             return;
         }
 
-        // 广播用户上线消息
-        if let Err(e) = self.send_user_status(user_id, "online").await {
-            error!("广播用户上线消息失败: {:?}", e);
+        // 仅在用户在整个集群的第一个连接建立时广播上线消息
+        if is_first_session {
+            match self.event_bus.presence_connect(user_id).await {
+                Ok(true) => {
+                    if let Err(e) = self.send_user_status(user_id, "online").await {
+                        error!("广播用户上线消息失败: {:?}", e);
+                    }
+                }
+                Ok(false) => {}
+                Err(e) => error!("更新用户 {} 的在线状态失败: {:?}", user_id, e),
+            }
+        }
+
+        // 登记连接，在线设备列表在整个集群范围内查询
+        if let Err(e) = self
+            .event_bus
+            .put_device(user_id, session.device_session())
+            .await
+        {
+            error!("登记用户 {} 的连接 {} 失败: {:?}", user_id, session_id, e);
+        }
+
+        // 补发客户端离线期间错过的事件
+        let mut replayed_seq = 0;
+        if let Some(last_seq) = connect_msg.last_seq {
+            match self
+                .replay_missed_events(&mut sender, user_id, last_seq)
+                .await
+            {
+                Ok(seq) => replayed_seq = seq,
+                Err(e) => error!("补发离线事件失败: {:?}", e),
+            }
         }
 
         // 处理消息接收和发送
@@ -233,30 +815,62 @@ impl WsService {
                 Some(result) = receiver.next() => {
                     match result {
                         Ok(Message::Text(text)) => {
-                            if let Ok(ws_message) = serde_json::from_str::<WebSocketMessage>(&text) {
-                                match ws_message.message_type {
-                                    WebSocketMessageType::Ping => {
-                                        let pong_msg = json!({
-                                            "type": "pong",
-                                            "timestamp": Utc::now().to_rfc3339()
-                                        }).to_string();
-                                        if let Err(e) = sender.send(Message::Text(pong_msg.into())).await {
-                                            error!("发送pong消息失败: {:?}", e);
-                                            break;
-                                        }
+                            let command = match serde_json::from_str::<WebSocketCommand>(&text) {
+                                Ok(command) => command,
+                                Err(e) => {
+                                    debug!("用户 {} 发送了无效的命令: {:?}", user_id, e);
+                                    let frame = command::error_frame(
+                                        None,
+                                        AppError::BadRequest("无效的命令格式".to_string()),
+                                    );
+                                    if let Err(e) = Self::send_frame(&mut sender, &frame).await {
+                                        error!("发送错误帧失败: {:?}", e);
+                                        break;
                                     }
-                                    WebSocketMessageType::Disconnect => {
-                                        info!("用户 {} 请求断开连接", user_id);
+                                    continue;
+                                }
+                            };
+
+                            match command.command {
+                                WebSocketCommandType::Ping => {
+                                    let pong_msg = json!({
+                                        "type": "pong",
+                                        "request_id": command.request_id,
+                                        "timestamp": Utc::now().to_rfc3339()
+                                    }).to_string();
+                                    if let Err(e) = sender.send(Message::Text(pong_msg.into())).await {
+                                        error!("发送pong消息失败: {:?}", e);
                                         break;
                                     }
-                                    _ => {
-                                        // 更新会话活动时间
-                                        self.session_manager.update_session_activity(user_id).await;
-
-                                        // 广播消息给所有订阅者
-                                        if let Err(e) = self.broadcast_message(ws_message) {
-                                            error!("广播消息失败: {:?}", e);
-                                        }
+                                }
+                                WebSocketCommandType::Disconnect => {
+                                    info!("用户 {} 请求断开连接", user_id);
+                                    break;
+                                }
+                                other => {
+                                    // 更新会话活动时间，定期同步到事件总线
+                                    if let Some(device) = self.session_manager.update_session_activity(user_id, session_id).await
+                                        && let Err(e) = self.event_bus.put_device(user_id, device).await
+                                    {
+                                        error!("更新用户 {} 的连接 {} 失败: {:?}", user_id, session_id, e);
+                                    }
+
+                                    // 执行命令并回复ack或error帧
+                                    let frame = match command::handle_command(
+                                        &state,
+                                        &current_user,
+                                        session_id,
+                                        &command.request_id,
+                                        other,
+                                    )
+                                    .await
+                                    {
+                                        Ok(result) => command::ack_frame(command.request_id, result),
+                                        Err(e) => command::error_frame(Some(command.request_id), e),
+                                    };
+                                    if let Err(e) = Self::send_frame(&mut sender, &frame).await {
+                                        error!("发送命令响应失败: {:?}", e);
+                                        break;
                                     }
                                 }
                             }
@@ -278,11 +892,46 @@ impl WsService {
                         _ => {}
                     }
                 }
-                // 接收广播消息
-                Ok(message) = rx.recv() => {
-                    let message_str = serde_json::to_string(&message).unwrap_or_default();
-                    if let Err(e) = sender.send(Message::Text(message_str.into())).await {
-                        error!("发送广播消息失败: {:?}", e);
+                // 接收路由给该用户的消息
+                Some(message) = rx.recv() => {
+                    // 缓冲区满时丢弃了未补发过的事件，通知客户端从丢弃处重新同步
+                    if let Some(dropped_seq) = rx.take_dropped_seq()
+                        && dropped_seq > replayed_seq
+                    {
+                        let frame = WebSocketMessage {
+                            message_type: WebSocketMessageType::SyncRequired,
+                            data: Some(json!({ "since_seq": dropped_seq - 1 })),
+                            timestamp: Utc::now(),
+                            seq: None,
+                        };
+                        if let Err(e) = Self::send_frame(&mut sender, &frame).await {
+                            error!("发送同步提示失败: {:?}", e);
+                            break;
+                        }
+                    }
+                    // 跳过补发阶段已经发送过的事件
+                    if message.seq.is_some_and(|seq| seq <= replayed_seq) {
+                        continue;
+                    }
+                    if let Err(e) = Self::send_frame(&mut sender, &message).await {
+                        error!("发送推送消息失败: {:?}", e);
+                        break;
+                    }
+                    // 本人资料变更后更新连接缓存的昵称
+                    if matches!(message.message_type, WebSocketMessageType::ProfileUpdated)
+                        && let Some(event) = message
+                            .data
+                            .clone()
+                            .and_then(|data| serde_json::from_value::<UserProfileEvent>(data).ok())
+                        && event.user_id == user_id
+                    {
+                        self.session_manager.set_nickname(user_id, &event.nickname).await;
+                        current_user.nickname = event.nickname;
+                    }
+                    // 令牌已注销，通知客户端后关闭连接
+                    if matches!(message.message_type, WebSocketMessageType::SessionRevoked) {
+                        info!("用户 {} 的连接 {} 因令牌注销被关闭", user_id, session_id);
+                        let _ = sender.send(Message::Close(None)).await;
                         break;
                     }
                 }
@@ -290,29 +939,64 @@ impl WsService {
             }
         }
 
-        // 用户断开连接，移除会话
-        self.session_manager.remove_session(user_id).await;
-
-        // 广播用户离线消息
-        if let Err(e) = self.send_user_status(user_id, "offline").await {
-            error!("广播用户离线消息失败: {:?}", e);
+        // 连接断开，注销路由并移除会话
+        drop(rx);
+        self.router.unregister(user_id, session_id).await;
+        if let Err(e) = self.event_bus.remove_device(user_id, session_id).await {
+            error!("移除用户 {} 的连接 {} 失败: {:?}", user_id, session_id, e);
+        }
+        let is_last_session = self.session_manager.remove_session(user_id, session_id).await;
+
+        // 仅在用户在整个集群的最后一个连接断开时广播离线消息
+        if is_last_session {
+            match self.event_bus.presence_disconnect(user_id).await {
+                Ok(true) => {
+                    if let Err(e) = self.send_user_status(user_id, "offline").await {
+                        error!("广播用户离线消息失败: {:?}", e);
+                    }
+                }
+                Ok(false) => {}
+                Err(e) => error!("更新用户 {} 的在线状态失败: {:?}", user_id, e),
+            }
         }
 
-        info!("用户 {} 已断开连接", user_id);
+        info!("用户 {} 的连接 {} 已断开", user_id, session_id);
     }
 
     // 处理WebSocket连接
-    pub async fn handle_socket(&self, socket: WebSocket, user_id: i64, nickname: String) {
+    pub async fn handle_socket(
+        &self,
+        socket: WebSocket,
+        state: Arc<AppState>,
+        current_user: AuthUser,
+        token_id: String,
+        connect_msg: WebSocketConnectMessage,
+    ) {
         let (sender, receiver) = socket.split();
-        self.handle_ws_stream(sender, receiver, user_id, nickname)
+        self.handle_ws_stream(sender, receiver, state, current_user, token_id, connect_msg)
             .await;
     }
 
-    // 验证JWT令牌
-    pub fn verify_token(&self, token: &str) -> Result<i64, String> {
+    // 获取用户在整个集群的在线连接（设备）
+    pub async fn list_user_devices(&self, user_id: i64) -> Result<Vec<DeviceSession>, AppError> {
+        self.event_bus.user_devices(user_id).await.map_err(|e| {
+            error!("获取用户 {} 的在线设备失败: {:?}", user_id, e);
+            AppError::InternalServerError("获取在线设备失败".to_string())
+        })
+    }
+
+    // 验证JWT令牌，拒绝已注销的令牌
+    pub async fn verify_token(&self, token: &str) -> Result<TokenClaims, String> {
         let config = self.config_service.get_config();
-        let claims = verify_jwt_token(token, &config.jwt_secret)
+        let claims = verify_access_token(token, &config.jwt_keys)
             .map_err(|_| "无效的token".to_string())?;
-        Ok(claims.sub)
+        match self.token_denylist_service.is_revoked(&claims.jti).await {
+            Ok(false) => Ok(claims),
+            Ok(true) => Err("token已注销".to_string()),
+            Err(e) => {
+                error!("查询令牌黑名单失败: {:?}", e);
+                Err("无法验证token".to_string())
+            }
+        }
     }
 }
@@ -0,0 +1,30 @@
+// 客户端IP解析
+use axum::http::HeaderMap;
+use std::net::{IpAddr, Ipv4Addr, SocketAddr};
+
+// 请求方的客户端IP，由认证中间件写入请求扩展
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ClientIp(pub IpAddr);
+
+// 确定客户端IP：信任反向代理时取X-Forwarded-For中最后一个地址（由最近一层代理追加），
+// 否则使用TCP连接的对端地址
+pub fn resolve_client_ip(
+    headers: &HeaderMap,
+    peer: Option<SocketAddr>,
+    trust_forwarded_for: bool,
+) -> ClientIp {
+    if trust_forwarded_for
+        && let Some(ip) = headers
+            .get("x-forwarded-for")
+            .and_then(|value| value.to_str().ok())
+            .and_then(|value| value.rsplit(',').next())
+            .and_then(|value| value.trim().parse::<IpAddr>().ok())
+    {
+        return ClientIp(ip);
+    }
+
+    ClientIp(
+        peer.map(|addr| addr.ip())
+            .unwrap_or(IpAddr::V4(Ipv4Addr::UNSPECIFIED)),
+    )
+}
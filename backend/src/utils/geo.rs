@@ -0,0 +1,211 @@
+//! 地图可视范围和瓦片相关的坐标工具
+
+use std::f64::consts::PI;
+
+/// Web墨卡托投影可表示的最大纬度
+pub const MAX_MERCATOR_LATITUDE: f64 = 85.051_128_78;
+
+/// 地球平均半径（米）
+const EARTH_RADIUS_METERS: f64 = 6_371_008.8;
+
+/// 经纬度矩形范围，不跨越180度经线
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Viewport {
+    pub south: f64,
+    pub west: f64,
+    pub north: f64,
+    pub east: f64,
+}
+
+impl Viewport {
+    /// 范围中心点（纬度, 经度）
+    pub fn center(&self) -> (f64, f64) {
+        (
+            (self.south + self.north) / 2.0,
+            (self.west + self.east) / 2.0,
+        )
+    }
+
+    /// 包含以某点为圆心、指定半径（米）的圆的最小范围，超出经纬度范围的部分被截断
+    pub fn around(latitude: f64, longitude: f64, radius: f64) -> Self {
+        let lat_delta = (radius / EARTH_RADIUS_METERS).to_degrees();
+        let cos_lat = latitude.to_radians().cos().max(1e-6);
+        let lon_delta = (lat_delta / cos_lat).min(180.0);
+        Self {
+            south: (latitude - lat_delta).max(-90.0),
+            west: (longitude - lon_delta).max(-180.0),
+            north: (latitude + lat_delta).min(90.0),
+            east: (longitude + lon_delta).min(180.0),
+        }
+    }
+
+    /// 在指定缩放级别下覆盖范围的瓦片(x, y)列表
+    pub fn tiles(&self, zoom: u32) -> Vec<(u32, u32)> {
+        let (min_x, max_x) = (tile_x(self.west, zoom), tile_x(self.east, zoom));
+        let (min_y, max_y) = (tile_y(self.north, zoom), tile_y(self.south, zoom));
+        (min_x..=max_x)
+            .flat_map(|x| (min_y..=max_y).map(move |y| (x, y)))
+            .collect()
+    }
+
+    /// 点是否在瓦片范围内：按左闭右开判断，与相邻瓦片共享的边界只属于一个瓦片；
+    /// 最北一行包含北极，最东一列包含180度经线
+    pub fn tile_contains(&self, latitude: f64, longitude: f64) -> bool {
+        latitude >= self.south
+            && (latitude < self.north || self.north >= 90.0)
+            && longitude >= self.west
+            && (longitude < self.east || self.east >= 180.0)
+    }
+
+    /// 在指定缩放级别下覆盖范围的瓦片数量
+    pub fn tile_count(&self, zoom: u32) -> u64 {
+        let columns = tile_x(self.east, zoom) - tile_x(self.west, zoom) + 1;
+        let rows = tile_y(self.south, zoom) - tile_y(self.north, zoom) + 1;
+        columns as u64 * rows as u64
+    }
+}
+
+// 某缩放级别下每行/每列的瓦片数
+fn tiles_per_side(zoom: u32) -> f64 {
+    (1u64 << zoom) as f64
+}
+
+/// 经度所在瓦片的x坐标
+pub fn tile_x(longitude: f64, zoom: u32) -> u32 {
+    let n = tiles_per_side(zoom);
+    (((longitude + 180.0) / 360.0 * n).floor()).clamp(0.0, n - 1.0) as u32
+}
+
+/// 纬度所在瓦片的y坐标，超出墨卡托范围的纬度按边界处理
+pub fn tile_y(latitude: f64, zoom: u32) -> u32 {
+    let n = tiles_per_side(zoom);
+    let lat = latitude
+        .clamp(-MAX_MERCATOR_LATITUDE, MAX_MERCATOR_LATITUDE)
+        .to_radians();
+    let y = (1.0 - lat.tan().asinh() / PI) / 2.0 * n;
+    y.floor().clamp(0.0, n - 1.0) as u32
+}
+
+/// 瓦片的经纬度范围。超出墨卡托范围的纬度归入最北/最南一行（见tile_y），
+/// 因此这两行的范围延伸到两极
+pub fn tile_bounds(zoom: u32, x: u32, y: u32) -> Viewport {
+    let n = tiles_per_side(zoom);
+    let latitude = |y: f64| (PI * (1.0 - 2.0 * y / n)).sinh().atan().to_degrees();
+    let last = n as u32 - 1;
+    Viewport {
+        south: if y >= last {
+            -90.0
+        } else {
+            latitude(y as f64 + 1.0)
+        },
+        west: x as f64 / n * 360.0 - 180.0,
+        north: if y == 0 { 90.0 } else { latitude(y as f64) },
+        east: (x as f64 + 1.0) / n * 360.0 - 180.0,
+    }
+}
+
+/// 两点之间的球面距离（米）
+pub fn distance_meters(lat1: f64, lon1: f64, lat2: f64, lon2: f64) -> f64 {
+    let (phi1, phi2) = (lat1.to_radians(), lat2.to_radians());
+    let d_phi = (lat2 - lat1).to_radians();
+    let d_lambda = (lon2 - lon1).to_radians();
+    let a = (d_phi / 2.0).sin().powi(2) + phi1.cos() * phi2.cos() * (d_lambda / 2.0).sin().powi(2);
+    2.0 * EARTH_RADIUS_METERS * a.sqrt().asin()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn tile_x_at_antimeridian() {
+        for zoom in [0, 1, 5, 22] {
+            let last = (1u64 << zoom) as u32 - 1;
+            assert_eq!(tile_x(-180.0, zoom), 0);
+            assert_eq!(tile_x(180.0, zoom), last);
+            assert_eq!(tile_x(179.999_999, zoom), last);
+        }
+    }
+
+    #[test]
+    fn tile_y_at_poles() {
+        for zoom in [0, 1, 5, 22] {
+            let last = (1u64 << zoom) as u32 - 1;
+            assert_eq!(tile_y(90.0, zoom), 0);
+            assert_eq!(tile_y(MAX_MERCATOR_LATITUDE, zoom), 0);
+            assert_eq!(tile_y(-90.0, zoom), last);
+            assert_eq!(tile_y(-MAX_MERCATOR_LATITUDE, zoom), last);
+        }
+    }
+
+    #[test]
+    fn edge_tiles_extend_to_poles_and_antimeridian() {
+        let zoom = 3;
+        let last = (1u32 << zoom) - 1;
+
+        let north_west = tile_bounds(zoom, 0, 0);
+        assert_eq!(north_west.north, 90.0);
+        assert_eq!(north_west.west, -180.0);
+        assert!(north_west.tile_contains(90.0, -180.0));
+        assert!(north_west.tile_contains(MAX_MERCATOR_LATITUDE + 1.0, -179.0));
+
+        let south_east = tile_bounds(zoom, last, last);
+        assert_eq!(south_east.south, -90.0);
+        assert_eq!(south_east.east, 180.0);
+        assert!(south_east.tile_contains(-90.0, 180.0));
+
+        // -180度经线属于最西一列
+        assert!(!south_east.tile_contains(-89.0, -180.0));
+    }
+
+    #[test]
+    fn tile_bounds_match_tile_coordinates() {
+        let zoom = 4;
+        let last = (1u32 << zoom) - 1;
+        for x in 0..=last {
+            for y in 0..=last {
+                let bounds = tile_bounds(zoom, x, y);
+                // 西南角属于该瓦片，与tile_x/tile_y一致
+                assert!(bounds.tile_contains(bounds.south, bounds.west));
+                assert_eq!(tile_x(bounds.west, zoom), x);
+                let (latitude, longitude) = bounds.center();
+                assert_eq!(tile_x(longitude, zoom), x);
+                assert_eq!(tile_y(latitude, zoom), y);
+            }
+        }
+    }
+
+    #[test]
+    fn shared_edges_belong_to_one_tile() {
+        let zoom = 2;
+        let west = tile_bounds(zoom, 1, 1);
+        let east = tile_bounds(zoom, 2, 1);
+        let south = tile_bounds(zoom, 1, 2);
+        let (latitude, _) = west.center();
+
+        assert!(!west.tile_contains(latitude, west.east));
+        assert!(east.tile_contains(latitude, west.east));
+        assert!(west.tile_contains(west.south, west.west));
+        assert!(!south.tile_contains(west.south, west.west));
+    }
+
+    #[test]
+    fn viewport_tiles_near_antimeridian_and_poles() {
+        // 范围被截断在180度经线，不跨越到另一侧
+        let viewport = Viewport::around(0.0, 179.9, 50_000.0);
+        assert_eq!(viewport.east, 180.0);
+        let tiles = viewport.tiles(2);
+        assert_eq!(tiles, vec![(3, 1), (3, 2)]);
+        assert_eq!(viewport.tile_count(2), tiles.len() as u64);
+
+        // 极点附近经度跨度覆盖全部经度，只包含最北一行
+        let viewport = Viewport::around(89.9, 0.0, 50_000.0);
+        assert_eq!(
+            (viewport.west, viewport.east, viewport.north),
+            (-180.0, 180.0, 90.0)
+        );
+        let tiles = viewport.tiles(2);
+        assert_eq!(tiles, vec![(0, 0), (1, 0), (2, 0), (3, 0)]);
+        assert_eq!(viewport.tile_count(2), 4);
+    }
+}
@@ -0,0 +1,150 @@
+//! geohash解码工具，编码由PostGIS的ST_GeoHash完成
+
+const BASE32: &[u8] = b"0123456789bcdefghjkmnpqrstuvwxyz";
+
+/// geohash单元的范围
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct CellBounds {
+    pub south: f64,
+    pub west: f64,
+    pub north: f64,
+    pub east: f64,
+}
+
+impl CellBounds {
+    /// 单元中心点（纬度, 经度）
+    pub fn center(&self) -> (f64, f64) {
+        (
+            (self.south + self.north) / 2.0,
+            (self.west + self.east) / 2.0,
+        )
+    }
+}
+
+/// 解码geohash得到单元范围，包含无效字符时返回None
+pub fn decode_bounds(hash: &str) -> Option<CellBounds> {
+    let mut bounds = CellBounds {
+        south: -90.0,
+        west: -180.0,
+        north: 90.0,
+        east: 180.0,
+    };
+    // 第一个比特对应经度，之后经纬度交替
+    let mut is_longitude = true;
+
+    for c in hash.bytes() {
+        let value = BASE32.iter().position(|&b| b == c)?;
+        for shift in (0..5).rev() {
+            let bit = (value >> shift) & 1 == 1;
+            if is_longitude {
+                let mid = (bounds.west + bounds.east) / 2.0;
+                if bit {
+                    bounds.west = mid;
+                } else {
+                    bounds.east = mid;
+                }
+            } else {
+                let mid = (bounds.south + bounds.north) / 2.0;
+                if bit {
+                    bounds.south = mid;
+                } else {
+                    bounds.north = mid;
+                }
+            }
+            is_longitude = !is_longitude;
+        }
+    }
+
+    Some(bounds)
+}
+
+/// 指定精度的单元大小（纬度跨度, 经度跨度），单位为度
+pub fn cell_size(precision: u32) -> (f64, f64) {
+    let bits = precision * 5;
+    let longitude_bits = bits.div_ceil(2);
+    let latitude_bits = bits / 2;
+    (
+        180.0 / 2f64.powi(latitude_bits as i32),
+        360.0 / 2f64.powi(longitude_bits as i32),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // 参照实现：与ST_GeoHash相同的编码规则，用于验证解码结果
+    fn encode(latitude: f64, longitude: f64, precision: u32) -> String {
+        let mut bounds = CellBounds {
+            south: -90.0,
+            west: -180.0,
+            north: 90.0,
+            east: 180.0,
+        };
+        let mut hash = String::with_capacity(precision as usize);
+        // 第一个比特对应经度，之后经纬度交替
+        let mut is_longitude = true;
+
+        for _ in 0..precision {
+            let mut value = 0;
+            for _ in 0..5 {
+                let bit = if is_longitude {
+                    let mid = (bounds.west + bounds.east) / 2.0;
+                    let bit = longitude >= mid;
+                    if bit {
+                        bounds.west = mid;
+                    } else {
+                        bounds.east = mid;
+                    }
+                    bit
+                } else {
+                    let mid = (bounds.south + bounds.north) / 2.0;
+                    let bit = latitude >= mid;
+                    if bit {
+                        bounds.south = mid;
+                    } else {
+                        bounds.north = mid;
+                    }
+                    bit
+                };
+                value = (value << 1) | bit as usize;
+                is_longitude = !is_longitude;
+            }
+            hash.push(BASE32[value] as char);
+        }
+
+        hash
+    }
+
+    #[test]
+    fn encode_known_vector() {
+        assert_eq!(encode(57.64911, 10.40744, 11), "u4pruydqqvj");
+        assert_eq!(encode(39.92, 116.46, 4), "wx4g");
+    }
+
+    #[test]
+    fn decode_contains_encoded_point() {
+        for &(latitude, longitude) in &[
+            (57.64911, 10.40744),
+            (35.16, 112.68),
+            (-33.8688, 151.2093),
+            (0.0, 0.0),
+            (-90.0, -180.0),
+        ] {
+            let hash = encode(latitude, longitude, 6);
+            let bounds = decode_bounds(&hash).unwrap();
+            assert!(bounds.south <= latitude && latitude <= bounds.north);
+            assert!(bounds.west <= longitude && longitude <= bounds.east);
+
+            let (height, width) = cell_size(6);
+            assert!((bounds.north - bounds.south - height).abs() < 1e-9);
+            assert!((bounds.east - bounds.west - width).abs() < 1e-9);
+        }
+    }
+
+    #[test]
+    fn decode_rejects_invalid_characters() {
+        // a、i、l、o不在geohash字符表中
+        assert!(decode_bounds("wx4a").is_none());
+    }
+}
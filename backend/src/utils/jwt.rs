@@ -1,23 +1,252 @@
+use base64::{Engine, engine::general_purpose::URL_SAFE_NO_PAD};
 use chrono::{Duration, Utc};
-use jsonwebtoken::{DecodingKey, EncodingKey, Header, Validation, decode, encode};
+use jsonwebtoken::jwk::{
+    AlgorithmParameters, CommonParameters, EllipticCurve, Jwk, JwkSet, KeyAlgorithm,
+    OctetKeyPairParameters, OctetKeyPairType, PublicKeyUse,
+};
+use jsonwebtoken::{
+    Algorithm, DecodingKey, EncodingKey, Header, Validation, decode, decode_header, encode,
+};
+use ring::rand::SystemRandom;
+use ring::signature::{Ed25519KeyPair, KeyPair};
 use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::path::Path;
+use uuid::Uuid;
 
-#[derive(Debug, Serialize, Deserialize)]
+// Ed25519原始公钥的长度
+const ED25519_PUBLIC_KEY_LEN: usize = 32;
+// Ed25519公钥SubjectPublicKeyInfo编码的固定前缀（算法标识1.3.101.112），其后紧跟原始公钥
+const ED25519_SPKI_PREFIX: [u8; 12] = [
+    0x30, 0x2a, 0x30, 0x05, 0x06, 0x03, 0x2b, 0x65, 0x70, 0x03, 0x21, 0x00,
+];
+
+// 令牌类型
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TokenType {
+    // 访问令牌，用于接口认证
+    Access,
+    // 刷新令牌，只能用于换取新的令牌
+    Refresh,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct TokenClaims {
-    pub sub: i64,        // 用户ID
-    pub iat: usize,      // 颁发时间
-    pub exp: usize,      // 过期时间
-    pub temp: bool,      // 是否为临时用户
+    pub sub: i64,              // 用户ID
+    pub iat: usize,            // 颁发时间
+    pub exp: usize,            // 过期时间
+    pub temp: bool,            // 是否为临时用户
+    pub token_type: TokenType, // 令牌类型
+    pub jti: String,           // 令牌ID
+}
+
+// 一个可用于验证令牌的公钥
+#[derive(Clone)]
+struct VerificationKey {
+    decoding_key: DecodingKey,
+    jwk: Jwk,
+}
+
+// JWT密钥集：使用当前签名密钥（EdDSA）签发令牌，接受所有已配置密钥签发的令牌。
+// 轮换时先加入新密钥再切换签名密钥，旧密钥保留到其签发的令牌全部过期，用户无需重新登录
+#[derive(Clone)]
+pub struct JwtKeys {
+    signing_kid: String,
+    encoding_key: EncodingKey,
+    verification_keys: HashMap<String, VerificationKey>,
 }
 
+impl std::fmt::Debug for JwtKeys {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let mut kids: Vec<&String> = self.verification_keys.keys().collect();
+        kids.sort();
+        f.debug_struct("JwtKeys")
+            .field("signing_kid", &self.signing_kid)
+            .field("verification_kids", &kids)
+            .finish()
+    }
+}
+
+impl JwtKeys {
+    // 从目录加载密钥：<kid>.pem为PKCS#8格式的Ed25519私钥，<kid>.pub.pem为只用于验证的公钥。
+    // 未指定签名密钥时使用kid排序最大的私钥
+    pub fn load(dir: &Path, signing_kid: Option<&str>) -> Result<Self, String> {
+        let entries =
+            std::fs::read_dir(dir).map_err(|e| format!("读取JWT密钥目录失败: {:?}", e))?;
+
+        let mut private_keys = HashMap::new();
+        // 每个kid的原始公钥，同一kid同时有私钥和公钥文件时两者必须匹配
+        let mut public_keys: HashMap<String, Vec<u8>> = HashMap::new();
+        for entry in entries {
+            let path = entry
+                .map_err(|e| format!("读取JWT密钥目录失败: {:?}", e))?
+                .path();
+            let Some(file_name) = path.file_name().and_then(|name| name.to_str()) else {
+                continue;
+            };
+            let Some(stem) = file_name.strip_suffix(".pem") else {
+                continue;
+            };
+
+            let content = std::fs::read(&path)
+                .map_err(|e| format!("读取JWT密钥失败: {}, {:?}", file_name, e))?;
+            let pem = pem::parse(content)
+                .map_err(|e| format!("解析JWT密钥失败: {}, {:?}", file_name, e))?;
+
+            let (kid, public_key) = match stem.strip_suffix(".pub") {
+                Some(kid) => {
+                    let public_key = Self::public_key_from_spki(pem.contents())
+                        .ok_or_else(|| format!("不是有效的Ed25519公钥: {}", file_name))?;
+                    (kid, public_key.to_vec())
+                }
+                None => {
+                    let key_pair = Ed25519KeyPair::from_pkcs8_maybe_unchecked(pem.contents())
+                        .map_err(|e| format!("不是有效的Ed25519私钥: {}, {:?}", file_name, e))?;
+                    private_keys.insert(stem.to_string(), pem.contents().to_vec());
+                    (stem, key_pair.public_key().as_ref().to_vec())
+                }
+            };
+
+            match public_keys.get(kid) {
+                Some(existing) if *existing != public_key => {
+                    return Err(format!("JWT私钥与公钥不匹配: kid={}", kid));
+                }
+                Some(_) => {}
+                None => {
+                    public_keys.insert(kid.to_string(), public_key);
+                }
+            }
+        }
+
+        let verification_keys = public_keys
+            .iter()
+            .map(|(kid, public_key)| Ok((kid.clone(), Self::verification_key(kid, public_key)?)))
+            .collect::<Result<HashMap<_, _>, String>>()?;
+
+        let signing_kid = match signing_kid {
+            Some(kid) => kid.to_string(),
+            None => private_keys
+                .keys()
+                .max()
+                .cloned()
+                .ok_or_else(|| format!("JWT密钥目录中没有私钥: {}", dir.display()))?,
+        };
+        let pkcs8 = private_keys
+            .get(&signing_kid)
+            .ok_or_else(|| format!("找不到签名密钥的私钥: kid={}", signing_kid))?;
+
+        Ok(Self {
+            encoding_key: EncodingKey::from_ed_der(pkcs8),
+            signing_kid,
+            verification_keys,
+        })
+    }
+
+    // 生成临时密钥，仅用于开发环境，重启后之前签发的令牌全部失效
+    pub fn generate_ephemeral() -> Result<Self, String> {
+        let pkcs8 = Ed25519KeyPair::generate_pkcs8(&SystemRandom::new())
+            .map_err(|e| format!("生成JWT密钥失败: {:?}", e))?;
+        let key_pair = Ed25519KeyPair::from_pkcs8(pkcs8.as_ref())
+            .map_err(|e| format!("生成JWT密钥失败: {:?}", e))?;
+
+        let signing_kid = format!("dev-{}", Uuid::new_v4().simple());
+        let key = Self::verification_key(&signing_kid, key_pair.public_key().as_ref())?;
+
+        Ok(Self {
+            encoding_key: EncodingKey::from_ed_der(pkcs8.as_ref()),
+            verification_keys: HashMap::from([(signing_kid.clone(), key)]),
+            signing_kid,
+        })
+    }
+
+    fn verification_key(kid: &str, public_key: &[u8]) -> Result<VerificationKey, String> {
+        let jwk = Jwk {
+            common: CommonParameters {
+                public_key_use: Some(PublicKeyUse::Signature),
+                key_algorithm: Some(KeyAlgorithm::EdDSA),
+                key_id: Some(kid.to_string()),
+                ..Default::default()
+            },
+            algorithm: AlgorithmParameters::OctetKeyPair(OctetKeyPairParameters {
+                key_type: OctetKeyPairType::OctetKeyPair,
+                curve: EllipticCurve::Ed25519,
+                x: URL_SAFE_NO_PAD.encode(public_key),
+            }),
+        };
+        let decoding_key = DecodingKey::from_jwk(&jwk)
+            .map_err(|e| format!("创建JWT验证密钥失败: kid={}, {:?}", kid, e))?;
+        Ok(VerificationKey { decoding_key, jwk })
+    }
+
+    // 从SubjectPublicKeyInfo中取出Ed25519原始公钥，算法标识或长度不符时返回None
+    fn public_key_from_spki(der: &[u8]) -> Option<&[u8]> {
+        der.strip_prefix(&ED25519_SPKI_PREFIX)
+            .filter(|key| key.len() == ED25519_PUBLIC_KEY_LEN)
+    }
+
+    // 当前签名密钥的ID
+    pub fn signing_kid(&self) -> &str {
+        &self.signing_kid
+    }
+
+    // 所有验证公钥，供其他服务验证令牌
+    pub fn jwks(&self) -> JwkSet {
+        let mut keys: Vec<Jwk> = self
+            .verification_keys
+            .values()
+            .map(|key| key.jwk.clone())
+            .collect();
+        keys.sort_by(|a, b| a.common.key_id.cmp(&b.common.key_id));
+        JwkSet { keys }
+    }
+}
+
+// 生成访问令牌
 pub fn generate_jwt_token(
     user_id: i64,
-    secret: &str,
+    keys: &JwtKeys,
     expires_in: i64,
     is_temp: bool,
+) -> Result<(String, chrono::DateTime<Utc>), jsonwebtoken::errors::Error> {
+    encode_token(
+        user_id,
+        keys,
+        expires_in,
+        is_temp,
+        TokenType::Access,
+        Uuid::new_v4(),
+    )
+}
+
+// 生成刷新令牌，jti与refresh_tokens表中的记录对应
+pub fn generate_refresh_token(
+    user_id: i64,
+    keys: &JwtKeys,
+    expires_in: i64,
+    is_temp: bool,
+    token_id: Uuid,
+) -> Result<(String, chrono::DateTime<Utc>), jsonwebtoken::errors::Error> {
+    encode_token(
+        user_id,
+        keys,
+        expires_in,
+        is_temp,
+        TokenType::Refresh,
+        token_id,
+    )
+}
+
+fn encode_token(
+    user_id: i64,
+    keys: &JwtKeys,
+    expires_in: i64,
+    is_temp: bool,
+    token_type: TokenType,
+    token_id: Uuid,
 ) -> Result<(String, chrono::DateTime<Utc>), jsonwebtoken::errors::Error> {
     let now = Utc::now();
-    let expires_at = now + Duration::minutes(expires_in);
+    let expires_at = now + Duration::seconds(expires_in);
     let exp = expires_at.timestamp() as usize;
 
     let claims = TokenClaims {
@@ -25,31 +254,114 @@ pub fn generate_jwt_token(
         iat: now.timestamp() as usize,
         exp,
         temp: is_temp,
+        token_type,
+        jti: token_id.to_string(),
     };
 
-    let token = encode(
-        &Header::default(),
-        &claims,
-        &EncodingKey::from_secret(secret.as_bytes()),
-    )?;
+    // 令牌头携带签名密钥的kid，验证方据此选择公钥
+    let mut header = Header::new(Algorithm::EdDSA);
+    header.kid = Some(keys.signing_kid.clone());
+    let token = encode(&header, &claims, &keys.encoding_key)?;
 
     Ok((token, expires_at))
 }
 
+// 按令牌头中的kid选择验证公钥；没有kid或kid未知的令牌（包括旧版HS256令牌）一律拒绝
 pub fn verify_jwt_token(
     token: &str,
-    secret: &str,
+    keys: &JwtKeys,
 ) -> Result<TokenClaims, jsonwebtoken::errors::Error> {
-    let decoded = decode::<TokenClaims>(
-        token,
-        &DecodingKey::from_secret(secret.as_bytes()),
-        &Validation::default(),
-    )?;
+    let header = decode_header(token)?;
+    let key = header
+        .kid
+        .and_then(|kid| keys.verification_keys.get(&kid))
+        .ok_or(jsonwebtoken::errors::ErrorKind::InvalidToken)?;
+    let decoded =
+        decode::<TokenClaims>(token, &key.decoding_key, &Validation::new(Algorithm::EdDSA))?;
 
     Ok(decoded.claims)
 }
 
+// 验证访问令牌，拒绝刷新令牌
+pub fn verify_access_token(
+    token: &str,
+    keys: &JwtKeys,
+) -> Result<TokenClaims, jsonwebtoken::errors::Error> {
+    let claims = verify_jwt_token(token, keys)?;
+    if claims.token_type != TokenType::Access {
+        return Err(jsonwebtoken::errors::ErrorKind::InvalidToken.into());
+    }
+    Ok(claims)
+}
+
+// 验证刷新令牌，拒绝访问令牌
+pub fn verify_refresh_token(
+    token: &str,
+    keys: &JwtKeys,
+) -> Result<TokenClaims, jsonwebtoken::errors::Error> {
+    let claims = verify_jwt_token(token, keys)?;
+    if claims.token_type != TokenType::Refresh {
+        return Err(jsonwebtoken::errors::ErrorKind::InvalidToken.into());
+    }
+    Ok(claims)
+}
+
 // 检查用户是否为临时用户
 pub fn is_temp_user(claims: &TokenClaims) -> bool {
     claims.temp
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn access_and_refresh_tokens_are_not_interchangeable() {
+        let keys = JwtKeys::generate_ephemeral().unwrap();
+        let (access, _) = generate_jwt_token(1, &keys, 3600, false).unwrap();
+        let (refresh, _) = generate_refresh_token(1, &keys, 3600, false, Uuid::new_v4()).unwrap();
+
+        assert_eq!(verify_access_token(&access, &keys).unwrap().sub, 1);
+        assert!(verify_refresh_token(&access, &keys).is_err());
+        assert!(verify_access_token(&refresh, &keys).is_err());
+        assert!(verify_refresh_token(&refresh, &keys).is_ok());
+    }
+
+    #[test]
+    fn rejects_legacy_hs256_token_without_kid() {
+        let keys = JwtKeys::generate_ephemeral().unwrap();
+        let now = Utc::now().timestamp();
+        let claims = serde_json::json!({
+            "sub": 1,
+            "iat": now,
+            "exp": now + 3600,
+            "temp": false,
+        });
+        let token = encode(
+            &Header::new(Algorithm::HS256),
+            &claims,
+            &EncodingKey::from_secret(b"devkey"),
+        )
+        .unwrap();
+
+        assert!(verify_jwt_token(&token, &keys).is_err());
+        assert!(verify_access_token(&token, &keys).is_err());
+    }
+
+    #[test]
+    fn rejects_token_without_type_or_id() {
+        let keys = JwtKeys::generate_ephemeral().unwrap();
+        let now = Utc::now().timestamp();
+        let claims = serde_json::json!({
+            "sub": 1,
+            "iat": now,
+            "exp": now + 3600,
+            "temp": false,
+        });
+        let mut header = Header::new(Algorithm::EdDSA);
+        header.kid = Some(keys.signing_kid.clone());
+        let token = encode(&header, &claims, &keys.encoding_key).unwrap();
+
+        assert!(verify_access_token(&token, &keys).is_err());
+    }
+}
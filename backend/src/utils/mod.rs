@@ -1,2 +1,6 @@
+pub mod client_ip;
+pub mod geo;
+pub mod geohash;
 pub mod jwt;
+pub mod redis;
 pub mod response;
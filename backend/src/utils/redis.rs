@@ -0,0 +1,123 @@
+// Redis异步命令执行器：多个请求共享一条自动重连的多路复用连接
+use crate::utils::response::AppError;
+use redis::aio::{ConnectionManager, ConnectionManagerConfig};
+use redis::{Client as RedisClient, RedisResult};
+use std::future::Future;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use tokio::sync::OnceCell;
+use tracing::error;
+
+// 建立连接的超时时间
+const CONNECT_TIMEOUT: Duration = Duration::from_secs(2);
+// 单条命令的超时时间
+const COMMAND_TIMEOUT: Duration = Duration::from_secs(1);
+// 连接断开后的重连次数
+const RECONNECT_RETRIES: usize = 3;
+// 建立连接失败后的冷却时间，期间直接返回错误，避免每个请求都等待连接超时
+const CONNECT_COOLDOWN: Duration = Duration::from_secs(5);
+
+#[derive(Clone)]
+pub struct RedisExecutor {
+    client: Arc<RedisClient>,
+    // 首次使用时建立的连接管理器，建立失败时下次使用再重试
+    manager: Arc<OnceCell<ConnectionManager>>,
+    // 上次建立连接失败的时间
+    connect_failed_at: Arc<Mutex<Option<Instant>>>,
+}
+
+impl std::fmt::Debug for RedisExecutor {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("RedisExecutor").finish_non_exhaustive()
+    }
+}
+
+impl RedisExecutor {
+    pub fn new(client: Arc<RedisClient>) -> Self {
+        Self {
+            client,
+            manager: Arc::new(OnceCell::new()),
+            connect_failed_at: Arc::new(Mutex::new(None)),
+        }
+    }
+
+    pub fn client(&self) -> &Arc<RedisClient> {
+        &self.client
+    }
+
+    async fn connection(&self) -> Result<ConnectionManager, AppError> {
+        if let Some(manager) = self.manager.get() {
+            return Ok(manager.clone());
+        }
+
+        {
+            let failed_at = self
+                .connect_failed_at
+                .lock()
+                .unwrap_or_else(|e| e.into_inner());
+            if failed_at.is_some_and(|at| at.elapsed() < CONNECT_COOLDOWN) {
+                return Err(Self::unavailable());
+            }
+        }
+
+        let result = self
+            .manager
+            .get_or_try_init(|| async {
+                let config = ConnectionManagerConfig::new()
+                    .set_connection_timeout(CONNECT_TIMEOUT)
+                    .set_response_timeout(COMMAND_TIMEOUT)
+                    .set_number_of_retries(RECONNECT_RETRIES);
+                let connect = ConnectionManager::new_with_config((*self.client).clone(), config);
+                match tokio::time::timeout(CONNECT_TIMEOUT, connect).await {
+                    Ok(result) => result.map_err(|e| {
+                        error!("连接Redis失败: {:?}", e);
+                        Self::unavailable()
+                    }),
+                    Err(_) => {
+                        error!("连接Redis超时");
+                        Err(Self::unavailable())
+                    }
+                }
+            })
+            .await;
+
+        let mut failed_at = self
+            .connect_failed_at
+            .lock()
+            .unwrap_or_else(|e| e.into_inner());
+        match result {
+            Ok(manager) => {
+                *failed_at = None;
+                Ok(manager.clone())
+            }
+            Err(e) => {
+                *failed_at = Some(Instant::now());
+                Err(e)
+            }
+        }
+    }
+
+    // 使用共享连接执行Redis命令，出错或超时时记录日志并返回通用错误
+    pub async fn run<T, F, Fut>(&self, f: F) -> Result<T, AppError>
+    where
+        F: FnOnce(ConnectionManager) -> Fut,
+        Fut: Future<Output = RedisResult<T>>,
+    {
+        let connection = self.connection().await?;
+        match tokio::time::timeout(COMMAND_TIMEOUT, f(connection)).await {
+            Ok(Ok(value)) => Ok(value),
+            Ok(Err(e)) => {
+                error!("Redis命令执行失败: {:?}", e);
+                Err(Self::unavailable())
+            }
+            Err(_) => {
+                error!("Redis命令执行超时");
+                Err(Self::unavailable())
+            }
+        }
+    }
+
+    fn unavailable() -> AppError {
+        AppError::InternalServerError("服务器错误".to_string())
+    }
+}
@@ -20,18 +20,27 @@ pub enum AppError {
     Unauthorized(String),
     Forbidden(String),
     Conflict(String),
+    TooManyRequests(String),
 }
 
-impl IntoResponse for AppError {
-    fn into_response(self) -> Response {
-        let (error_message, error_code) = match self {
+impl AppError {
+    /// 拆分为错误消息和业务错误码
+    pub fn into_parts(self) -> (String, i32) {
+        match self {
             AppError::NotFound(message) => (message, 1004), // 资源不存在
             AppError::BadRequest(message) => (message, 1000), // 参数验证错误
             AppError::InternalServerError(message) => (message, 5000), // 服务器内部错误
             AppError::Unauthorized(message) => (message, 1002), // 认证失败
             AppError::Forbidden(message) => (message, 1003), // 权限不足
             AppError::Conflict(message) => (message, 1005), // 冲突
-        };
+            AppError::TooManyRequests(message) => (message, 1006), // 请求过于频繁
+        }
+    }
+}
+
+impl IntoResponse for AppError {
+    fn into_response(self) -> Response {
+        let (error_message, error_code) = self.into_parts();
 
         // 所有错误响应统一使用HTTP状态码200，将实际错误码放在ApiResponse中
         let body = Json(ApiResponse::<()> {
@@ -75,6 +84,25 @@ pub struct Pagination {
     pub cursor: Option<i64>,
 }
 
+/// 按多查一条的结果截取一页数据
+/// rows为按limit + 1查询的结果，返回截取后的数据、是否有更多数据和下一页游标
+pub fn paginate<T>(
+    mut rows: Vec<T>,
+    limit: i64,
+    cursor_of: impl Fn(&T) -> i64,
+) -> (Vec<T>, bool, Option<i64>) {
+    let has_more = rows.len() as i64 > limit;
+    if has_more {
+        rows.truncate(limit as usize);
+    }
+    let next_cursor = if has_more {
+        rows.last().map(cursor_of)
+    } else {
+        None
+    };
+    (rows, has_more, next_cursor)
+}
+
 /// 通用分页响应元数据
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct PaginationMeta {
@@ -0,0 +1,11 @@
+use crate::utils::response::AppError;
+
+/// 验证管理操作的原因说明
+pub fn validate_reason(reason: Option<&str>) -> Result<(), AppError> {
+    if reason.is_some_and(|reason| reason.chars().count() > 500) {
+        return Err(AppError::BadRequest(
+            "操作原因不能超过500个字符".to_string(),
+        ));
+    }
+    Ok(())
+}
@@ -30,15 +30,17 @@ pub fn validate_checkin_tags(tags: &[String]) -> Result<(), AppError> {
 }
 
 /// 验证签到创建请求的所有参数
-pub fn validate_checkin_creation_request(description: &str, latitude: f64, longitude: f64, location_name: &str, tags: &[String]) -> Result<(), AppError> {
+pub fn validate_checkin_creation_request(description: &str, latitude: f64, longitude: f64, location_name: Option<&str>, tags: &[String]) -> Result<(), AppError> {
     // 组合各个验证函数
     validate_checkin_content(description)?;
     
     // 位置验证由common_validator处理
     super::common_validator::validate_location_params(latitude, longitude)?;
     
-    // 位置名称验证由common_validator处理
-    super::common_validator::validate_location_name(location_name)?;
+    // 位置名称验证由common_validator处理，未提供时由服务端生成
+    if let Some(name) = location_name {
+        super::common_validator::validate_location_name(name)?;
+    }
     
     validate_checkin_tags(tags)?;
     
@@ -1,4 +1,5 @@
 use crate::utils::response::AppError;
+use chrono::{DateTime, Utc};
 use uuid::Uuid;
 
 /// 验证UUID格式的参数
@@ -21,6 +22,19 @@ pub fn validate_location_params(latitude: f64, longitude: f64) -> Result<(), App
     Ok(())
 }
 
+/// 验证时间范围，两端都指定时开始时间不能晚于结束时间
+pub fn validate_time_range(
+    start_time: Option<DateTime<Utc>>,
+    end_time: Option<DateTime<Utc>>,
+) -> Result<(), AppError> {
+    if let (Some(start), Some(end)) = (start_time, end_time)
+        && start > end
+    {
+        return Err(AppError::BadRequest("开始时间不能晚于结束时间".to_string()));
+    }
+    Ok(())
+}
+
 /// 验证分页参数
 pub fn validate_pagination(cursor: i64, limit: i64, max_limit: i64) -> Result<(), AppError> {
     if cursor < 0 {
@@ -0,0 +1,69 @@
+use crate::{
+    models::{api::location::BoundingBox, constants::viewport_types},
+    services::{
+        cluster_service::{MAX_CLUSTER_TILES, MAX_CLUSTER_ZOOM},
+        hotspot_service::ROLLUP_PRECISION,
+    },
+    utils::{geo::Viewport, response::AppError},
+};
+
+/// 验证地图可视范围
+pub fn validate_bounding_box(bounds: &BoundingBox) -> Result<(), AppError> {
+    let BoundingBox {
+        south_west,
+        north_east,
+    } = bounds;
+    super::common_validator::validate_location_params(south_west.latitude, south_west.longitude)?;
+    super::common_validator::validate_location_params(north_east.latitude, north_east.longitude)?;
+
+    if south_west.latitude >= north_east.latitude {
+        return Err(AppError::BadRequest(
+            "西南角纬度必须小于东北角纬度".to_string(),
+        ));
+    }
+    if south_west.longitude >= north_east.longitude {
+        return Err(AppError::BadRequest(
+            "西南角经度必须小于东北角经度".to_string(),
+        ));
+    }
+    Ok(())
+}
+
+/// 验证热力图的geohash精度
+pub fn validate_heatmap_precision(precision: u32) -> Result<(), AppError> {
+    if (1..=ROLLUP_PRECISION).contains(&precision) {
+        Ok(())
+    } else {
+        Err(AppError::BadRequest(format!(
+            "精度必须在1-{}之间",
+            ROLLUP_PRECISION
+        )))
+    }
+}
+
+/// 验证可视范围查询的内容类型
+pub fn validate_viewport_types(types: &[String]) -> Result<(), AppError> {
+    if types.is_empty() {
+        return Err(AppError::BadRequest("查询类型不能为空".to_string()));
+    }
+    if let Some(invalid) = types.iter().find(|t| !viewport_types::is_valid_type(t)) {
+        return Err(AppError::BadRequest(format!("无效的查询类型: {}", invalid)));
+    }
+    Ok(())
+}
+
+/// 验证聚合的缩放级别，范围在该级别下覆盖的瓦片过多时要求提高缩放级别
+pub fn validate_cluster_zoom(zoom: u32, viewport: &Viewport) -> Result<(), AppError> {
+    if zoom > MAX_CLUSTER_ZOOM {
+        return Err(AppError::BadRequest(format!(
+            "缩放级别必须在0-{}之间",
+            MAX_CLUSTER_ZOOM
+        )));
+    }
+    if viewport.tile_count(zoom) > MAX_CLUSTER_TILES {
+        return Err(AppError::BadRequest(
+            "查询范围相对缩放级别过大，请提高缩放级别或缩小范围".to_string(),
+        ));
+    }
+    Ok(())
+}
@@ -1,3 +1,4 @@
+use crate::models::constants::ws_target_types;
 use crate::utils::response::AppError;
 
 /// 验证消息内容
@@ -49,3 +50,22 @@ pub fn validate_recipient_id(recipient_id: &str) -> Result<i64, AppError> {
         .parse::<i64>()
         .map_err(|_| AppError::BadRequest("无效的用户ID格式".to_string()))
 }
+
+/// 验证WebSocket命令的请求ID
+pub fn validate_request_id(request_id: &str) -> Result<(), AppError> {
+    if request_id.is_empty() || request_id.len() > 64 {
+        return Err(AppError::BadRequest("请求ID长度应在1-64个字符之间".to_string()));
+    }
+    Ok(())
+}
+
+/// 验证输入状态/订阅的目标类型
+pub fn validate_target_type(target_type: &str) -> Result<(), AppError> {
+    if !ws_target_types::is_valid_target_type(target_type) {
+        return Err(AppError::BadRequest(format!(
+            "不支持的目标类型: {}",
+            target_type
+        )));
+    }
+    Ok(())
+}
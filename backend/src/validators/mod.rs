@@ -1,5 +1,7 @@
+pub mod admin_validator;
 pub mod checkin_validator;
 pub mod common_validator;
 pub mod group_validator;
+pub mod location_validator;
 pub mod message_validator;
 pub mod user_validator;
@@ -1,4 +1,8 @@
-use crate::utils::response::AppError;
+use crate::{
+    models::constants::{genders, location_privacy, location_visibility},
+    utils::response::AppError,
+};
+use chrono::{NaiveDate, Utc};
 
 /// 验证用户注册信息
 pub fn validate_user_registration(login_id: &str, password: Option<&str>, nickname: Option<&str>) -> Result<(), AppError> {
@@ -29,6 +33,22 @@ pub fn validate_user_registration(login_id: &str, password: Option<&str>, nickna
     Ok(())
 }
 
+/// 验证密码
+pub fn validate_password(password: &str) -> Result<(), AppError> {
+    if password.len() < 6 || password.len() > 20 {
+        return Err(AppError::BadRequest("密码长度应在6-20个字符之间".to_string()));
+    }
+    Ok(())
+}
+
+/// 验证密码重置验证码
+pub fn validate_reset_code(code: &str) -> Result<(), AppError> {
+    if code.len() != 8 || !code.chars().all(|c| c.is_ascii_digit()) {
+        return Err(AppError::BadRequest("验证码应为8位数字".to_string()));
+    }
+    Ok(())
+}
+
 /// 验证昵称
 pub fn validate_nickname(nickname: &str) -> Result<(), AppError> {
     if nickname.is_empty() || nickname.len() > 50 {
@@ -37,6 +57,104 @@ pub fn validate_nickname(nickname: &str) -> Result<(), AppError> {
     Ok(())
 }
 
+/// 验证头像URL，空字符串表示清除头像
+pub fn validate_avatar_url(avatar_url: &str) -> Result<(), AppError> {
+    if avatar_url.is_empty() {
+        return Ok(());
+    }
+    if avatar_url.len() > 500 {
+        return Err(AppError::BadRequest("头像URL不能超过500个字符".to_string()));
+    }
+    if !avatar_url.starts_with("https://") && !avatar_url.starts_with("http://") {
+        return Err(AppError::BadRequest("头像URL必须以http://或https://开头".to_string()));
+    }
+    Ok(())
+}
+
+/// 验证个人简介
+pub fn validate_bio(bio: &str) -> Result<(), AppError> {
+    if bio.chars().count() > 200 {
+        return Err(AppError::BadRequest("个人简介不能超过200个字符".to_string()));
+    }
+    Ok(())
+}
+
+/// 验证性别，空字符串表示清除
+pub fn validate_gender(gender: &str) -> Result<(), AppError> {
+    if gender.is_empty() || genders::is_valid_gender(gender) {
+        Ok(())
+    } else {
+        Err(AppError::BadRequest(format!("无效的性别: {}", gender)))
+    }
+}
+
+/// 验证位置隐私级别
+pub fn validate_location_privacy(level: &str) -> Result<(), AppError> {
+    if location_privacy::is_valid_level(level) {
+        Ok(())
+    } else {
+        Err(AppError::BadRequest(format!("无效的位置隐私级别: {}", level)))
+    }
+}
+
+/// 验证位置可见范围，限时设置最长7天，everyone不能设置时长
+pub fn validate_location_visibility(
+    visibility: &str,
+    duration_minutes: Option<i64>,
+) -> Result<(), AppError> {
+    if !location_visibility::is_valid_visibility(visibility) {
+        return Err(AppError::BadRequest(format!(
+            "无效的位置可见范围: {}",
+            visibility
+        )));
+    }
+    if let Some(minutes) = duration_minutes {
+        if visibility == location_visibility::EVERYONE {
+            return Err(AppError::BadRequest(
+                "对所有人可见时不能设置时长".to_string(),
+            ));
+        }
+        if !(1..=10080).contains(&minutes) {
+            return Err(AppError::BadRequest(
+                "时长必须在1到10080分钟（7天）之间".to_string(),
+            ));
+        }
+    }
+    Ok(())
+}
+
+/// 验证设备上报的定位精度、速度和方向
+pub fn validate_motion_params(
+    accuracy: Option<f64>,
+    speed: Option<f64>,
+    heading: Option<f64>,
+) -> Result<(), AppError> {
+    if accuracy.is_some_and(|v| !v.is_finite() || v < 0.0) {
+        return Err(AppError::BadRequest("定位精度不能为负数".to_string()));
+    }
+    if speed.is_some_and(|v| !v.is_finite() || v < 0.0) {
+        return Err(AppError::BadRequest("速度不能为负数".to_string()));
+    }
+    if heading.is_some_and(|v| !(0.0..360.0).contains(&v)) {
+        return Err(AppError::BadRequest("方向必须在0到360度之间".to_string()));
+    }
+    Ok(())
+}
+
+/// 验证生日（YYYY-MM-DD），空字符串表示清除
+pub fn validate_birthday(birthday: &str) -> Result<Option<NaiveDate>, AppError> {
+    if birthday.is_empty() {
+        return Ok(None);
+    }
+    let date = NaiveDate::parse_from_str(birthday, "%Y-%m-%d")
+        .map_err(|_| AppError::BadRequest("生日格式应为YYYY-MM-DD".to_string()))?;
+    let min_date = NaiveDate::from_ymd_opt(1900, 1, 1).unwrap_or(NaiveDate::MIN);
+    if date < min_date || date > Utc::now().date_naive() {
+        return Err(AppError::BadRequest("生日超出有效范围".to_string()));
+    }
+    Ok(Some(date))
+}
+
 /// 验证在线状态
 pub fn validate_online_status(status: &str) -> Result<(), AppError> {
     match status {
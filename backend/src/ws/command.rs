@@ -0,0 +1,379 @@
+// WebSocket上行命令处理：校验、持久化并返回ack结果
+use crate::{
+    config::routes::find_ws_command_rate_limit_policy,
+    middleware::auth::AppState,
+    models::{
+        api::{
+            message::{
+                MarkReadRequest, MarkReadResponse, SendDirectMessageRequest,
+                SendDirectMessageResponse, SendGroupMessageRequest, SendGroupMessageResponse,
+            },
+            user::AuthUser,
+        },
+        constants::ws_target_types,
+        websocket::{
+            CommandAck, CommandError, SubscribeCommand, TypingCommand, WebSocketCommandType,
+            WebSocketMessage, WebSocketMessageType,
+        },
+    },
+    utils::response::AppError,
+    validators::{common_validator, message_validator},
+};
+use chrono::Utc;
+use serde::Serialize;
+use serde_json::Value;
+use tracing::{error, info};
+use uuid::Uuid;
+
+// 构造命令执行成功的ack帧
+pub fn ack_frame(request_id: String, result: Value) -> WebSocketMessage {
+    WebSocketMessage {
+        message_type: WebSocketMessageType::Ack,
+        data: Some(serde_json::to_value(CommandAck { request_id, result }).unwrap_or_default()),
+        timestamp: Utc::now(),
+        seq: None,
+    }
+}
+
+// 构造命令执行失败的error帧
+pub fn error_frame(request_id: Option<String>, error: AppError) -> WebSocketMessage {
+    let (error, code) = error.into_parts();
+    WebSocketMessage {
+        message_type: WebSocketMessageType::Error,
+        data: Some(
+            serde_json::to_value(CommandError {
+                request_id,
+                code,
+                error,
+            })
+            .unwrap_or_default(),
+        ),
+        timestamp: Utc::now(),
+        seq: None,
+    }
+}
+
+// 执行上行命令，返回ack帧中的结果数据
+pub async fn handle_command(
+    state: &AppState,
+    current_user: &AuthUser,
+    session_id: Uuid,
+    request_id: &str,
+    command: WebSocketCommandType,
+) -> Result<Value, AppError> {
+    message_validator::validate_request_id(request_id)?;
+
+    // 上行命令不经过HTTP限流中间件，在这里按用户限流
+    if let Some(policy) = find_ws_command_rate_limit_policy(command.name()) {
+        state
+            .rate_limit_service
+            .acquire(&policy, &format!("user:{}", current_user.user_id))
+            .await?;
+    }
+
+    match command {
+        WebSocketCommandType::SendDirectMessage(payload) => {
+            send_direct_message(state, current_user, payload).await
+        }
+        WebSocketCommandType::SendGroupMessage(payload) => {
+            send_group_message(state, current_user, payload).await
+        }
+        WebSocketCommandType::Typing(payload) => {
+            typing(state, current_user, session_id, payload).await
+        }
+        WebSocketCommandType::MarkRead(payload) => mark_read(state, current_user, payload).await,
+        WebSocketCommandType::Subscribe(payload) => {
+            subscribe(state, current_user, session_id, payload).await
+        }
+        // 心跳和断开由连接循环直接处理
+        WebSocketCommandType::Ping | WebSocketCommandType::Disconnect => Ok(Value::Null),
+    }
+}
+
+fn to_result<T: Serialize>(response: T) -> Result<Value, AppError> {
+    serde_json::to_value(response).map_err(|e| {
+        error!("序列化命令结果失败: {:?}", e);
+        AppError::InternalServerError("序列化命令结果失败".to_string())
+    })
+}
+
+// 发送私聊消息
+async fn send_direct_message(
+    state: &AppState,
+    current_user: &AuthUser,
+    payload: SendDirectMessageRequest,
+) -> Result<Value, AppError> {
+    // 验证消息内容
+    message_validator::validate_message_content(&payload.content, &payload.message_type)?;
+
+    // 验证位置参数（如果提供了位置信息）
+    if payload.latitude.abs() > 0.0 || payload.longitude.abs() > 0.0 {
+        common_validator::validate_location_params(payload.latitude, payload.longitude)?;
+    }
+
+    // 验证接收者ID
+    let recipient_id = message_validator::validate_recipient_id(&payload.recipient_id)?;
+
+    let message = state
+        .message_service
+        .send_direct_message(
+            current_user.user_id,
+            recipient_id,
+            &payload.content,
+            &payload.message_type,
+            payload.latitude,
+            payload.longitude,
+        )
+        .await?;
+
+    // 推送给会话双方，位置已按发送者的隐私级别模糊
+    let _ = state
+        .ws_service
+        .send_direct_message(
+            message.message_id,
+            message.conversation_id,
+            current_user.user_id,
+            current_user.nickname.clone(),
+            recipient_id,
+            payload.content,
+            payload.message_type,
+            message.latitude,
+            message.longitude,
+        )
+        .await;
+
+    info!(
+        "【WS】用户 {} 发送私信成功: message_id={}",
+        current_user.user_id, message.message_id
+    );
+
+    to_result(SendDirectMessageResponse {
+        message_id: message.message_id.to_string(),
+        conversation_id: message.conversation_id.to_string(),
+        recipient_id: payload.recipient_id,
+        message_type: message.message_type,
+        sent_at: message.created_at,
+    })
+}
+
+// 发送群组消息
+async fn send_group_message(
+    state: &AppState,
+    current_user: &AuthUser,
+    payload: SendGroupMessageRequest,
+) -> Result<Value, AppError> {
+    // 验证消息内容
+    message_validator::validate_message_content(&payload.content, &payload.message_type)?;
+
+    // 验证位置参数（如果提供了位置信息）
+    if payload.latitude.abs() > 0.0 || payload.longitude.abs() > 0.0 {
+        common_validator::validate_location_params(payload.latitude, payload.longitude)?;
+    }
+
+    // 验证群组ID
+    let group_id = common_validator::validate_uuid(&payload.group_id, "群组ID")?;
+
+    let message = state
+        .message_service
+        .send_group_message(
+            group_id,
+            current_user.user_id,
+            &payload.content,
+            &payload.message_type,
+            payload.latitude,
+            payload.longitude,
+        )
+        .await?;
+
+    // 获取群组名称
+    let group = state
+        .group_service
+        .search_group_by_id(current_user.user_id, group_id)
+        .await?;
+
+    // 推送给群组成员
+    let _ = state
+        .ws_service
+        .send_group_message(
+            &message.message_id.to_string(),
+            group_id,
+            &group.name,
+            current_user.user_id,
+            &current_user.nickname,
+            &payload.content,
+            &payload.message_type,
+        )
+        .await;
+
+    info!(
+        "【WS】用户 {} 发送群组消息成功: message_id={}",
+        current_user.user_id, message.message_id
+    );
+
+    to_result(SendGroupMessageResponse {
+        group_id: payload.group_id,
+        message_id: message.message_id.to_string(),
+        message_type: message.message_type,
+        sent_at: message.created_at,
+    })
+}
+
+// 输入状态
+async fn typing(
+    state: &AppState,
+    current_user: &AuthUser,
+    session_id: Uuid,
+    payload: TypingCommand,
+) -> Result<Value, AppError> {
+    message_validator::validate_target_type(&payload.target_type)?;
+
+    let recipient_ids = if payload.target_type == ws_target_types::CONVERSATION {
+        let conversation_id = common_validator::validate_uuid(&payload.target_id, "会话ID")?;
+        if !state
+            .message_service
+            .check_user_in_conversation(conversation_id, current_user.user_id)
+            .await?
+        {
+            return Err(AppError::Forbidden("您不是该会话的成员".to_string()));
+        }
+        let peer_id = state
+            .message_service
+            .get_other_user_in_conversation(conversation_id, current_user.user_id)
+            .await?;
+        if state
+            .message_service
+            .check_users_blocked(current_user.user_id, peer_id)
+            .await?
+        {
+            return Err(AppError::Forbidden("无法与该用户私信".to_string()));
+        }
+        vec![peer_id]
+    } else {
+        let group_id = common_validator::validate_uuid(&payload.target_id, "群组ID")?;
+        if !state
+            .message_service
+            .check_user_in_group(group_id, current_user.user_id)
+            .await?
+        {
+            return Err(AppError::Forbidden("您不是该群组成员".to_string()));
+        }
+        state
+            .ws_service
+            .get_group_member_ids(group_id)
+            .await?
+            .into_iter()
+            .filter(|user_id| *user_id != current_user.user_id)
+            .collect()
+    };
+
+    state
+        .ws_service
+        .session_manager
+        .set_typing_status(current_user.user_id, session_id, payload.is_typing)
+        .await;
+
+    state
+        .ws_service
+        .send_user_typing(
+            current_user.user_id,
+            &payload.target_type,
+            &payload.target_id,
+            payload.is_typing,
+            &recipient_ids,
+        )
+        .await?;
+
+    Ok(Value::Null)
+}
+
+// 标记消息已读
+async fn mark_read(
+    state: &AppState,
+    current_user: &AuthUser,
+    payload: MarkReadRequest,
+) -> Result<Value, AppError> {
+    // 解析会话ID
+    let conversation_id = common_validator::validate_uuid(&payload.conversation_id, "会话ID")?;
+
+    // 解析消息ID（如果存在）
+    let message_id = match payload.message_id.as_deref() {
+        Some(msg_id) if !msg_id.is_empty() => {
+            Some(common_validator::validate_uuid(msg_id, "消息ID")?)
+        }
+        _ => None,
+    };
+
+    let marked_count = state
+        .message_service
+        .mark_messages_read(conversation_id, current_user.user_id, message_id)
+        .await?;
+
+    // 通知会话另一方，存在屏蔽关系（任一方向）时不发送已读回执
+    let peer_id = state
+        .message_service
+        .get_other_user_in_conversation(conversation_id, current_user.user_id)
+        .await?;
+    if !state
+        .block_service
+        .is_blocked_between(current_user.user_id, peer_id)
+        .await?
+    {
+        let _ = state
+            .ws_service
+            .send_read_receipt(current_user.user_id, peer_id, conversation_id, message_id)
+            .await;
+    }
+
+    to_result(MarkReadResponse {
+        success: true,
+        marked_count: Some(marked_count),
+    })
+}
+
+// 订阅（关注）会话或群组
+async fn subscribe(
+    state: &AppState,
+    current_user: &AuthUser,
+    session_id: Uuid,
+    payload: SubscribeCommand,
+) -> Result<Value, AppError> {
+    message_validator::validate_target_type(&payload.target_type)?;
+
+    let target_id = match payload.target_id.as_deref() {
+        Some(target_id) if !target_id.is_empty() => Some(target_id.to_string()),
+        _ => None,
+    };
+
+    let session_manager = &state.ws_service.session_manager;
+    if payload.target_type == ws_target_types::CONVERSATION {
+        if let Some(target_id) = &target_id {
+            let conversation_id = common_validator::validate_uuid(target_id, "会话ID")?;
+            if !state
+                .message_service
+                .check_user_in_conversation(conversation_id, current_user.user_id)
+                .await?
+            {
+                return Err(AppError::Forbidden("您不是该会话的成员".to_string()));
+            }
+        }
+        session_manager
+            .set_current_conversation(current_user.user_id, session_id, target_id)
+            .await;
+    } else {
+        if let Some(target_id) = &target_id {
+            let group_id = common_validator::validate_uuid(target_id, "群组ID")?;
+            if !state
+                .message_service
+                .check_user_in_group(group_id, current_user.user_id)
+                .await?
+            {
+                return Err(AppError::Forbidden("您不是该群组成员".to_string()));
+            }
+        }
+        session_manager
+            .set_current_group(current_user.user_id, session_id, target_id)
+            .await;
+    }
+
+    Ok(Value::Null)
+}
@@ -0,0 +1,182 @@
+// WebSocket事件总线：在所有后端节点之间分发事件并维护集群在线状态
+use crate::{
+    config::app_config::EventBusKind, models::websocket::WebSocketMessage,
+    utils::response::AppError, ws::redis_event_bus::RedisEventBus,
+};
+use chrono::{DateTime, Utc};
+use futures::future::BoxFuture;
+use redis::Client as RedisClient;
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+use std::fmt::Debug;
+use std::sync::Arc;
+use tokio::sync::{RwLock, broadcast};
+use uuid::Uuid;
+
+// 本地订阅者的事件缓冲容量
+pub const EVENT_BUS_BUFFER_SIZE: usize = 1024;
+
+// 事件的投递目标
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "target", rename_all = "snake_case")]
+pub enum BusTarget {
+    // 所有在线用户
+    Broadcast,
+    // 指定用户
+    Users { user_ids: Vec<i64> },
+    // 指定用户，每个用户携带各自的事件序列号 (用户ID, 序列号)
+    Sequenced { seqs: Vec<(i64, i64)> },
+    // 用户使用指定访问令牌建立的连接
+    Token { user_id: i64, token_id: String },
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BusEnvelope {
+    pub target: BusTarget,
+    pub message: WebSocketMessage,
+}
+
+// 用户在集群内的一个在线连接（设备）
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DeviceSession {
+    pub session_id: Uuid,
+    pub device: Option<String>,
+    pub connected_at: DateTime<Utc>,
+    pub last_active: DateTime<Utc>,
+}
+
+pub trait EventBus: Send + Sync + Debug {
+    // 发布事件，所有节点（包括本节点）的订阅者都会收到
+    fn publish(&self, envelope: BusEnvelope) -> BoxFuture<'_, Result<(), AppError>>;
+
+    // 订阅所有节点发布的事件
+    fn subscribe(&self) -> broadcast::Receiver<BusEnvelope>;
+
+    // 用户在本节点上线，返回是否为集群内首次上线
+    fn presence_connect(&self, user_id: i64) -> BoxFuture<'_, Result<bool, AppError>>;
+
+    // 用户在本节点下线，返回是否已在整个集群下线
+    fn presence_disconnect(&self, user_id: i64) -> BoxFuture<'_, Result<bool, AppError>>;
+
+    // 获取集群内所有在线用户ID
+    fn online_user_ids(&self) -> BoxFuture<'_, Result<Vec<i64>, AppError>>;
+
+    // 记录或更新用户在本节点的连接
+    fn put_device(
+        &self,
+        user_id: i64,
+        device: DeviceSession,
+    ) -> BoxFuture<'_, Result<(), AppError>>;
+
+    // 移除用户在本节点的连接
+    fn remove_device(&self, user_id: i64, session_id: Uuid) -> BoxFuture<'_, Result<(), AppError>>;
+
+    // 获取用户在整个集群的在线连接
+    fn user_devices(&self, user_id: i64) -> BoxFuture<'_, Result<Vec<DeviceSession>, AppError>>;
+}
+
+// 根据配置创建事件总线
+pub fn create_event_bus(
+    kind: EventBusKind,
+    redis_client: Option<Arc<RedisClient>>,
+) -> Result<Arc<dyn EventBus>, String> {
+    match kind {
+        EventBusKind::InProcess => Ok(Arc::new(InProcessEventBus::new())),
+        EventBusKind::Redis => {
+            let client =
+                redis_client.ok_or_else(|| "使用Redis事件总线需要配置REDIS_URL".to_string())?;
+            Ok(RedisEventBus::start(client))
+        }
+    }
+}
+
+// 进程内事件总线，适用于单节点部署
+#[derive(Debug)]
+pub struct InProcessEventBus {
+    tx: broadcast::Sender<BusEnvelope>,
+    online_users: RwLock<HashSet<i64>>,
+    devices: RwLock<HashMap<i64, HashMap<Uuid, DeviceSession>>>,
+}
+
+impl InProcessEventBus {
+    pub fn new() -> Self {
+        let (tx, _) = broadcast::channel(EVENT_BUS_BUFFER_SIZE);
+        Self {
+            tx,
+            online_users: RwLock::new(HashSet::new()),
+            devices: RwLock::new(HashMap::new()),
+        }
+    }
+}
+
+impl Default for InProcessEventBus {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl EventBus for InProcessEventBus {
+    fn publish(&self, envelope: BusEnvelope) -> BoxFuture<'_, Result<(), AppError>> {
+        Box::pin(async move {
+            // 没有订阅者时发送失败，直接忽略
+            let _ = self.tx.send(envelope);
+            Ok(())
+        })
+    }
+
+    fn subscribe(&self) -> broadcast::Receiver<BusEnvelope> {
+        self.tx.subscribe()
+    }
+
+    fn presence_connect(&self, user_id: i64) -> BoxFuture<'_, Result<bool, AppError>> {
+        Box::pin(async move { Ok(self.online_users.write().await.insert(user_id)) })
+    }
+
+    fn presence_disconnect(&self, user_id: i64) -> BoxFuture<'_, Result<bool, AppError>> {
+        Box::pin(async move { Ok(self.online_users.write().await.remove(&user_id)) })
+    }
+
+    fn online_user_ids(&self) -> BoxFuture<'_, Result<Vec<i64>, AppError>> {
+        Box::pin(async move { Ok(self.online_users.read().await.iter().copied().collect()) })
+    }
+
+    fn put_device(
+        &self,
+        user_id: i64,
+        device: DeviceSession,
+    ) -> BoxFuture<'_, Result<(), AppError>> {
+        Box::pin(async move {
+            let mut devices = self.devices.write().await;
+            devices
+                .entry(user_id)
+                .or_default()
+                .insert(device.session_id, device);
+            Ok(())
+        })
+    }
+
+    fn remove_device(&self, user_id: i64, session_id: Uuid) -> BoxFuture<'_, Result<(), AppError>> {
+        Box::pin(async move {
+            let mut devices = self.devices.write().await;
+            if let Some(user_devices) = devices.get_mut(&user_id) {
+                user_devices.remove(&session_id);
+                if user_devices.is_empty() {
+                    devices.remove(&user_id);
+                }
+            }
+            Ok(())
+        })
+    }
+
+    fn user_devices(&self, user_id: i64) -> BoxFuture<'_, Result<Vec<DeviceSession>, AppError>> {
+        Box::pin(async move {
+            let devices = self.devices.read().await;
+            let mut user_devices: Vec<DeviceSession> = devices
+                .get(&user_id)
+                .map(|user_devices| user_devices.values().cloned().collect())
+                .unwrap_or_default();
+            user_devices.sort_by_key(|device| device.connected_at);
+            Ok(user_devices)
+        })
+    }
+}
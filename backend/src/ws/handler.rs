@@ -1,4 +1,7 @@
-use crate::{middleware::auth::AppState, models::websocket::WebSocketConnectMessage};
+use crate::{
+    middleware::auth::AppState,
+    models::{api::user::AuthUser, websocket::WebSocketConnectMessage},
+};
 use axum::{
     extract::{
         State, WebSocketUpgrade,
@@ -29,6 +32,7 @@ async fn handle_socket(socket: WebSocket, state: Arc<AppState>) {
                 message_type: WebSocketMessageType::Error,
                 data: Some(serde_json::json!({"error": "需要文本消息进行认证"})),
                 timestamp: Utc::now(),
+                seq: None,
             }).unwrap();
             let _ = sender.send(Message::Text(error_msg.into())).await;
             return;
@@ -52,6 +56,7 @@ async fn handle_socket(socket: WebSocket, state: Arc<AppState>) {
                 message_type: WebSocketMessageType::Error,
                 data: Some(serde_json::json!({"error": "无效的连接消息格式"})),
                 timestamp: Utc::now(),
+                seq: None,
             }).unwrap();
             let _ = sender.send(Message::Text(error_msg.into())).await;
             return;
@@ -59,14 +64,15 @@ async fn handle_socket(socket: WebSocket, state: Arc<AppState>) {
     };
 
     // 提取并验证用户ID
-    let user_id = match state.ws_service.verify_token(&connect_msg.token) {
-        Ok(id) => id,
+    let claims = match state.ws_service.verify_token(&connect_msg.token).await {
+        Ok(claims) => claims,
         Err(e) => {
             error!("Token验证失败: {}", e);
             let error_msg = serde_json::to_string(&WebSocketMessage {
                 message_type: WebSocketMessageType::Error,
                 data: Some(serde_json::json!({"error": format!("认证失败: {}", e)})),
                 timestamp: Utc::now(),
+                seq: None,
             }).unwrap();
             let _ = sender.send(Message::Text(error_msg.into())).await;
             return;
@@ -74,7 +80,7 @@ async fn handle_socket(socket: WebSocket, state: Arc<AppState>) {
     };
 
     // 获取用户详细信息
-    let user = match state.user_service.get_auth_info(user_id).await {
+    let user = match state.user_service.get_auth_info(claims.sub).await {
         Ok(user) => user,
         Err(e) => {
             error!("获取用户信息失败: {:?}", e);
@@ -82,12 +88,51 @@ async fn handle_socket(socket: WebSocket, state: Arc<AppState>) {
                 message_type: WebSocketMessageType::Error,
                 data: Some(serde_json::json!({"error": "获取用户信息失败"})),
                 timestamp: Utc::now(),
+                seq: None,
             }).unwrap();
             let _ = sender.send(Message::Text(error_msg.into())).await;
             return;
         }
     };
 
-    // 认证通过，进入主消息循环
-    state.ws_service.handle_ws_stream(sender, receiver, user.user_id, user.nickname).await;
+    // 修改或重置密码前签发的令牌不再有效
+    if user.is_token_outdated(claims.iat) {
+        let error_msg = serde_json::to_string(&WebSocketMessage {
+            message_type: WebSocketMessageType::Error,
+            data: Some(serde_json::json!({"error": "认证失败: 令牌已失效，请重新登录"})),
+            timestamp: Utc::now(),
+            seq: None,
+        }).unwrap();
+        let _ = sender.send(Message::Text(error_msg.into())).await;
+        return;
+    }
+
+    if user.banned_at.is_some() {
+        let error_msg = serde_json::to_string(&WebSocketMessage {
+            message_type: WebSocketMessageType::Error,
+            data: Some(serde_json::json!({"error": "认证失败: 账号已被封禁"})),
+            timestamp: Utc::now(),
+            seq: None,
+        }).unwrap();
+        let _ = sender.send(Message::Text(error_msg.into())).await;
+        return;
+    }
+
+    // 认证通过，补发离线事件后进入主消息循环
+    let current_user = AuthUser {
+        user_id: user.user_id,
+        nickname: user.nickname,
+        platform_role: user.platform_role,
+    };
+    let ws_service = state.ws_service.clone();
+    ws_service
+        .handle_ws_stream(
+            sender,
+            receiver,
+            state,
+            current_user,
+            claims.jti,
+            connect_msg,
+        )
+        .await;
 }
@@ -1,3 +1,7 @@
+pub mod command;
+pub mod event_bus;
 pub mod handler;
 pub mod session;
 pub mod message_queue;
+pub mod router;
+pub mod redis_event_bus;
@@ -0,0 +1,305 @@
+// 基于Redis pub/sub的事件总线，适用于多节点部署
+use crate::{
+    utils::{redis::RedisExecutor, response::AppError},
+    ws::event_bus::{BusEnvelope, DeviceSession, EVENT_BUS_BUFFER_SIZE, EventBus},
+};
+use futures::future::BoxFuture;
+use redis::aio::ConnectionManager;
+use redis::{AsyncCommands, Client as RedisClient, RedisResult};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::broadcast;
+use tracing::{error, info, warn};
+use uuid::Uuid;
+
+// 事件发布频道
+const EVENT_CHANNEL: &str = "geotalk:ws:events";
+// 所有节点ID的集合
+const PRESENCE_NODES_KEY: &str = "geotalk:presence:nodes";
+// 节点在线用户集合的过期时间（秒），节点异常退出后其在线用户自动失效
+const PRESENCE_TTL_SECONDS: i64 = 60;
+// 用户在线连接的哈希表前缀，字段为会话ID，值为连接信息及其所在节点
+const PRESENCE_DEVICES_KEY_PREFIX: &str = "geotalk:presence:devices:";
+// 节点心跳间隔
+const HEARTBEAT_INTERVAL: Duration = Duration::from_secs(20);
+// 订阅连接断开后的重连间隔
+const RECONNECT_DELAY: Duration = Duration::from_secs(3);
+
+// Redis中保存的连接信息
+#[derive(Debug, Serialize, Deserialize)]
+struct StoredDevice {
+    node_id: String,
+    #[serde(flatten)]
+    device: DeviceSession,
+}
+
+pub struct RedisEventBus {
+    // 发布/查询使用的命令执行器
+    redis: RedisExecutor,
+    node_id: String,
+    tx: broadcast::Sender<BusEnvelope>,
+}
+
+impl std::fmt::Debug for RedisEventBus {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("RedisEventBus")
+            .field("node_id", &self.node_id)
+            .finish()
+    }
+}
+
+impl RedisEventBus {
+    // 创建事件总线，并启动订阅线程和节点心跳
+    pub fn start(client: Arc<RedisClient>) -> Arc<Self> {
+        let (tx, _) = broadcast::channel(EVENT_BUS_BUFFER_SIZE);
+        let bus = Arc::new(Self {
+            redis: RedisExecutor::new(client),
+            node_id: Uuid::new_v4().to_string(),
+            tx,
+        });
+
+        info!("启动Redis事件总线: node_id={}", bus.node_id);
+        bus.spawn_subscriber();
+        bus.spawn_heartbeat();
+        bus
+    }
+
+    fn presence_key(node_id: &str) -> String {
+        format!("geotalk:presence:{}", node_id)
+    }
+
+    fn devices_key(user_id: i64) -> String {
+        format!("{}{}", PRESENCE_DEVICES_KEY_PREFIX, user_id)
+    }
+
+    // 订阅事件频道，转发给本地订阅者
+    fn spawn_subscriber(&self) {
+        let client = self.redis.client().clone();
+        let tx = self.tx.clone();
+
+        std::thread::spawn(move || {
+            loop {
+                if let Err(e) = Self::subscribe_loop(&client, &tx) {
+                    error!("Redis事件订阅中断: {:?}", e);
+                }
+                std::thread::sleep(RECONNECT_DELAY);
+            }
+        });
+    }
+
+    fn subscribe_loop(
+        client: &RedisClient,
+        tx: &broadcast::Sender<BusEnvelope>,
+    ) -> RedisResult<()> {
+        let mut connection = client.get_connection()?;
+        let mut pubsub = connection.as_pubsub();
+        pubsub.subscribe(EVENT_CHANNEL)?;
+        info!("已订阅Redis事件频道: {}", EVENT_CHANNEL);
+
+        loop {
+            let message = pubsub.get_message()?;
+            let payload: String = message.get_payload()?;
+            match serde_json::from_str::<BusEnvelope>(&payload) {
+                Ok(envelope) => {
+                    let _ = tx.send(envelope);
+                }
+                Err(e) => warn!("无法解析Redis事件: {:?}", e),
+            }
+        }
+    }
+
+    // 定期续期本节点的在线用户集合，并清理已失效的节点
+    fn spawn_heartbeat(self: &Arc<Self>) {
+        let bus = self.clone();
+
+        tokio::spawn(async move {
+            let mut interval = tokio::time::interval(HEARTBEAT_INTERVAL);
+            loop {
+                interval.tick().await;
+                let node_id = bus.node_id.clone();
+                let result = bus
+                    .redis
+                    .run(|mut conn| async move {
+                        conn.sadd::<_, _, ()>(PRESENCE_NODES_KEY, &node_id).await?;
+                        let key = Self::presence_key(&node_id);
+                        conn.expire::<_, ()>(&key, PRESENCE_TTL_SECONDS).await?;
+
+                        let nodes: Vec<String> = conn.smembers(PRESENCE_NODES_KEY).await?;
+                        for node in nodes.iter().filter(|node| **node != node_id) {
+                            let alive: bool = conn.exists(Self::presence_key(node)).await?;
+                            if !alive {
+                                conn.srem::<_, _, ()>(PRESENCE_NODES_KEY, node).await?;
+                            }
+                        }
+                        Ok(())
+                    })
+                    .await;
+                if let Err(e) = result {
+                    error!("Redis事件总线心跳失败: {:?}", e);
+                }
+            }
+        });
+    }
+
+    // 用户是否在其他节点在线
+    async fn is_online_elsewhere(
+        conn: &mut ConnectionManager,
+        node_id: &str,
+        user_id: i64,
+    ) -> RedisResult<bool> {
+        let nodes: Vec<String> = conn.smembers(PRESENCE_NODES_KEY).await?;
+        for node in nodes.iter().filter(|node| *node != node_id) {
+            let online: bool = conn.sismember(Self::presence_key(node), user_id).await?;
+            if online {
+                return Ok(true);
+            }
+        }
+        Ok(false)
+    }
+}
+
+impl EventBus for RedisEventBus {
+    fn publish(&self, envelope: BusEnvelope) -> BoxFuture<'_, Result<(), AppError>> {
+        Box::pin(async move {
+            let payload = serde_json::to_string(&envelope).map_err(|e| {
+                AppError::InternalServerError(format!("序列化总线事件失败: {:?}", e))
+            })?;
+            self.redis
+                .run(|mut conn| async move { conn.publish::<_, _, ()>(EVENT_CHANNEL, payload).await })
+                .await
+        })
+    }
+
+    fn subscribe(&self) -> broadcast::Receiver<BusEnvelope> {
+        self.tx.subscribe()
+    }
+
+    fn presence_connect(&self, user_id: i64) -> BoxFuture<'_, Result<bool, AppError>> {
+        Box::pin(async move {
+            let node_id = self.node_id.clone();
+            self.redis
+                .run(|mut conn| async move {
+                    let key = Self::presence_key(&node_id);
+                    conn.sadd::<_, _, ()>(PRESENCE_NODES_KEY, &node_id).await?;
+                    let added: i64 = conn.sadd(&key, user_id).await?;
+                    conn.expire::<_, ()>(&key, PRESENCE_TTL_SECONDS).await?;
+                    Ok(added > 0
+                        && !Self::is_online_elsewhere(&mut conn, &node_id, user_id).await?)
+                })
+                .await
+        })
+    }
+
+    fn presence_disconnect(&self, user_id: i64) -> BoxFuture<'_, Result<bool, AppError>> {
+        Box::pin(async move {
+            let node_id = self.node_id.clone();
+            self.redis
+                .run(|mut conn| async move {
+                    let removed: i64 = conn.srem(Self::presence_key(&node_id), user_id).await?;
+                    Ok(removed > 0
+                        && !Self::is_online_elsewhere(&mut conn, &node_id, user_id).await?)
+                })
+                .await
+        })
+    }
+
+    fn online_user_ids(&self) -> BoxFuture<'_, Result<Vec<i64>, AppError>> {
+        Box::pin(async move {
+            self.redis
+                .run(|mut conn| async move {
+                    let nodes: Vec<String> = conn.smembers(PRESENCE_NODES_KEY).await?;
+                    if nodes.is_empty() {
+                        return Ok(Vec::new());
+                    }
+                    let keys: Vec<String> =
+                        nodes.iter().map(|node| Self::presence_key(node)).collect();
+                    conn.sunion(keys).await
+                })
+                .await
+        })
+    }
+
+    fn put_device(
+        &self,
+        user_id: i64,
+        device: DeviceSession,
+    ) -> BoxFuture<'_, Result<(), AppError>> {
+        Box::pin(async move {
+            let field = device.session_id.to_string();
+            let stored = StoredDevice {
+                node_id: self.node_id.clone(),
+                device,
+            };
+            let value = serde_json::to_string(&stored).map_err(|e| {
+                AppError::InternalServerError(format!("序列化连接信息失败: {:?}", e))
+            })?;
+            self.redis
+                .run(|mut conn| async move {
+                    conn.hset::<_, _, _, ()>(Self::devices_key(user_id), field, value)
+                        .await
+                })
+                .await
+        })
+    }
+
+    fn remove_device(&self, user_id: i64, session_id: Uuid) -> BoxFuture<'_, Result<(), AppError>> {
+        Box::pin(async move {
+            let field = session_id.to_string();
+            self.redis
+                .run(|mut conn| async move {
+                    conn.hdel::<_, _, ()>(Self::devices_key(user_id), field)
+                        .await
+                })
+                .await
+        })
+    }
+
+    // 节点异常退出后其连接不会被移除，只返回用户仍在其所在节点在线的连接，并清理其余记录
+    fn user_devices(&self, user_id: i64) -> BoxFuture<'_, Result<Vec<DeviceSession>, AppError>> {
+        Box::pin(async move {
+            self.redis
+                .run(|mut conn| async move {
+                    let key = Self::devices_key(user_id);
+                    let entries: HashMap<String, String> = conn.hgetall(&key).await?;
+
+                    let mut online_nodes: HashMap<String, bool> = HashMap::new();
+                    let mut devices = Vec::new();
+                    let mut stale = Vec::new();
+                    for (field, value) in entries {
+                        let Ok(stored) = serde_json::from_str::<StoredDevice>(&value) else {
+                            warn!(
+                                "无法解析连接信息: user_id={}, session_id={}",
+                                user_id, field
+                            );
+                            stale.push(field);
+                            continue;
+                        };
+                        let online = match online_nodes.get(&stored.node_id) {
+                            Some(online) => *online,
+                            None => {
+                                let online: bool = conn
+                                    .sismember(Self::presence_key(&stored.node_id), user_id)
+                                    .await?;
+                                online_nodes.insert(stored.node_id.clone(), online);
+                                online
+                            }
+                        };
+                        if online {
+                            devices.push(stored.device);
+                        } else {
+                            stale.push(field);
+                        }
+                    }
+
+                    if !stale.is_empty() {
+                        conn.hdel::<_, _, ()>(&key, stale).await?;
+                    }
+                    devices.sort_by_key(|device| device.connected_at);
+                    Ok(devices)
+                })
+                .await
+        })
+    }
+}
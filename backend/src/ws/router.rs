@@ -0,0 +1,196 @@
+use crate::models::websocket::WebSocketMessage;
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicI64, Ordering};
+use tokio::sync::{RwLock, mpsc, mpsc::error::TrySendError};
+use tracing::warn;
+use uuid::Uuid;
+
+// 每个连接的待发送消息缓冲容量
+const CONNECTION_BUFFER_SIZE: usize = 100;
+
+// 没有丢弃事件时dropped_seq的取值
+const NO_DROPPED_SEQ: i64 = i64::MAX;
+
+pub type ConnectionSender = mpsc::Sender<WebSocketMessage>;
+
+// 单个连接的发送端及其认证使用的访问令牌ID
+#[derive(Debug, Clone)]
+struct RoutedConnection {
+    token_id: String,
+    tx: ConnectionSender,
+    // 缓冲区已满时被丢弃的最小事件序列号
+    dropped_seq: Arc<AtomicI64>,
+}
+
+// 连接的消息接收端。缓冲区已满时新消息被丢弃，带序列号的事件被丢弃后
+// 连接需要通知客户端从丢弃处重新同步
+#[derive(Debug)]
+pub struct ConnectionReceiver {
+    rx: mpsc::Receiver<WebSocketMessage>,
+    dropped_seq: Arc<AtomicI64>,
+}
+
+impl ConnectionReceiver {
+    pub async fn recv(&mut self) -> Option<WebSocketMessage> {
+        self.rx.recv().await
+    }
+
+    // 取出并清除被丢弃的最小事件序列号
+    pub fn take_dropped_seq(&self) -> Option<i64> {
+        let seq = self.dropped_seq.swap(NO_DROPPED_SEQ, Ordering::AcqRel);
+        (seq != NO_DROPPED_SEQ).then_some(seq)
+    }
+}
+
+// 按用户ID路由WebSocket消息，投递给目标用户的所有连接（设备）
+#[derive(Debug, Clone, Default)]
+pub struct MessageRouter {
+    connections: Arc<RwLock<HashMap<i64, HashMap<Uuid, RoutedConnection>>>>,
+}
+
+impl MessageRouter {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    // 注册用户连接，返回该连接的消息接收端
+    pub async fn register(
+        &self,
+        user_id: i64,
+        session_id: Uuid,
+        token_id: String,
+    ) -> ConnectionReceiver {
+        let (tx, rx) = mpsc::channel(CONNECTION_BUFFER_SIZE);
+        let dropped_seq = Arc::new(AtomicI64::new(NO_DROPPED_SEQ));
+        let mut connections = self.connections.write().await;
+        connections.entry(user_id).or_default().insert(
+            session_id,
+            RoutedConnection {
+                token_id,
+                tx,
+                dropped_seq: dropped_seq.clone(),
+            },
+        );
+        ConnectionReceiver { rx, dropped_seq }
+    }
+
+    // 注销用户的指定连接，不影响该用户的其他设备
+    pub async fn unregister(&self, user_id: i64, session_id: Uuid) {
+        let mut connections = self.connections.write().await;
+        if let Some(user_connections) = connections.get_mut(&user_id) {
+            user_connections.remove(&session_id);
+            if user_connections.is_empty() {
+                connections.remove(&user_id);
+            }
+        }
+    }
+
+    // 发送消息给多个用户，返回成功投递的连接数
+    pub async fn send_to_users(&self, user_ids: &[i64], message: WebSocketMessage) -> usize {
+        let connections = self.connections.read().await;
+        let mut delivered = 0;
+        for user_id in user_ids {
+            if let Some(user_connections) = connections.get(user_id) {
+                for connection in user_connections.values() {
+                    if Self::deliver(*user_id, connection, message.clone()) {
+                        delivered += 1;
+                    }
+                }
+            }
+        }
+        delivered
+    }
+
+    // 发送消息给用户使用指定访问令牌建立的连接
+    pub async fn send_to_token(
+        &self,
+        user_id: i64,
+        token_id: &str,
+        message: WebSocketMessage,
+    ) -> usize {
+        let connections = self.connections.read().await;
+        let mut delivered = 0;
+        if let Some(user_connections) = connections.get(&user_id) {
+            for connection in user_connections.values() {
+                if connection.token_id == token_id
+                    && Self::deliver(user_id, connection, message.clone())
+                {
+                    delivered += 1;
+                }
+            }
+        }
+        delivered
+    }
+
+    // 发送消息给所有在线用户
+    pub async fn broadcast(&self, message: WebSocketMessage) -> usize {
+        let connections = self.connections.read().await;
+        let mut delivered = 0;
+        for (user_id, user_connections) in connections.iter() {
+            for connection in user_connections.values() {
+                if Self::deliver(*user_id, connection, message.clone()) {
+                    delivered += 1;
+                }
+            }
+        }
+        delivered
+    }
+
+    fn deliver(user_id: i64, connection: &RoutedConnection, message: WebSocketMessage) -> bool {
+        match connection.tx.try_send(message) {
+            Ok(_) => true,
+            Err(TrySendError::Full(message)) => {
+                warn!(
+                    "用户 {} 的连接缓冲区已满，丢弃消息: seq={:?}",
+                    user_id, message.seq
+                );
+                if let Some(seq) = message.seq {
+                    connection.dropped_seq.fetch_min(seq, Ordering::AcqRel);
+                }
+                false
+            }
+            Err(e) => {
+                warn!("投递消息给用户 {} 失败: {:?}", user_id, e);
+                false
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::websocket::WebSocketMessageType;
+    use chrono::Utc;
+
+    fn event(seq: Option<i64>) -> WebSocketMessage {
+        WebSocketMessage {
+            message_type: WebSocketMessageType::DirectMessage,
+            data: None,
+            timestamp: Utc::now(),
+            seq,
+        }
+    }
+
+    #[tokio::test]
+    async fn records_lowest_dropped_seq_when_buffer_is_full() {
+        let router = MessageRouter::new();
+        let mut rx = router.register(1, Uuid::new_v4(), "jti".to_string()).await;
+
+        for seq in 1..=CONNECTION_BUFFER_SIZE as i64 {
+            assert_eq!(router.send_to_users(&[1], event(Some(seq))).await, 1);
+        }
+        // 没有序列号的消息被丢弃时不需要重新同步
+        assert_eq!(router.send_to_users(&[1], event(None)).await, 0);
+        assert_eq!(rx.take_dropped_seq(), None);
+
+        let next = CONNECTION_BUFFER_SIZE as i64 + 1;
+        assert_eq!(router.send_to_users(&[1], event(Some(next))).await, 0);
+        assert_eq!(router.send_to_users(&[1], event(Some(next + 1))).await, 0);
+        assert_eq!(rx.take_dropped_seq(), Some(next));
+        assert_eq!(rx.take_dropped_seq(), None);
+
+        assert_eq!(rx.recv().await.and_then(|message| message.seq), Some(1));
+    }
+}
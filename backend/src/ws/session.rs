@@ -1,22 +1,44 @@
-use chrono::{DateTime, Utc};
+use crate::ws::event_bus::DeviceSession;
+use chrono::{DateTime, Duration, Utc};
 use std::collections::HashMap;
 use std::sync::Arc;
 use tokio::sync::RwLock;
+use uuid::Uuid;
 
+// 连接的最后活跃时间同步到事件总线的最短间隔（秒）
+const DEVICE_SYNC_INTERVAL_SECS: i64 = 60;
+
+// 单个WebSocket连接（设备）的会话
 #[derive(Debug, Clone)]
 pub struct Session {
+    pub session_id: Uuid,
     pub user_id: i64,
     pub nickname: String,
+    pub device: Option<String>,
     pub connected_at: DateTime<Utc>,
     pub last_active: DateTime<Utc>,
     pub is_typing: bool,
     pub current_conversation: Option<String>,
     pub current_group: Option<String>,
+    // 最近一次把连接信息同步到事件总线的时间
+    pub device_synced_at: DateTime<Utc>,
+}
+
+impl Session {
+    pub fn device_session(&self) -> DeviceSession {
+        DeviceSession {
+            session_id: self.session_id,
+            device: self.device.clone(),
+            connected_at: self.connected_at,
+            last_active: self.last_active,
+        }
+    }
 }
 
+// 按用户管理会话，同一用户可以有多个设备同时在线
 #[derive(Debug, Clone)]
 pub struct SessionManager {
-    sessions: Arc<RwLock<HashMap<i64, Session>>>,
+    sessions: Arc<RwLock<HashMap<i64, Vec<Session>>>>,
 }
 
 impl SessionManager {
@@ -26,63 +48,132 @@ impl SessionManager {
         }
     }
 
-    pub async fn add_session(&self, user_id: i64, nickname: String) {
-        let mut sessions = self.sessions.write().await;
-        sessions.insert(
+    // 添加会话，返回新会话以及是否为该用户的第一个连接
+    pub async fn add_session(
+        &self,
+        user_id: i64,
+        nickname: String,
+        device: Option<String>,
+    ) -> (Session, bool) {
+        let now = Utc::now();
+        let session = Session {
+            session_id: Uuid::new_v4(),
             user_id,
-            Session {
-                user_id,
-                nickname,
-                connected_at: Utc::now(),
-                last_active: Utc::now(),
-                is_typing: false,
-                current_conversation: None,
-                current_group: None,
-            },
-        );
+            nickname,
+            device,
+            connected_at: now,
+            last_active: now,
+            is_typing: false,
+            current_conversation: None,
+            current_group: None,
+            device_synced_at: now,
+        };
+        let mut sessions = self.sessions.write().await;
+        let user_sessions = sessions.entry(user_id).or_default();
+        user_sessions.push(session.clone());
+        (session, user_sessions.len() == 1)
     }
 
-    pub async fn remove_session(&self, user_id: i64) {
+    // 移除会话，返回该用户是否已没有其他连接
+    pub async fn remove_session(&self, user_id: i64, session_id: Uuid) -> bool {
         let mut sessions = self.sessions.write().await;
-        sessions.remove(&user_id);
+        let Some(user_sessions) = sessions.get_mut(&user_id) else {
+            return false;
+        };
+        let before = user_sessions.len();
+        user_sessions.retain(|session| session.session_id != session_id);
+        let removed = user_sessions.len() < before;
+        let is_last = user_sessions.is_empty();
+        if is_last {
+            sessions.remove(&user_id);
+        }
+        removed && is_last
     }
 
-    pub async fn update_session_activity(&self, user_id: i64) {
+    // 修改指定会话
+    async fn update_session<F>(&self, user_id: i64, session_id: Uuid, f: F)
+    where
+        F: FnOnce(&mut Session),
+    {
         let mut sessions = self.sessions.write().await;
-        if let Some(session) = sessions.get_mut(&user_id) {
-            session.last_active = Utc::now();
+        if let Some(session) = sessions.get_mut(&user_id).and_then(|user_sessions| {
+            user_sessions
+                .iter_mut()
+                .find(|session| session.session_id == session_id)
+        }) {
+            f(session);
         }
     }
 
-    pub async fn set_typing_status(&self, user_id: i64, is_typing: bool) {
-        let mut sessions = self.sessions.write().await;
-        if let Some(session) = sessions.get_mut(&user_id) {
+    // 更新最后活跃时间，距上次同步超过间隔时返回需要同步到事件总线的连接信息
+    pub async fn update_session_activity(
+        &self,
+        user_id: i64,
+        session_id: Uuid,
+    ) -> Option<DeviceSession> {
+        let mut device = None;
+        self.update_session(user_id, session_id, |session| {
+            let now = Utc::now();
+            session.last_active = now;
+            if now - session.device_synced_at >= Duration::seconds(DEVICE_SYNC_INTERVAL_SECS) {
+                session.device_synced_at = now;
+                device = Some(session.device_session());
+            }
+        })
+        .await;
+        device
+    }
+
+    pub async fn set_typing_status(&self, user_id: i64, session_id: Uuid, is_typing: bool) {
+        self.update_session(user_id, session_id, |session| {
             session.is_typing = is_typing;
-        }
+        })
+        .await;
     }
 
-    pub async fn set_current_conversation(&self, user_id: i64, conversation_id: Option<String>) {
-        let mut sessions = self.sessions.write().await;
-        if let Some(session) = sessions.get_mut(&user_id) {
+    pub async fn set_current_conversation(
+        &self,
+        user_id: i64,
+        session_id: Uuid,
+        conversation_id: Option<String>,
+    ) {
+        self.update_session(user_id, session_id, |session| {
             session.current_conversation = conversation_id;
-        }
+        })
+        .await;
     }
 
-    pub async fn set_current_group(&self, user_id: i64, group_id: Option<String>) {
-        let mut sessions = self.sessions.write().await;
-        if let Some(session) = sessions.get_mut(&user_id) {
+    pub async fn set_current_group(
+        &self,
+        user_id: i64,
+        session_id: Uuid,
+        group_id: Option<String>,
+    ) {
+        self.update_session(user_id, session_id, |session| {
             session.current_group = group_id;
+        })
+        .await;
+    }
+
+    // 更新用户所有会话中缓存的昵称
+    pub async fn set_nickname(&self, user_id: i64, nickname: &str) {
+        let mut sessions = self.sessions.write().await;
+        if let Some(user_sessions) = sessions.get_mut(&user_id) {
+            for session in user_sessions.iter_mut() {
+                session.nickname = nickname.to_string();
+            }
         }
     }
 
-    pub async fn get_session(&self, user_id: i64) -> Option<Session> {
+    // 获取用户的所有在线会话（设备）
+    pub async fn get_user_sessions(&self, user_id: i64) -> Vec<Session> {
         let sessions = self.sessions.read().await;
-        sessions.get(&user_id).cloned()
+        sessions.get(&user_id).cloned().unwrap_or_default()
     }
 
     pub async fn get_all_sessions(&self) -> Vec<Session> {
         let sessions = self.sessions.read().await;
-        sessions.values().cloned().collect()
+        sessions.values().flatten().cloned().collect()
     }
 
     pub async fn get_active_sessions(&self, timeout_seconds: i64) -> Vec<Session> {
@@ -90,6 +181,7 @@ impl SessionManager {
         let now = Utc::now();
         sessions
             .values()
+            .flatten()
             .filter(|session| (now - session.last_active).num_seconds() < timeout_seconds)
             .cloned()
             .collect()